@@ -1,13 +1,23 @@
-use std::marker::PhantomData;
-use std::{collections::BTreeMap, fmt};
-use std::hash::Hash;
-
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::hash::Hash;
+use core::marker::PhantomData;
+
+#[cfg(feature = "std")]
 use combine::easy::{Error, Info};
+#[cfg(feature = "std")]
 use combine::{choice, many, many1, optional, position, StdParseResult};
+#[cfg(feature = "std")]
 use combine::{parser, Parser};
 
-use crate::helpers::{_blockstring, _string, ident, kind, name, punct};
+#[cfg(feature = "std")]
+use crate::helpers::{ident, kind, name, punct};
 use crate::position::Pos;
+#[cfg(feature = "std")]
 use crate::tokenizer::{Kind as T, Token, TokenStream};
 
 /// Text abstracts over types that hold a string value.
@@ -16,7 +26,7 @@ pub trait Text<'a>:
 'a
 + From<&'a str>
 + AsRef<str>
-+ std::borrow::Borrow<str>
++ core::borrow::Borrow<str>
 + PartialEq
 + Eq
 + Hash
@@ -30,36 +40,142 @@ impl<'a> Text<'a> for &'a str {}
 
 impl<'a> Text<'a> for String {}
 
-impl<'a> Text<'a> for std::borrow::Cow<'a, str> {}
+impl<'a> Text<'a> for Cow<'a, str> {}
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Directive<'a, T: Text<'a>> {
     pub position: Pos,
     pub name: T,
     pub arguments: Vec<(T, Value<'a, T>)>,
 }
 
+impl<'a, T: Text<'a>> Directive<'a, T> {
+    /// Returns the value of the argument `name`, if it was given
+    pub fn argument(&self, name: &str) -> Option<&Value<'a, T>> {
+        self.arguments
+            .iter()
+            .find(|(arg, _)| arg.as_ref() == name)
+            .map(|(_, value)| value)
+    }
+
+    /// Returns the argument `name` if it's a string
+    pub fn arg_str(&self, name: &str) -> Option<&str> {
+        self.argument(name)?.as_string()
+    }
+
+    /// Returns the argument `name` if it's a boolean
+    pub fn arg_bool(&self, name: &str) -> Option<bool> {
+        self.argument(name)?.as_bool()
+    }
+
+    /// Returns the argument `name` if it's an integer
+    pub fn arg_int(&self, name: &str) -> Option<i64> {
+        self.argument(name)?.as_int()?.as_i64()
+    }
+
+    /// Returns the argument `name` if it's a list
+    pub fn arg_list(&self, name: &str) -> Option<&[Value<'a, T>]> {
+        self.argument(name)?.as_list()
+    }
+}
+
+/// Compares AST nodes by structure, ignoring their source positions
+///
+/// With `loose` set, schema nodes also ignore descriptions and the order
+/// root operations are listed in, see
+/// [`schema::Document::eq_ignoring_descriptions`](crate::schema::Document::eq_ignoring_descriptions).
+pub(crate) trait StructuralEq {
+    fn structural_eq(&self, other: &Self, loose: bool) -> bool;
+}
+
+impl<N: StructuralEq> StructuralEq for [N] {
+    fn structural_eq(&self, other: &Self, loose: bool) -> bool {
+        self.len() == other.len()
+            && self.iter().zip(other).all(|(a, b)| a.structural_eq(b, loose))
+    }
+}
+
+impl<N: StructuralEq> StructuralEq for Vec<N> {
+    fn structural_eq(&self, other: &Self, loose: bool) -> bool {
+        self[..].structural_eq(&other[..], loose)
+    }
+}
+
+impl<'a, T: Text<'a>> StructuralEq for Directive<'a, T> {
+    fn structural_eq(&self, other: &Self, _loose: bool) -> bool {
+        self.name == other.name && self.arguments == other.arguments
+    }
+}
+
+/// A `#` comment, as returned by
+/// [`parse_query_with_comments`](crate::query::parse_query_with_comments)
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Comment {
+    /// Position of the `#` character
+    pub position: Pos,
+    /// Text following the `#` up to the end of the line
+    pub text: String,
+}
+
 /// This represents integer number
 ///
 /// But since there is no definition on limit of number in spec
 /// (only in implemetation), we do a trick similar to the one
 /// in `serde_json`: encapsulate value in new-type, allowing type
 /// to be extended later.
+///
+/// Numbers which fit an `i64` are stored as one. Larger literals, such as
+/// 64-bit unsigned ids of custom scalars, keep the digits they were written
+/// with, so they are formatted exactly as parsed. [`Number::as_i64`] returns
+/// `None` for them, and `Display` writes the digits of any number.
 #[derive(Debug, Clone, PartialEq)]
-// we use i64 as a reference implementation: graphql-js thinks even 32bit
-// integers is enough. We might consider lift this limit later though
-pub struct Number(pub(crate) i64);
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Number(pub(crate) Int);
 
+/// Value of a [`Number`]
+///
+/// Big numbers are only used for values which don't fit an `i64`, and
+/// integer literals have no leading zeros, so each number has a single
+/// representation.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(untagged)
+)]
+pub(crate) enum Int {
+    I64(i64),
+    // only the parser and `Value::from_json` create big numbers
+    #[cfg_attr(not(feature = "std"), allow(dead_code))]
+    Big(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Value<'a, T: Text<'a>> {
     Variable(T, PhantomData<&'a ()>),
     Int(Number),
     Float(f64),
-    String(T),
+    /// The decoded string, with escapes resolved and block strings dedented
+    ///
+    /// Decoding can't borrow from the source, so unlike names this is
+    /// always an owned `String`. Before 0.5 this held the raw `T` slice
+    /// between the quotes, escapes and all.
+    String(String),
     Boolean(bool),
     Null,
     Enum(T),
     List(Vec<Value<'a, T>>),
+    /// Fields are kept sorted by name, so objects written with fields in a
+    /// different order compare (and format) the same
+    ///
+    /// This is why there is no `normalize` method sorting object fields:
+    /// every `Value`, nested ones included, is always in that form, and so
+    /// is its formatted text, which
+    /// [`query::Document::content_hash`](crate::query::Document::content_hash)
+    /// hashes.
     Object(BTreeMap<T, Value<'a, T>>),
 }
 
@@ -69,7 +185,7 @@ impl<'a, T: Text<'a>> Value<'a, T> {
             Self::Variable(v, _) => Value::Variable(v.as_ref().into(), PhantomData),
             Self::Int(i) => Value::Int(i.clone()),
             Self::Float(f) => Value::Float(*f),
-            Self::String(s) => Value::String(s.as_ref().into()),
+            Self::String(s) => Value::String(s.clone()),
             Self::Boolean(b) => Value::Boolean(*b),
             Self::Null => Value::Null,
             Self::Enum(v) => Value::Enum(v.as_ref().into()),
@@ -83,44 +199,226 @@ impl<'a, T: Text<'a>> Value<'a, T> {
     }
     pub fn as_variable(&self) -> Option<&T> {
         match self {
-            Value::Variable(s, _) => Some(&s),
+            Value::Variable(s, _) => Some(s),
+            _ => None,
+        }
+    }
+    pub fn as_int(&self) -> Option<&Number> {
+        match self {
+            Value::Int(n) => Some(n),
+            _ => None,
+        }
+    }
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            Value::Float(f) => Some(*f),
+            _ => None,
+        }
+    }
+    pub fn as_string(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
             _ => None,
         }
     }
-    pub fn as_string(&self) -> Option<&T> {
+    pub fn as_bool(&self) -> Option<bool> {
         match self {
-            Value::String(s) => Some(&s),
+            Value::Boolean(b) => Some(*b),
             _ => None,
         }
     }
+    pub fn is_null(&self) -> bool {
+        matches!(self, Value::Null)
+    }
     pub fn as_enum(&self) -> Option<&T> {
         match self {
-            Value::Enum(e) => Some(&e),
+            Value::Enum(e) => Some(e),
+            _ => None,
+        }
+    }
+    pub fn as_list(&self) -> Option<&[Value<'a, T>]> {
+        match self {
+            Value::List(l) => Some(l),
+            _ => None,
+        }
+    }
+    pub fn as_object(&self) -> Option<&BTreeMap<T, Value<'a, T>>> {
+        match self {
+            Value::Object(o) => Some(o),
             _ => None,
         }
     }
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Type<'a, T: Text<'a>> {
     NamedType(T),
     ListType(Box<Type<'a, T>>),
     NonNullType(Box<Type<'a, T>>, PhantomData<&'a ()>),
 }
 
+impl<'a, T: Text<'a>> Type<'a, T> {
+    /// Creates a type referring to the type `name`
+    pub fn named(name: T) -> Self {
+        Type::NamedType(name)
+    }
+
+    /// Creates a list of `inner`, e.g. `[inner]`
+    pub fn list(inner: Type<'a, T>) -> Self {
+        Type::ListType(Box::new(inner))
+    }
+
+    /// Creates a non-null `inner`, e.g. `inner!`
+    ///
+    /// Wrapping a type which is already non-null creates a type the parser
+    /// would reject, such as `Int!!`.
+    pub fn non_null(inner: Type<'a, T>) -> Self {
+        Type::NonNullType(Box::new(inner), PhantomData)
+    }
+
+    /// Returns the name of the type with list and non-null wrappers removed
+    pub fn base_name(&self) -> &T {
+        match self {
+            Type::NamedType(name) => name,
+            Type::ListType(inner) => inner.base_name(),
+            Type::NonNullType(inner, _) => inner.base_name(),
+        }
+    }
+
+    /// Returns `true` for non-null types, e.g. `Int!` and `[Int]!`
+    pub fn is_non_null(&self) -> bool {
+        matches!(self, Type::NonNullType(..))
+    }
+
+    /// Returns `true` for list types, whether they are nullable or not,
+    /// e.g. `[Int]` and `[Int!]!`
+    pub fn is_list(&self) -> bool {
+        match self {
+            Type::NamedType(_) => false,
+            Type::ListType(_) => true,
+            Type::NonNullType(inner, _) => inner.is_list(),
+        }
+    }
+}
+
+/// Converts the text type of AST nodes by calling a function on every
+/// piece of text, see `query::Document::map_text`
+///
+/// Node types of queries and schemas add their own methods in their
+/// modules.
+pub(crate) struct TextMapper<'a, 'b, T, U, F> {
+    f: F,
+    _text: PhantomData<(&'a (), &'b (), T, U)>,
+}
+
+impl<'a, 'b, T, U, F> TextMapper<'a, 'b, T, U, F>
+where
+    T: Text<'a>,
+    U: Text<'b>,
+    F: FnMut(T) -> U,
+{
+    pub(crate) fn new(f: F) -> Self {
+        TextMapper {
+            f,
+            _text: PhantomData,
+        }
+    }
+
+    pub(crate) fn text(&mut self, text: T) -> U {
+        (self.f)(text)
+    }
+
+    pub(crate) fn texts(&mut self, texts: Vec<T>) -> Vec<U> {
+        texts.into_iter().map(|t| self.text(t)).collect()
+    }
+
+    pub(crate) fn value(&mut self, value: Value<'a, T>) -> Value<'b, U> {
+        match value {
+            Value::Variable(name, _) => Value::Variable(self.text(name), PhantomData),
+            Value::Int(n) => Value::Int(n),
+            Value::Float(f) => Value::Float(f),
+            Value::String(s) => Value::String(s),
+            Value::Boolean(b) => Value::Boolean(b),
+            Value::Null => Value::Null,
+            Value::Enum(name) => Value::Enum(self.text(name)),
+            Value::List(items) => Value::List(items.into_iter().map(|v| self.value(v)).collect()),
+            Value::Object(fields) => Value::Object(
+                fields
+                    .into_iter()
+                    .map(|(name, value)| (self.text(name), self.value(value)))
+                    .collect(),
+            ),
+        }
+    }
+
+    pub(crate) fn arguments(
+        &mut self,
+        arguments: Vec<(T, Value<'a, T>)>,
+    ) -> Vec<(U, Value<'b, U>)> {
+        arguments
+            .into_iter()
+            .map(|(name, value)| (self.text(name), self.value(value)))
+            .collect()
+    }
+
+    pub(crate) fn directives(
+        &mut self,
+        directives: Vec<Directive<'a, T>>,
+    ) -> Vec<Directive<'b, U>> {
+        directives
+            .into_iter()
+            .map(|d| Directive {
+                position: d.position,
+                name: self.text(d.name),
+                arguments: self.arguments(d.arguments),
+            })
+            .collect()
+    }
+
+    pub(crate) fn typ(&mut self, typ: Type<'a, T>) -> Type<'b, U> {
+        match typ {
+            Type::NamedType(name) => Type::NamedType(self.text(name)),
+            Type::ListType(inner) => Type::ListType(Box::new(self.typ(*inner))),
+            Type::NonNullType(inner, _) => {
+                Type::NonNullType(Box::new(self.typ(*inner)), PhantomData)
+            }
+        }
+    }
+}
+
 impl Number {
     /// Returns a number as i64 if it fits the type
     pub fn as_i64(&self) -> Option<i64> {
-        Some(self.0)
+        match self.0 {
+            Int::I64(i) => Some(i),
+            Int::Big(_) => None,
+        }
     }
 }
 
 impl From<i32> for Number {
     fn from(i: i32) -> Self {
-        Number(i as i64)
+        Number(Int::I64(i as i64))
+    }
+}
+
+impl From<i64> for Number {
+    fn from(i: i64) -> Self {
+        Number(Int::I64(i))
+    }
+}
+
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            Int::I64(i) => write!(f, "{}", i),
+            Int::Big(ref digits) => f.write_str(digits),
+        }
     }
 }
 
+#[cfg(feature = "std")]
 pub fn directives<'a, T>(
     input: &mut TokenStream<'a>,
 ) -> StdParseResult<Vec<Directive<'a, T>>, TokenStream<'a>>
@@ -143,6 +441,7 @@ pub fn directives<'a, T>(
 }
 
 #[allow(clippy::type_complexity)]
+#[cfg(feature = "std")]
 pub fn arguments<'a, T>(
     input: &mut TokenStream<'a>,
 ) -> StdParseResult<Vec<(T, Value<'a, T>)>, TokenStream<'a>>
@@ -159,6 +458,7 @@ pub fn arguments<'a, T>(
         .into_result()
 }
 
+#[cfg(feature = "std")]
 pub fn int_value<'a, S>(
     input: &mut TokenStream<'a>,
 ) -> StdParseResult<Value<'a, S>, TokenStream<'a>>
@@ -166,13 +466,18 @@ pub fn int_value<'a, S>(
         S: Text<'a>,
 {
     kind(T::IntValue)
-        .and_then(|tok| tok.value.parse())
+        .map(|tok| match tok.value.parse() {
+            Ok(i) => Int::I64(i),
+            // the tokenizer only accepts digits, so it can only be too large
+            Err(_) => Int::Big(tok.value.to_string()),
+        })
         .map(Number)
         .map(Value::Int)
         .parse_stream(input)
         .into_result()
 }
 
+#[cfg(feature = "std")]
 pub fn float_value<'a, S>(
     input: &mut TokenStream<'a>,
 ) -> StdParseResult<Value<'a, S>, TokenStream<'a>>
@@ -186,89 +491,120 @@ pub fn float_value<'a, S>(
         .into_result()
 }
 
+/// Returns the value of a block string, following `BlockStringValue` of
+/// the spec
+///
+/// The common indentation of all lines but the first is removed, as are
+/// leading and trailing lines containing only whitespace.
+#[cfg(feature = "std")]
 fn unquote_block_string(src: &str) -> Result<String, Error<Token<'_>, Token<'_>>> {
     debug_assert!(src.starts_with("\"\"\"") && src.ends_with("\"\"\""));
-    let indent = src[3..src.len() - 3]
-        .lines()
+    let raw = src[3..src.len() - 3].replace(r#"\""""#, r#"""""#);
+    let lines = raw
+        .split("\r\n")
+        .flat_map(|line| line.split(['\n', '\r']))
+        .collect::<Vec<_>>();
+    let indent_of = |line: &str| line.len() - line.trim_start_matches([' ', '\t']).len();
+    let is_blank = |line: &&str| indent_of(line) == line.len();
+    let indent = lines
+        .iter()
         .skip(1)
-        .filter_map(|line| {
-            let trimmed = line.trim_start().len();
-            if trimmed > 0 {
-                Some(line.len() - trimmed)
-            } else {
-                None // skip whitespace-only lines
-            }
-        })
+        .filter(|line| !is_blank(line))
+        .map(|line| indent_of(line))
         .min()
         .unwrap_or(0);
-    let mut result = String::with_capacity(src.len() - 6);
-    let mut lines = src[3..src.len() - 3].lines();
-    if let Some(first) = lines.next() {
-        let stripped = first.trim();
-        if !stripped.is_empty() {
-            result.push_str(stripped);
+
+    let start = lines.iter().position(|line| !is_blank(line));
+    let end = lines.iter().rposition(|line| !is_blank(line));
+    let (start, end) = match (start, end) {
+        (Some(start), Some(end)) => (start, end),
+        _ => return Ok(String::new()),
+    };
+    let mut result = String::with_capacity(raw.len());
+    for (idx, line) in lines.iter().enumerate().take(end + 1).skip(start) {
+        if idx > start {
             result.push('\n');
         }
-    }
-    let mut last_line = 0;
-    for line in lines {
-        last_line = result.len();
-        if line.len() > indent {
-            result.push_str(&line[indent..].replace(r#"\""""#, r#"""""#));
+        if idx == 0 {
+            result.push_str(line);
+        } else {
+            result.push_str(line.get(indent..).unwrap_or(""));
         }
-        result.push('\n');
-    }
-    if result[last_line..].trim().is_empty() {
-        result.truncate(last_line);
     }
-
     Ok(result)
 }
 
-fn unquote_string(s: &str) -> Result<String, Error<Token, Token>> {
+/// Reads the 4 hex digits of a `\u` escape
+#[cfg(feature = "std")]
+fn unicode_escape<'a>(chars: &mut std::str::Chars) -> Result<u32, Error<Token<'a>, Token<'a>>> {
+    let mut code_point = String::with_capacity(4);
+    for _ in 0..4 {
+        match chars.next() {
+            Some(c) => code_point.push(c),
+            None => {
+                return Err(Error::Unexpected(Info::Owned(
+                    format_args!(
+                        "\\u must have 4 characters after it, only found '{}'",
+                        code_point
+                    )
+                        .to_string(),
+                )));
+            }
+        }
+    }
+    u32::from_str_radix(&code_point, 16).map_err(|_| {
+        Error::Unexpected(Info::Owned(
+            format_args!("{} is not a valid unicode code point", code_point).to_string(),
+        ))
+    })
+}
+
+#[cfg(feature = "std")]
+fn unquote_string(s: &str) -> Result<String, Error<Token<'_>, Token<'_>>> {
     let mut res = String::with_capacity(s.len());
-    debug_assert!(s.starts_with('"') && s.ends_with('"'));
+    // Single quotes are only accepted with `ParseOptions::single_quoted_strings`
+    let quote = if s.starts_with('\'') { '\'' } else { '"' };
+    debug_assert!(s.starts_with(quote) && s.ends_with(quote));
     let mut chars = s[1..s.len() - 1].chars();
-    let mut temp_code_point = String::with_capacity(4);
     while let Some(c) = chars.next() {
         match c {
             '\\' => {
                 match chars.next().expect("slash cant be at the end") {
                     c @ '"' | c @ '\\' | c @ '/' => res.push(c),
-                    'b' => res.push('\u{0010}'),
+                    '\'' if quote == '\'' => res.push('\''),
+                    'b' => res.push('\u{0008}'),
                     'f' => res.push('\u{000C}'),
                     'n' => res.push('\n'),
                     'r' => res.push('\r'),
                     't' => res.push('\t'),
                     'u' => {
-                        temp_code_point.clear();
-                        for _ in 0..4 {
-                            match chars.next() {
-                                Some(inner_c) => temp_code_point.push(inner_c),
-                                None => {
-                                    return Err(Error::Unexpected(Info::Owned(
-                                        format_args!(
-                                            "\\u must have 4 characters after it, only found '{}'",
-                                            temp_code_point
-                                        )
-                                            .to_string(),
-                                    )));
-                                }
-                            }
-                        }
-
-                        // convert our hex string into a u32, then convert that into a char
-                        match u32::from_str_radix(&temp_code_point, 16).map(std::char::from_u32) {
-                            Ok(Some(unicode_char)) => res.push(unicode_char),
-                            _ => {
+                        let mut code = unicode_escape(&mut chars)?;
+                        // Characters outside the basic plane are escaped as
+                        // a pair of UTF-16 surrogates
+                        if (0xD800..0xDC00).contains(&code) {
+                            let low = match (chars.next(), chars.next()) {
+                                (Some('\\'), Some('u')) => unicode_escape(&mut chars)?,
+                                _ => 0,
+                            };
+                            if !(0xDC00..0xE000).contains(&low) {
                                 return Err(Error::Unexpected(Info::Owned(
                                     format_args!(
-                                        "{} is not a valid unicode code point",
-                                        temp_code_point
+                                        "{:04X} must be followed by a low surrogate",
+                                        code
                                     )
                                         .to_string(),
                                 )));
                             }
+                            code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                        }
+                        match std::char::from_u32(code) {
+                            Some(unicode_char) => res.push(unicode_char),
+                            None => {
+                                return Err(Error::Unexpected(Info::Owned(
+                                    format_args!("{:04X} is not a valid unicode code point", code)
+                                        .to_string(),
+                                )));
+                            }
                         }
                     }
                     c => {
@@ -285,6 +621,7 @@ fn unquote_string(s: &str) -> Result<String, Error<Token, Token>> {
     Ok(res)
 }
 
+#[cfg(feature = "std")]
 pub fn string<'a>(input: &mut TokenStream<'a>) -> StdParseResult<String, TokenStream<'a>> {
     choice((
         kind(T::StringValue).and_then(|tok| unquote_string(tok.value)),
@@ -294,30 +631,20 @@ pub fn string<'a>(input: &mut TokenStream<'a>) -> StdParseResult<String, TokenSt
         .into_result()
 }
 
+#[cfg(feature = "std")]
 pub fn string_value<'a, S>(
     input: &mut TokenStream<'a>,
 ) -> StdParseResult<Value<'a, S>, TokenStream<'a>>
     where
         S: Text<'a>,
 {
-    _string()
-        .map(Value::String)
-        .parse_stream(input)
-        .into_result()
-}
-
-pub fn block_string_value<'a, S>(
-    input: &mut TokenStream<'a>,
-) -> StdParseResult<Value<'a, S>, TokenStream<'a>>
-    where
-        S: Text<'a>,
-{
-    _blockstring()
+    parser(string)
         .map(Value::String)
         .parse_stream(input)
         .into_result()
 }
 
+#[cfg(feature = "std")]
 pub fn plain_value<'a, T>(
     input: &mut TokenStream<'a>,
 ) -> StdParseResult<Value<'a, T>, TokenStream<'a>>
@@ -332,11 +659,11 @@ pub fn plain_value<'a, T>(
         .or(parser(int_value))
         .or(parser(float_value))
         .or(parser(string_value))
-        .or(parser(block_string_value))
         .parse_stream(input)
         .into_result()
 }
 
+#[cfg(feature = "std")]
 pub fn value<'a, T>(input: &mut TokenStream<'a>) -> StdParseResult<Value<'a, T>, TokenStream<'a>>
     where
         T: Text<'a>,
@@ -357,6 +684,7 @@ pub fn value<'a, T>(input: &mut TokenStream<'a>) -> StdParseResult<Value<'a, T>,
         .into_result()
 }
 
+#[cfg(feature = "std")]
 pub fn default_value<'a, T>(
     input: &mut TokenStream<'a>,
 ) -> StdParseResult<Value<'a, T>, TokenStream<'a>>
@@ -378,6 +706,7 @@ pub fn default_value<'a, T>(
         .into_result()
 }
 
+#[cfg(feature = "std")]
 pub fn parse_type<'a, T>(
     input: &mut TokenStream<'a>,
 ) -> StdParseResult<Type<'a, T>, TokenStream<'a>>
@@ -405,20 +734,101 @@ pub fn parse_type<'a, T>(
 
 #[cfg(test)]
 mod tests {
-    use super::unquote_string;
-    use super::Number;
+    use super::{unquote_block_string, unquote_string};
+    use super::{Directive, Number, Type, Value};
+    use crate::position::Pos;
 
     #[test]
     fn number_from_i32_and_to_i64_conversion() {
         assert_eq!(Number::from(1).as_i64(), Some(1));
         assert_eq!(Number::from(584).as_i64(), Some(584));
         assert_eq!(
-            Number::from(i32::min_value()).as_i64(),
-            Some(i32::min_value() as i64)
+            Number::from(i32::MIN).as_i64(),
+            Some(i32::MIN as i64)
+        );
+        assert_eq!(
+            Number::from(i32::MAX).as_i64(),
+            Some(i32::MAX as i64)
+        );
+    }
+
+    #[test]
+    fn big_numbers() {
+        let doc = crate::query::parse_query::<&str>(
+            "{ f(a: 12345678901234567890, b: -9223372036854775808) }",
+        )
+        .unwrap();
+        let field = match &doc.definitions[0] {
+            crate::query::Definition::Operation(op) => match &op.selection_set().items[0] {
+                crate::query::Selection::Field(field) => field,
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        };
+        let number = |name| match field.argument(name) {
+            Some((_, Value::Int(n))) => n,
+            other => panic!("unexpected {:?}", other),
+        };
+        assert_eq!(number("a").as_i64(), None);
+        assert_eq!(number("a").to_string(), "12345678901234567890");
+        assert_eq!(number("b").as_i64(), Some(i64::MIN));
+        assert_eq!(number("b"), &Number::from(i64::MIN));
+    }
+
+    #[test]
+    fn unquote_delimiters() {
+        assert_eq!(unquote_string(r#""""#).unwrap(), "");
+        assert_eq!(unquote_string(r#""a""#).unwrap(), "a");
+        assert_eq!(unquote_string(r#""\"""#).unwrap(), "\"");
+        assert_eq!(unquote_string(r#""\"a\"""#).unwrap(), "\"a\"");
+        assert_eq!(unquote_string(r#""\\""#).unwrap(), "\\");
+        assert_eq!(unquote_block_string(r#""""""""#).unwrap(), "");
+        assert_eq!(unquote_block_string(r#"""" """"#).unwrap(), "");
+
+        let doc = crate::query::parse_query::<&str>(
+            r#"{ f(a: "", b: "\"", c: """""", e: "\\") }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            doc.to_string(),
+            "{\n  f(a: \"\", b: \"\\\"\", c: \"\", e: \"\\\\\")\n}\n"
         );
+    }
+
+    #[test]
+    fn unquote_escapes() {
         assert_eq!(
-            Number::from(i32::max_value()).as_i64(),
-            Some(i32::max_value() as i64)
+            unquote_string(r#""\"\\\/\b\f\n\r\t""#).unwrap(),
+            "\"\\/\u{8}\u{c}\n\r\t"
+        );
+        assert_eq!(unquote_string(r#""\uD83D\uDE00!""#).unwrap(), "\u{1F600}!");
+        assert_eq!(unquote_string(r#""\ud83d\ude00""#).unwrap(), "\u{1F600}");
+        assert!(unquote_string(r#""\uD83D""#).is_err());
+        assert!(unquote_string(r#""\uD83Dx""#).is_err());
+        assert!(unquote_string(r#""\uD83D\u0041""#).is_err());
+        assert!(unquote_string(r#""\uDE00""#).is_err());
+
+        let source = r#"{ f(a: "\b\u0001\uD83D\uDE00\u00e9") }"#;
+        let doc = crate::query::parse_query::<&str>(source).unwrap();
+        let formatted = doc.to_string();
+        assert_eq!(formatted, "{\n  f(a: \"\\u0008\\u0001\u{1F600}\u{e9}\")\n}\n");
+        assert_eq!(crate::query::parse_query::<&str>(&formatted).unwrap().to_string(), formatted);
+    }
+
+    #[test]
+    fn unquote_block_dedent() {
+        assert_eq!(unquote_block_string(r#""""x""""#).unwrap(), "x");
+        assert_eq!(
+            unquote_block_string("\"\"\"  first\n    a\n\t  b\n      c\n  \"\"\"").unwrap(),
+            "  first\n a\nb\n   c"
+        );
+        assert_eq!(
+            unquote_block_string("\"\"\"\r\n\n  a\r\n\r  b\r\n   \n\"\"\"").unwrap(),
+            "a\n\nb"
+        );
+        assert_eq!(
+            unquote_block_string("\"\"\"\n  a \"quote\" and \\\"\"\"\n  b\"\"\"").unwrap(),
+            "a \"quote\" and \"\"\"\nb"
         );
     }
 
@@ -437,4 +847,85 @@ mod tests {
             "\u{0009} hello \u{000A} there"
         );
     }
+
+    #[test]
+    fn value_accessors() {
+        let value: Value<&str> = Value::Object(
+            vec![
+                ("list", Value::List(vec![Value::Int(1.into()), Value::Null])),
+                ("flag", Value::Boolean(true)),
+            ]
+            .into_iter()
+            .collect(),
+        );
+        let object = value.as_object().unwrap();
+        let list = object["list"].as_list().unwrap();
+        assert_eq!(list[0].as_int().and_then(|n| n.as_i64()), Some(1));
+        assert!(list[1].is_null());
+        assert_eq!(object["flag"].as_bool(), Some(true));
+        assert_eq!(object["flag"].as_float(), None);
+        assert_eq!(value.as_list(), None);
+        assert_eq!(Value::<&str>::Float(0.5).as_float(), Some(0.5));
+        assert_eq!(Value::<&str>::String("s".into()).as_string(), Some("s"));
+    }
+
+    #[test]
+    fn directive_arguments() {
+        let dir: Directive<&str> = Directive {
+            position: Pos::default(),
+            name: "key",
+            arguments: vec![
+                ("fields", Value::String("id".into())),
+                ("resolvable", Value::Boolean(false)),
+                ("weight", Value::Int(3.into())),
+                ("tags", Value::List(vec![Value::Enum("A")])),
+            ],
+        };
+        assert_eq!(dir.arg_str("fields"), Some("id"));
+        assert_eq!(dir.arg_bool("resolvable"), Some(false));
+        assert_eq!(dir.arg_int("weight"), Some(3));
+        assert_eq!(dir.arg_list("tags"), Some(&[Value::Enum("A")][..]));
+        assert_eq!(dir.arg_str("weight"), None);
+        assert_eq!(dir.argument("missing"), None);
+    }
+
+    #[test]
+    fn type_wrappers() {
+        let doc = crate::schema::parse_schema::<&str>(
+            "type T { a: Int, b: Int!, c: [Int], d: [[Int!]]! }",
+        )
+        .unwrap();
+        let fields = match &doc.definitions[0] {
+            crate::schema::Definition::TypeDefinition(t) => &t.as_object().unwrap().fields,
+            _ => unreachable!(),
+        };
+        let types = fields
+            .iter()
+            .map(|f| {
+                let t = &f.field_type;
+                (*t.base_name(), t.is_non_null(), t.is_list())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            types,
+            vec![
+                ("Int", false, false),
+                ("Int", true, false),
+                ("Int", false, true),
+                ("Int", true, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn type_constructors() {
+        let typ = Type::non_null(Type::list(Type::non_null(Type::named("User"))));
+        assert_eq!(typ.to_string(), "[User!]!");
+        let doc = crate::query::parse_query::<&str>("query($u: [User!]!) { a }").unwrap();
+        let parsed = match &doc.definitions[0] {
+            crate::query::Definition::Operation(op) => &op.variable_definitions()[0].var_type,
+            _ => unreachable!(),
+        };
+        assert_eq!(&typ, parsed);
+    }
 }
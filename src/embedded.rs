@@ -0,0 +1,238 @@
+//! Extracting GraphQL embedded in JavaScript and TypeScript sources
+//!
+use crate::position::Pos;
+
+/// Contents of a tagged template literal
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateBlock<'a> {
+    /// Position of the first character after the opening backtick
+    pub position: Pos,
+    /// Text between the backticks, as written in the source
+    pub text: &'a str,
+    /// Every `${...}` interpolation within `text`, including the `${` and
+    /// `}` delimiters, with its position in the source
+    pub interpolations: Vec<(Pos, &'a str)>,
+}
+
+struct Cursor<'a> {
+    source: &'a str,
+    off: usize,
+    pos: Pos,
+}
+
+impl<'a> Cursor<'a> {
+    fn peek(&self) -> Option<char> {
+        self.source[self.off..].chars().next()
+    }
+
+    /// Advances past the next character, counting columns the way the
+    /// tokenizer does
+    fn bump(&mut self) -> Option<char> {
+        let ch = self.peek()?;
+        self.off += ch.len_utf8();
+        self.pos.offset = self.off;
+        match ch {
+            '\n' => {
+                self.pos.line += 1;
+                self.pos.column = 1;
+            }
+            '\t' => self.pos.column += 8,
+            '\r' | '\u{feff}' => {}
+            _ => self.pos.column += 1,
+        }
+        Some(ch)
+    }
+
+    fn starts_with(&self, s: &str) -> bool {
+        self.source[self.off..].starts_with(s)
+    }
+
+    /// Skips an interpolation, the cursor must be right after `${`
+    fn skip_interpolation(&mut self) -> Option<()> {
+        let mut depth = 1;
+        while depth > 0 {
+            match self.bump()? {
+                '{' => depth += 1,
+                '}' => depth -= 1,
+                '`' => self.skip_template()?,
+                '"' | '\'' => {
+                    let quote = self.source[..self.off].chars().next_back();
+                    while self.peek()? != '\n' {
+                        let ch = self.bump()?;
+                        if ch == '\\' {
+                            self.bump()?;
+                        } else if Some(ch) == quote {
+                            break;
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        Some(())
+    }
+
+    /// Skips a nested template literal, the cursor must be right after the
+    /// opening backtick
+    fn skip_template(&mut self) -> Option<()> {
+        loop {
+            match self.bump()? {
+                '\\' => {
+                    self.bump()?;
+                }
+                '`' => return Some(()),
+                '$' if self.peek() == Some('{') => {
+                    self.bump();
+                    self.skip_interpolation()?;
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn is_ident_char(ch: char) -> bool {
+    ch == '_' || ch == '$' || ch.is_alphanumeric()
+}
+
+/// Finds template literals tagged with `tag` in a JavaScript or TypeScript
+/// source
+///
+/// Typical use is `extract_graphql_blocks(source, "gql")` to find every
+/// ``gql`...` `` literal. The contents are returned verbatim, so
+/// interpolations have to be removed or replaced before parsing them.
+/// Columns in positions count characters, unterminated literals are ignored.
+///
+/// This is not a JavaScript parser: tags appearing within strings or
+/// comments are reported as well.
+pub fn extract_graphql_blocks<'a>(source: &'a str, tag: &str) -> Vec<TemplateBlock<'a>> {
+    let mut result = Vec::new();
+    if tag.is_empty() {
+        return result;
+    }
+    let mut cur = Cursor {
+        source,
+        off: 0,
+        pos: Pos {
+            line: 1,
+            column: 1,
+            offset: 0,
+        },
+    };
+    let mut prev = None;
+    while let Some(ch) = cur.peek() {
+        let standalone = !matches!(prev, Some(c) if is_ident_char(c));
+        if standalone && cur.starts_with(tag) {
+            for _ in tag.chars() {
+                prev = cur.bump();
+            }
+            if matches!(cur.peek(), Some(c) if is_ident_char(c)) {
+                continue;
+            }
+            while matches!(cur.peek(), Some(c) if c.is_whitespace()) {
+                cur.bump();
+            }
+            if cur.peek() != Some('`') {
+                continue;
+            }
+            cur.bump();
+            let start = cur.off;
+            let position = cur.pos;
+            let mut interpolations = Vec::new();
+            let end = loop {
+                let (off, pos) = (cur.off, cur.pos);
+                match cur.bump() {
+                    None => break None,
+                    Some('\\') => {
+                        cur.bump();
+                    }
+                    Some('`') => break Some(off),
+                    Some('$') if cur.peek() == Some('{') => {
+                        cur.bump();
+                        if cur.skip_interpolation().is_none() {
+                            break None;
+                        }
+                        interpolations.push((pos, &source[off..cur.off]));
+                    }
+                    Some(_) => {}
+                }
+            };
+            match end {
+                Some(end) => result.push(TemplateBlock {
+                    position,
+                    text: &source[start..end],
+                    interpolations,
+                }),
+                None => break,
+            }
+            prev = Some('`');
+            continue;
+        }
+        cur.bump();
+        prev = Some(ch);
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::extract_graphql_blocks;
+    use crate::position::Pos;
+
+    fn pos(line: usize, column: usize, offset: usize) -> Pos {
+        Pos {
+            line,
+            column,
+            offset,
+        }
+    }
+
+    #[test]
+    fn simple() {
+        let src = "const Q = gql`\n  { a }\n`;\nconst R = gql `query { b }`";
+        let blocks = extract_graphql_blocks(src, "gql");
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].position, pos(1, 15, 14));
+        assert_eq!(blocks[0].text, "\n  { a }\n");
+        assert_eq!(blocks[1].position, pos(4, 16, 41));
+        assert_eq!(blocks[1].text, "query { b }");
+    }
+
+    #[test]
+    fn interpolations() {
+        let src = "gql`query { ...F } ${frag({ a: `x${1}` })} ${\"}\"}`";
+        let blocks = extract_graphql_blocks(src, "gql");
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(
+            blocks[0].interpolations,
+            vec![
+                (pos(1, 20, 19), "${frag({ a: `x${1}` })}"),
+                (pos(1, 44, 43), "${\"}\"}"),
+            ]
+        );
+        assert_eq!(blocks[0].text, &src[4..src.len() - 1]);
+    }
+
+    #[test]
+    fn tabs() {
+        let src = "\tconst Q = gql`\n\t\t{ ...F ${x} }`";
+        let blocks = extract_graphql_blocks(src, "gql");
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].position, pos(1, 23, 15));
+        assert_eq!(blocks[0].interpolations, vec![(pos(2, 24, 25), "${x}")]);
+    }
+
+    #[test]
+    fn other_tags() {
+        let src = "mygql`{ a }` gqlx`{ b }` html`<p>` gql`\\`{ c }\\``";
+        let blocks = extract_graphql_blocks(src, "gql");
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].text, "\\`{ c }\\`");
+    }
+
+    #[test]
+    fn unterminated() {
+        assert!(extract_graphql_blocks("gql`{ a }", "gql").is_empty());
+        assert!(extract_graphql_blocks("gql`{ a } ${b", "gql").is_empty());
+    }
+}
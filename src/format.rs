@@ -1,13 +1,26 @@
 //! Formatting graphql
-use std::default::Default;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::default::Default;
+#[cfg(feature = "std")]
+use std::io;
 
-use crate::common::Directive;
+use crate::common::{Comment, Directive};
+use crate::position::Pos;
 
 #[derive(Debug, PartialEq)]
 pub(crate) struct Formatter<'a> {
     buf: String,
     style: &'a Style,
     indent: u32,
+    /// Whether anything was already taken out of `buf` by `write_to`
+    flushed: bool,
+    /// Comments not written yet, in source order
+    comments: &'a [Comment],
+    /// Whether each argument block being written is put one item per line
+    blocks: Vec<bool>,
+    /// Whether the next argument block is put one item per line
+    expand_next: bool,
 }
 
 /// A configuration of formatting style
@@ -18,6 +31,12 @@ pub(crate) struct Formatter<'a> {
 pub struct Style {
     indent: u32,
     multiline_arguments: bool,
+    expand_all: bool,
+    descriptions: bool,
+    builtin_definitions: bool,
+    tabs: bool,
+    max_width: Option<usize>,
+    block_strings: bool,
 }
 
 impl Default for Style {
@@ -25,22 +44,121 @@ impl Default for Style {
         Style {
             indent: 2,
             multiline_arguments: false,
+            expand_all: false,
+            descriptions: true,
+            builtin_definitions: true,
+            tabs: false,
+            max_width: None,
+            block_strings: true,
         }
     }
 }
 
+/// A named set of formatting options, see [`Style::preset`]
+///
+/// Presets only differ in the options `Style` has, the rest of the output
+/// is the same for all of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preset {
+    /// The output of `printSchema` of graphql-js: two space indentation,
+    /// arguments on one line, and no definitions of the built-in scalars
+    /// and directives, since graphql-js leaves those out
+    GraphqlJs,
+    /// Same as [`Preset::GraphqlJs`], the Relay compiler reads schemas
+    /// printed by graphql-js
+    Relay,
+    /// Same as [`Preset::GraphqlJs`], which is also what Apollo Federation
+    /// uses for printing subgraph schemas. Federation directives are
+    /// printed like any other directive.
+    ApolloFederation,
+    /// Like [`Preset::GraphqlJs`] but without descriptions, for the
+    /// smallest output that is still readable
+    Minimal,
+}
+
 impl Style {
+    /// Returns the style of a preset
+    ///
+    /// The result can be changed further using the other methods.
+    pub fn preset(preset: Preset) -> Style {
+        let mut style = Style::default();
+        style.builtin_definitions(false);
+        if preset == Preset::Minimal {
+            style.descriptions(false);
+        }
+        style
+    }
+
     /// Change the number of spaces used for indentation
     pub fn indent(&mut self, indent: u32) -> &mut Self {
         self.indent = indent;
         self
     }
 
+    /// Set whether to indent with tabs, one per level, instead of spaces
+    ///
+    /// The number of spaces set by [`Style::indent`] is ignored when
+    /// tabs are used.
+    pub fn tabs(&mut self, tabs: bool) -> &mut Self {
+        self.tabs = tabs;
+        self
+    }
+
+    /// Set whether strings containing new lines are written as block
+    /// strings
+    ///
+    /// When disabled they are written as regular strings with the new
+    /// lines escaped.
+    pub fn block_strings(&mut self, block_strings: bool) -> &mut Self {
+        self.block_strings = block_strings;
+        self
+    }
+
+    /// Set the line width after which arguments are put one per line
+    ///
+    /// Arguments of a field which would make the line longer than
+    /// `max_width` characters, together with directives following them, are
+    /// put one per line, other fields keep them on one line. Values within
+    /// the arguments are not wrapped. Indentation counts one character per
+    /// space or tab.
+    pub fn max_width(&mut self, max_width: Option<usize>) -> &mut Self {
+        self.max_width = max_width;
+        self
+    }
+
     /// Set whether to add new lines between arguments
     pub fn multiline_arguments(&mut self, multiline_arguments: bool) -> &mut Self {
         self.multiline_arguments = multiline_arguments;
         self
     }
+
+    /// Set whether every list-like construct is put one item per line
+    ///
+    /// In addition to `multiline_arguments` this expands variable
+    /// definitions, argument definitions in schema and object and list
+    /// values, so that each item ends up on its own line regardless of
+    /// length. This is useful for line-based diffs.
+    pub fn expand_all(&mut self, expand_all: bool) -> &mut Self {
+        self.expand_all = expand_all;
+        self
+    }
+
+    /// Set whether descriptions are written
+    pub fn descriptions(&mut self, descriptions: bool) -> &mut Self {
+        self.descriptions = descriptions;
+        self
+    }
+
+    /// Set whether schema definitions of the built-in scalars and
+    /// directives are written
+    ///
+    /// Those are `Int`, `Float`, `String`, `Boolean`, `ID` and the
+    /// [built-in directives](crate::schema::BUILTIN_DIRECTIVES). Extensions
+    /// of them are always written.
+    pub fn builtin_definitions(&mut self, builtin_definitions: bool) -> &mut Self {
+        self.builtin_definitions = builtin_definitions;
+        self
+    }
 }
 
 pub(crate) trait Displayable {
@@ -48,17 +166,55 @@ pub(crate) trait Displayable {
 }
 
 impl<'a> Formatter<'a> {
-    pub fn new(style: &Style) -> Formatter {
+    pub fn new(style: &Style) -> Formatter<'_> {
         Formatter {
             buf: String::with_capacity(1024),
             style,
             indent: 0,
+            flushed: false,
+            comments: &[],
+            blocks: Vec::new(),
+            expand_next: false,
+        }
+    }
+
+    pub fn with_comments(style: &'a Style, comments: &'a [Comment]) -> Formatter<'a> {
+        Formatter {
+            comments,
+            ..Formatter::new(style)
+        }
+    }
+
+    /// Writes comments located before `pos`, each on its own line
+    pub fn comments_before(&mut self, pos: Pos) {
+        while let Some((comment, rest)) = self.comments.split_first() {
+            if comment.position >= pos {
+                break;
+            }
+            self.indent();
+            self.buf.push('#');
+            self.buf.push_str(&comment.text);
+            self.endline();
+            self.comments = rest;
+        }
+    }
+
+    /// Writes all comments not written yet
+    pub fn remaining_comments(&mut self) {
+        if !self.comments.is_empty() {
+            self.margin();
+            self.comments_before(Pos {
+                line: usize::MAX,
+                column: usize::MAX,
+                offset: usize::MAX,
+            });
         }
     }
 
     pub fn indent(&mut self) {
+        let ch = if self.style.tabs { '\t' } else { ' ' };
         for _ in 0..self.indent {
-            self.buf.push(' ');
+            self.buf.push(ch);
         }
     }
 
@@ -66,15 +222,54 @@ impl<'a> Formatter<'a> {
         self.buf.push('\n');
     }
 
+    pub fn descriptions(&self) -> bool {
+        self.style.descriptions
+    }
+
+    pub fn builtin_definitions(&self) -> bool {
+        self.style.builtin_definitions
+    }
+
+    fn multiline_arguments(&self) -> bool {
+        self.style.multiline_arguments || self.style.expand_all
+    }
+
+    fn in_multiline_block(&self) -> bool {
+        self.blocks.last().cloned().unwrap_or(false)
+    }
+
+    /// Puts the items of the next argument block one per line if writing
+    /// it with `display` would exceed the maximum line width
+    pub fn wrap_next_block<F>(&mut self, display: F)
+    where
+        F: FnOnce(&mut Formatter),
+    {
+        let max_width = match self.style.max_width {
+            Some(max_width) => max_width,
+            None => return,
+        };
+        let mut style = self.style.clone();
+        style.max_width = None;
+        style.multiline_arguments = false;
+        style.expand_all = false;
+        let mut inline = Formatter::new(&style);
+        display(&mut inline);
+        let line = &self.buf[self.buf.rfind('\n').map_or(0, |idx| idx + 1)..];
+        let width = line.chars().count() + inline.buf.chars().count();
+        self.expand_next = !inline.buf.is_empty() && width > max_width;
+    }
+
     pub fn start_argument_block(&mut self, open_char: char) {
         self.buf.push(open_char);
-        if self.style.multiline_arguments {
+        let multiline = self.multiline_arguments() || core::mem::take(&mut self.expand_next);
+        if multiline {
             self.inc_indent();
         }
+        self.blocks.push(multiline);
     }
 
     pub fn end_argument_block(&mut self, close_char: char) {
-        if self.style.multiline_arguments {
+        if self.blocks.pop().unwrap_or(false) {
             self.endline();
             self.dec_indent();
             self.indent();
@@ -83,7 +278,7 @@ impl<'a> Formatter<'a> {
     }
 
     pub fn start_argument(&mut self) {
-        if self.style.multiline_arguments {
+        if self.in_multiline_block() {
             self.endline();
             self.indent();
         }
@@ -91,11 +286,42 @@ impl<'a> Formatter<'a> {
 
     pub fn deliniate_argument(&mut self) {
         self.buf.push(',');
-        if !self.style.multiline_arguments {
+        if !self.in_multiline_block() {
             self.buf.push(' ');
         }
     }
 
+    /// Writes a parenthesized comma-separated list, one item per line
+    /// when `expand_all` is enabled
+    pub fn write_list<I, F>(&mut self, items: &[I], mut display: F)
+    where
+        F: FnMut(&I, &mut Self),
+    {
+        if items.is_empty() {
+            return;
+        }
+        if self.style.expand_all {
+            self.start_argument_block('(');
+            for (idx, item) in items.iter().enumerate() {
+                if idx > 0 {
+                    self.deliniate_argument();
+                }
+                self.start_argument();
+                display(item, self);
+            }
+            self.end_argument_block(')');
+        } else {
+            self.buf.push('(');
+            for (idx, item) in items.iter().enumerate() {
+                if idx > 0 {
+                    self.write(", ");
+                }
+                display(item, self);
+            }
+            self.buf.push(')');
+        }
+    }
+
     pub fn start_block(&mut self) {
         self.buf.push('{');
         self.endline();
@@ -110,7 +336,7 @@ impl<'a> Formatter<'a> {
     }
 
     pub fn margin(&mut self) {
-        if !self.buf.is_empty() {
+        if self.flushed || !self.buf.is_empty() {
             self.buf.push('\n');
         }
     }
@@ -123,18 +349,23 @@ impl<'a> Formatter<'a> {
         self.buf
     }
 
+    /// Moves everything formatted so far to the writer
+    #[cfg(feature = "std")]
+    pub fn write_to<W: io::Write>(&mut self, w: &mut W) -> io::Result<()> {
+        w.write_all(self.buf.as_bytes())?;
+        self.flushed |= !self.buf.is_empty();
+        self.buf.clear();
+        Ok(())
+    }
+
+    /// Whether [`Formatter::write_quoted`] writes `s` as a block string
+    pub fn is_block_string(&self, s: &str) -> bool {
+        self.style.block_strings && fits_block_string(s)
+    }
+
     pub fn write_quoted(&mut self, s: &str) {
-        let mut has_newline = false;
-        let mut has_nonprintable = false;
-        for c in s.chars() {
-            match c {
-                '\n' => has_newline = true,
-                '\r' | '\t' | '\u{0020}'..='\u{FFFF}' => {}
-                _ => has_nonprintable = true,
-            }
-        }
-        if !has_newline || has_nonprintable {
-            use std::fmt::Write;
+        if !self.is_block_string(s) {
+            use core::fmt::Write;
             self.buf.push('"');
             for c in s.chars() {
                 match c {
@@ -143,40 +374,70 @@ impl<'a> Formatter<'a> {
                     '\t' => self.write(r"\t"),
                     '"' => self.write("\\\""),
                     '\\' => self.write(r"\\"),
-                    '\u{0020}'..='\u{FFFF}' => self.buf.push(c),
-                    _ => write!(&mut self.buf, "\\u{:04}", c as u32).unwrap(),
+                    _ if c < ' ' => write!(&mut self.buf, "\\u{:04X}", c as u32).unwrap(),
+                    _ => self.buf.push(c),
                 }
             }
             self.buf.push('"');
         } else {
             self.buf.push_str(r#"""""#);
             self.endline();
-            self.indent += self.style.indent;
-            for line in s.lines() {
-                if !line.trim().is_empty() {
+            self.inc_indent();
+            for line in s.split('\n') {
+                if !line.is_empty() {
                     self.indent();
                     self.write(&line.replace(r#"""""#, r#"\""""#));
                 }
                 self.endline();
             }
-            self.indent -= self.style.indent;
+            self.dec_indent();
             self.indent();
             self.buf.push_str(r#"""""#);
         }
     }
 
+    /// Number of characters added to the indentation for each level
+    fn indent_step(&self) -> u32 {
+        if self.style.tabs {
+            1
+        } else {
+            self.style.indent
+        }
+    }
+
     fn inc_indent(&mut self) {
-        self.indent += self.style.indent;
+        self.indent += self.indent_step();
     }
 
     fn dec_indent(&mut self) {
         self.indent = self
             .indent
-            .checked_sub(self.style.indent)
+            .checked_sub(self.indent_step())
             .expect("negative indent");
     }
 }
 
+/// Whether `s` is written as a block string, reading back as the same value
+///
+/// Block strings are only used for multi-line values. Since the lines are
+/// written indented and the common indentation of a block string isn't
+/// part of its value, the value can't have one. Whitespace-only lines at
+/// the start or end and whitespace on otherwise empty lines would be lost
+/// too, and carriage returns and other control characters would need
+/// escapes, which block strings don't have.
+fn fits_block_string(s: &str) -> bool {
+    if !s.contains('\n') || s.chars().any(|c| c < ' ' && c != '\n' && c != '\t') {
+        return false;
+    }
+    let indent_of = |line: &str| line.len() - line.trim_start_matches([' ', '\t']).len();
+    let is_blank = |line: &&str| indent_of(line) == line.len();
+    let lines = s.split('\n').collect::<Vec<_>>();
+    !is_blank(&lines[0])
+        && !is_blank(&lines[lines.len() - 1])
+        && lines.iter().all(|line| line.is_empty() || !is_blank(line))
+        && lines.iter().any(|line| !line.is_empty() && indent_of(line) == 0)
+}
+
 pub(crate) fn format_directives<'a, T>(dirs: &[Directive<'a, T>], f: &mut Formatter)
 where
     T: crate::common::Text<'a>,
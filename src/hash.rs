@@ -0,0 +1,64 @@
+//! Hashing documents independently of the process
+//!
+use core::fmt::{self, Write};
+use core::hash::{Hash, Hasher};
+
+use crate::common::Text;
+use crate::{query, schema};
+
+/// 64-bit FNV-1a, which unlike the standard library hashers gives the same
+/// result in every process and on every platform
+struct Fnv1a(u64);
+
+impl Write for Fnv1a {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for &byte in s.as_bytes() {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+        Ok(())
+    }
+}
+
+/// Hashes the formatted form of `value`
+///
+/// Formatting omits positions and normalizes whitespace, so the hash only
+/// depends on the content.
+pub(crate) fn content_hash<D: fmt::Display>(value: &D) -> u64 {
+    let mut hasher = Fnv1a(0xcbf2_9ce4_8422_2325);
+    write!(hasher, "{}", value).expect("hashing never fails");
+    hasher.0
+}
+
+/// A document usable as a `HashMap` key regardless of where it was parsed
+///
+/// Two keys are equal if their documents are equal when positions are
+/// ignored, see
+/// [`query::Document::eq_ignore_positions`](crate::query::Document::eq_ignore_positions),
+/// and hash with
+/// [`query::Document::content_hash`](crate::query::Document::content_hash).
+/// Works for both query and schema documents.
+#[derive(Debug, Clone)]
+pub struct DocumentKey<D>(pub D);
+
+macro_rules! impl_document_key {
+    ($( $module: ident, )+) => {
+        $(
+            impl<'a, T: Text<'a>> PartialEq for DocumentKey<$module::Document<'a, T>> {
+                fn eq(&self, other: &Self) -> bool {
+                    self.0.eq_ignore_positions(&other.0)
+                }
+            }
+
+            impl<'a, T: Text<'a>> Eq for DocumentKey<$module::Document<'a, T>> {}
+
+            impl<'a, T: Text<'a>> Hash for DocumentKey<$module::Document<'a, T>> {
+                fn hash<H: Hasher>(&self, state: &mut H) {
+                    state.write_u64(self.0.content_hash());
+                }
+            }
+        )+
+    };
+}
+
+impl_document_key!(query, schema,);
@@ -1,8 +1,8 @@
 use std::marker::PhantomData;
 
-use combine::error::Tracked;
+use combine::error::{Commit, Tracked};
 use combine::stream::easy::{Error, Errors, Info};
-use combine::{satisfy, ParseResult, Parser, StreamOnce};
+use combine::{satisfy, ParseResult, Parser, StdParseResult, StreamOnce};
 
 use crate::position::Pos;
 use crate::tokenizer::{Kind, Token, TokenStream};
@@ -23,16 +23,6 @@ pub struct NameMatch<'a, T>
     phantom: PhantomData<&'a T>,
 }
 
-#[derive(Debug, Clone)]
-pub struct StringMatch<'a, T>
-    where
-        T: Text<'a>,
-{
-    block: bool,
-    phantom: PhantomData<&'a T>,
-}
-
-
 #[derive(Debug, Clone)]
 pub struct Value<'a> {
     kind: Kind,
@@ -56,25 +46,6 @@ pub fn name<'a, T>() -> NameMatch<'a, T>
     }
 }
 
-pub fn _string<'a, T>() -> StringMatch<'a, T>
-    where T: Text<'a>
-{
-    StringMatch {
-        block: false,
-        phantom: PhantomData,
-    }
-}
-
-pub fn _blockstring<'a, T>() -> StringMatch<'a, T>
-    where T: Text<'a>
-{
-    StringMatch {
-        block: true,
-        phantom: PhantomData,
-    }
-}
-
-
 impl<'a> Parser<TokenStream<'a>> for TokenMatch<'a> {
     type Output = Token<'a>;
     type PartialState = ();
@@ -151,31 +122,8 @@ impl<'a, S> Parser<TokenStream<'a>> for NameMatch<'a, S>
     }
 }
 
-impl<'a, S> Parser<TokenStream<'a>> for StringMatch<'a, S>
-    where
-        S: Text<'a>,
-{
-    type Output = S;
-    type PartialState = ();
-
-    #[inline]
-    fn parse_lazy(
-        &mut self,
-        input: &mut TokenStream<'a>,
-    ) -> ParseResult<Self::Output, <TokenStream<'a> as StreamOnce>::Error> {
-        satisfy(|c: Token<'a>| if self.block { c.kind == Kind::BlockString } else { c.kind == Kind::StringValue })
-            .map(|t: Token<'a>| -> S {
-                if self.block {
-                    S::from(&t.value[3..=t.value.len() - 3])
-                } else {
-                    S::from(&t.value[1..=t.value.len() - 1])
-                }
-            })
-            .parse_lazy(input)
-    }
-
-    fn add_error(&mut self, error: &mut Tracked<Errors<Token<'a>, Token<'a>, Pos>>) {
-        error.error.add_error(Error::Expected(Info::Static("String")));
-    }
+/// Returns the position right past the last token parsed so far, without
+/// consuming any input
+pub fn end_position<'a>(input: &mut TokenStream<'a>) -> StdParseResult<Pos, TokenStream<'a>> {
+    Ok((input.end_position(), Commit::Peek(())))
 }
-
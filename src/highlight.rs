@@ -0,0 +1,528 @@
+//! Semantic classification of tokens for syntax highlighting
+//!
+use std::collections::HashMap;
+
+use combine::{Positioned, StreamOnce};
+
+use crate::common::Text;
+use crate::position::{Pos, Span};
+use crate::schema;
+use crate::tokenizer::{Kind, Token, TokenStream};
+
+/// Grammatical role of a token
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SemanticKind {
+    /// Punctuation, including `...`
+    Punctuator,
+    /// Keywords like `query`, `fragment`, `on`, and `true`/`false`/`null`
+    Keyword,
+    /// Integer and float literals
+    Number,
+    /// Quoted and block strings
+    String,
+    /// Name of an operation
+    OperationName,
+    /// Name of a fragment, both in its definition and in spreads
+    FragmentName,
+    /// Name of a variable, both in its definition and in usages
+    Variable,
+    /// Name of a selected field
+    Field,
+    /// Alias of a selected field
+    Alias,
+    /// Name of an argument of a field or a directive
+    Argument,
+    /// Field name within an input object value
+    InputField,
+    /// Name of a directive
+    Directive,
+    /// Name of a type in a variable definition or a type condition
+    Type,
+    /// Enum value literal
+    EnumValue,
+    /// Name which could not be classified, usually because of a syntax error
+    Name,
+}
+
+/// Token along with its classification
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SemanticToken {
+    pub span: Span,
+    pub kind: SemanticKind,
+    /// Whether the field is defined on its parent type
+    ///
+    /// Only set for fields, and only when a schema is provided.
+    pub known: Option<bool>,
+}
+
+struct Schema<'d> {
+    fields: HashMap<&'d str, HashMap<&'d str, &'d str>>,
+    roots: [&'d str; 3],
+}
+
+impl<'d> Schema<'d> {
+    fn new<'a, T: Text<'a>>(doc: &'d schema::Document<'a, T>) -> Schema<'d> {
+        use crate::schema::{Definition::*, TypeDefinition as D, TypeExtension as E};
+
+        let mut fields: HashMap<&str, HashMap<&str, &str>> = HashMap::new();
+        let mut roots = ["Query", "Mutation", "Subscription"];
+        for def in &doc.definitions {
+            let (name, list) = match def {
+                TypeDefinition(D::Object(o)) => (&o.name, &o.fields),
+                TypeDefinition(D::Interface(i)) => (&i.name, &i.fields),
+                TypeExtension(E::Object(o)) => (&o.name, &o.fields),
+                TypeExtension(E::Interface(i)) => (&i.name, &i.fields),
+                TypeDefinition(D::Union(u)) => {
+                    fields.entry(u.name.as_ref()).or_default();
+                    continue;
+                }
+                SchemaDefinition(s) => {
+                    let name = |t: &'d Option<T>| t.as_ref().map_or("", |t| t.as_ref());
+                    roots = [name(&s.query), name(&s.mutation), name(&s.subscription)];
+                    continue;
+                }
+                _ => continue,
+            };
+            fields.entry(name.as_ref()).or_default().extend(
+                list.iter()
+                    .map(|f| (f.name.as_ref(), f.field_type.base_name().as_ref())),
+            );
+        }
+        Schema { fields, roots }
+    }
+
+    fn composite(&self, name: &str) -> Option<&'d str> {
+        self.fields.get_key_value(name).map(|(&k, _)| k)
+    }
+}
+
+struct Highlighter<'s, 'd> {
+    tokens: Vec<(Token<'s>, Span)>,
+    out: Vec<SemanticToken>,
+    idx: usize,
+    schema: Option<Schema<'d>>,
+}
+
+fn token_end(start: Pos, value: &str) -> Pos {
+    match value.rfind('\n') {
+        Some(nl) => Pos {
+            line: start.line + value.matches('\n').count(),
+            column: value[nl + 1..].chars().count() + 1,
+            offset: start.offset + value.len(),
+        },
+        None => Pos {
+            line: start.line,
+            column: start.column + value.chars().count(),
+            offset: start.offset + value.len(),
+        },
+    }
+}
+
+impl<'s, 'd> Highlighter<'s, 'd> {
+    fn peek(&self) -> Option<Token<'s>> {
+        self.tokens.get(self.idx).map(|t| t.0)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<Token<'s>> {
+        self.tokens.get(self.idx + offset).map(|t| t.0)
+    }
+
+    fn is(&self, kind: Kind, value: &str) -> bool {
+        matches!(self.peek(), Some(t) if t.kind == kind && t.value == value)
+    }
+
+    fn is_punct(&self, value: &str) -> bool {
+        self.is(Kind::Punctuator, value)
+    }
+
+    fn is_name(&self) -> bool {
+        matches!(self.peek(), Some(t) if t.kind == Kind::Name)
+    }
+
+    /// Marks current token with `kind` and moves to the next one
+    fn bump(&mut self, kind: SemanticKind) {
+        if let Some(tok) = self.out.get_mut(self.idx) {
+            tok.kind = kind;
+        }
+        self.idx += 1;
+    }
+
+    fn bump_name(&mut self, kind: SemanticKind) {
+        if self.is_name() {
+            self.bump(kind);
+        }
+    }
+
+    fn document(&mut self) {
+        while let Some(tok) = self.peek() {
+            match (tok.kind, tok.value) {
+                (Kind::Name, "query") => self.operation(0),
+                (Kind::Name, "mutation") => self.operation(1),
+                (Kind::Name, "subscription") => self.operation(2),
+                (Kind::Name, "fragment") => self.fragment(),
+                (Kind::Punctuator, "{") => {
+                    let root = self.root(0);
+                    self.selection_set(root)
+                }
+                _ => self.idx += 1,
+            }
+        }
+    }
+
+    fn root(&self, kind: usize) -> Option<&'d str> {
+        self.schema
+            .as_ref()
+            .and_then(|s| s.composite(s.roots[kind]))
+    }
+
+    fn operation(&mut self, kind: usize) {
+        self.bump(SemanticKind::Keyword);
+        self.bump_name(SemanticKind::OperationName);
+        if self.is_punct("(") {
+            self.variable_definitions();
+        }
+        self.directives();
+        let root = self.root(kind);
+        self.selection_set(root);
+    }
+
+    fn fragment(&mut self) {
+        self.bump(SemanticKind::Keyword);
+        self.bump_name(SemanticKind::FragmentName);
+        let parent = self.type_condition();
+        self.directives();
+        self.selection_set(parent);
+    }
+
+    fn type_condition(&mut self) -> Option<&'d str> {
+        if !self.is(Kind::Name, "on") {
+            return None;
+        }
+        self.bump(SemanticKind::Keyword);
+        let name = self.peek().filter(|t| t.kind == Kind::Name)?.value;
+        self.bump(SemanticKind::Type);
+        self.schema.as_ref().and_then(|s| s.composite(name))
+    }
+
+    fn variable_definitions(&mut self) {
+        self.bump(SemanticKind::Punctuator);
+        while let Some(tok) = self.peek() {
+            match (tok.kind, tok.value) {
+                (Kind::Punctuator, ")") => {
+                    self.idx += 1;
+                    return;
+                }
+                (Kind::Punctuator, "$") => {
+                    self.idx += 1;
+                    self.bump_name(SemanticKind::Variable);
+                }
+                (Kind::Punctuator, ":") => {
+                    self.idx += 1;
+                    self.type_reference();
+                }
+                (Kind::Punctuator, "=") => {
+                    self.idx += 1;
+                    self.value();
+                }
+                (Kind::Punctuator, "@") => self.directives(),
+                (Kind::Punctuator, "{") => return,
+                _ => self.idx += 1,
+            }
+        }
+    }
+
+    fn type_reference(&mut self) {
+        while self.is_punct("[") {
+            self.idx += 1;
+        }
+        self.bump_name(SemanticKind::Type);
+        while self.is_punct("]") || self.is_punct("!") {
+            self.idx += 1;
+        }
+    }
+
+    fn directives(&mut self) {
+        while self.is_punct("@") {
+            self.idx += 1;
+            self.bump_name(SemanticKind::Directive);
+            if self.is_punct("(") {
+                self.arguments();
+            }
+        }
+    }
+
+    fn arguments(&mut self) {
+        self.idx += 1;
+        while let Some(tok) = self.peek() {
+            match (tok.kind, tok.value) {
+                (Kind::Punctuator, ")") => {
+                    self.idx += 1;
+                    return;
+                }
+                (Kind::Name, _) => {
+                    self.bump(SemanticKind::Argument);
+                    if self.is_punct(":") {
+                        self.idx += 1;
+                        self.value();
+                    }
+                }
+                (Kind::Punctuator, "{") | (Kind::Punctuator, "}") => return,
+                _ => self.idx += 1,
+            }
+        }
+    }
+
+    fn value(&mut self) {
+        let tok = match self.peek() {
+            Some(tok) => tok,
+            None => return,
+        };
+        match (tok.kind, tok.value) {
+            (Kind::Punctuator, "$") => {
+                self.idx += 1;
+                self.bump_name(SemanticKind::Variable);
+            }
+            (Kind::Punctuator, "[") => {
+                self.idx += 1;
+                while !self.is_punct("]") && self.peek().is_some() {
+                    let before = self.idx;
+                    self.value();
+                    if self.idx == before {
+                        break;
+                    }
+                }
+                if self.is_punct("]") {
+                    self.idx += 1;
+                }
+            }
+            (Kind::Punctuator, "{") => {
+                self.idx += 1;
+                while self.is_name() {
+                    self.bump(SemanticKind::InputField);
+                    if self.is_punct(":") {
+                        self.idx += 1;
+                        self.value();
+                    }
+                }
+                if self.is_punct("}") {
+                    self.idx += 1;
+                }
+            }
+            (Kind::Name, "true") | (Kind::Name, "false") | (Kind::Name, "null") => {
+                self.bump(SemanticKind::Keyword)
+            }
+            (Kind::Name, _) => self.bump(SemanticKind::EnumValue),
+            (Kind::Punctuator, _) => {}
+            _ => self.idx += 1,
+        }
+    }
+
+    fn selection_set(&mut self, parent: Option<&'d str>) {
+        if !self.is_punct("{") {
+            return;
+        }
+        self.idx += 1;
+        while let Some(tok) = self.peek() {
+            match (tok.kind, tok.value) {
+                (Kind::Punctuator, "}") => {
+                    self.idx += 1;
+                    return;
+                }
+                (Kind::Punctuator, "...") => {
+                    self.idx += 1;
+                    if self.is(Kind::Name, "on") {
+                        let cond = self.type_condition();
+                        self.directives();
+                        self.selection_set(cond);
+                    } else if self.is_name() {
+                        self.bump(SemanticKind::FragmentName);
+                        self.directives();
+                    } else {
+                        self.directives();
+                        self.selection_set(parent);
+                    }
+                }
+                (Kind::Name, _) => self.field(parent),
+                _ => self.idx += 1,
+            }
+        }
+    }
+
+    fn field(&mut self, parent: Option<&'d str>) {
+        let is_alias = matches!(
+            self.peek_at(1),
+            Some(t) if t.kind == Kind::Punctuator && t.value == ":"
+        );
+        if is_alias {
+            self.bump(SemanticKind::Alias);
+            self.idx += 1;
+            if !self.is_name() {
+                return;
+            }
+        }
+        let name = self.peek().map_or("", |t| t.value);
+        let mut child = None;
+        if let Some(ref schema) = self.schema {
+            let target = parent
+                .and_then(|p| schema.fields.get(p))
+                .and_then(|fields| fields.get(name));
+            child = target.and_then(|t| schema.composite(t));
+            if let Some(tok) = self.out.get_mut(self.idx) {
+                tok.known = Some(target.is_some() || name == "__typename");
+            }
+        }
+        self.bump(SemanticKind::Field);
+        if self.is_punct("(") {
+            self.arguments();
+        }
+        self.directives();
+        if self.is_punct("{") {
+            self.selection_set(child);
+        }
+    }
+}
+
+/// Splits `source` into tokens classified by their role in the document
+///
+/// The classification is based on a shallow parse which tolerates syntax
+/// errors, so it's suitable for highlighting documents being edited.
+/// Tokenizing stops at the first invalid token. Schema definitions are not
+/// classified beyond the kind of the token.
+///
+/// If `schema` is given, fields are marked as known or unknown depending on
+/// whether they are defined on their parent type.
+pub fn highlight<'a, T>(
+    source: &str,
+    schema: Option<&schema::Document<'a, T>>,
+) -> Vec<SemanticToken>
+where
+    T: Text<'a>,
+{
+    let mut tokens = Vec::new();
+    let mut stream = TokenStream::new(source);
+    loop {
+        let start = stream.position();
+        match stream.uncons() {
+            Ok(tok) => tokens.push((tok, Span::new(start, token_end(start, tok.value)))),
+            Err(_) => break,
+        }
+    }
+    let out = tokens
+        .iter()
+        .map(|&(tok, span)| SemanticToken {
+            span,
+            kind: match tok.kind {
+                Kind::Punctuator => SemanticKind::Punctuator,
+                Kind::Name => SemanticKind::Name,
+                Kind::IntValue | Kind::FloatValue => SemanticKind::Number,
+                Kind::StringValue | Kind::BlockString => SemanticKind::String,
+            },
+            known: None,
+        })
+        .collect();
+    let mut highlighter = Highlighter {
+        tokens,
+        out,
+        idx: 0,
+        schema: schema.map(Schema::new),
+    };
+    highlighter.document();
+    highlighter.out
+}
+
+#[cfg(test)]
+mod test {
+    use super::SemanticKind::*;
+    use super::{highlight, SemanticKind};
+    use crate::node_source;
+    use crate::schema::parse_schema;
+
+    fn kinds(source: &str) -> Vec<(&str, SemanticKind)> {
+        highlight::<&str>(source, None)
+            .into_iter()
+            .map(|t| (node_source(source, t.span), t.kind))
+            .filter(|&(_, kind)| kind != Punctuator)
+            .collect()
+    }
+
+    #[test]
+    fn operation() {
+        assert_eq!(
+            kinds("query Q($id: [ID!] = \"x\") @dir { u: user(id: $id, f: {a: ENUM}) { ...F } }"),
+            vec![
+                ("query", Keyword),
+                ("Q", OperationName),
+                ("id", Variable),
+                ("ID", Type),
+                ("\"x\"", String),
+                ("dir", Directive),
+                ("u", Alias),
+                ("user", Field),
+                ("id", Argument),
+                ("id", Variable),
+                ("f", Argument),
+                ("a", InputField),
+                ("ENUM", EnumValue),
+                ("F", FragmentName),
+            ]
+        );
+    }
+
+    #[test]
+    fn fragments() {
+        assert_eq!(
+            kinds("fragment F on User { ... on Admin @include(if: true) { level } }"),
+            vec![
+                ("fragment", Keyword),
+                ("F", FragmentName),
+                ("on", Keyword),
+                ("User", Type),
+                ("on", Keyword),
+                ("Admin", Type),
+                ("include", Directive),
+                ("if", Argument),
+                ("true", Keyword),
+                ("level", Field),
+            ]
+        );
+    }
+
+    #[test]
+    fn incomplete() {
+        assert_eq!(
+            kinds("{ a(x: 1.5 \n b"),
+            vec![
+                ("a", Field),
+                ("x", Argument),
+                ("1.5", Number),
+                ("b", Argument)
+            ]
+        );
+    }
+
+    #[test]
+    fn known_fields() {
+        let schema = parse_schema::<&str>(
+            "type Query { me: User } type User { name: String friends: [User] }",
+        )
+        .unwrap();
+        let source = "{ me { name age friends { __typename name } } other }";
+        let known: Vec<(&str, Option<bool>)> = highlight(source, Some(&schema))
+            .into_iter()
+            .filter(|t| t.kind == Field)
+            .map(|t| (node_source(source, t.span), t.known))
+            .collect();
+        assert_eq!(
+            known,
+            vec![
+                ("me", Some(true)),
+                ("name", Some(true)),
+                ("age", Some(false)),
+                ("friends", Some(true)),
+                ("__typename", Some(true)),
+                ("name", Some(true)),
+                ("other", Some(false)),
+            ]
+        );
+    }
+}
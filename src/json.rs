@@ -0,0 +1,197 @@
+//! Converting values to and from JSON
+//!
+use serde_json::{Map, Number as JsonNumber, Value as Json};
+use thiserror::Error;
+
+use crate::common::{Int, Number, Text, Value};
+
+/// Error converting a [`Value`] containing a variable to JSON
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("variable ${name} has no value")]
+pub struct UnresolvedVariable {
+    /// Name of the variable, without the `$`
+    pub name: String,
+}
+
+fn big_int_to_json(digits: &str) -> Json {
+    match digits.parse::<u64>() {
+        Ok(u) => Json::Number(u.into()),
+        Err(_) => digits
+            .parse::<f64>()
+            .ok()
+            .and_then(JsonNumber::from_f64)
+            .map_or(Json::Null, Json::Number),
+    }
+}
+
+impl<'a, T: Text<'a>> Value<'a, T> {
+    /// Converts the value to JSON
+    ///
+    /// Enum values become strings, and floats which aren't finite become
+    /// `null`, as JSON can't represent them. Integers too large for both
+    /// `i64` and `u64` become floats, losing precision. Fails if the value
+    /// contains a variable, see [`Value::to_json_with`] to resolve them.
+    pub fn to_json(&self) -> Result<Json, UnresolvedVariable> {
+        self.to_json_with(&mut |_| None)
+    }
+
+    /// Converts the value to JSON, replacing variables with the values
+    /// `variables` returns for their names
+    ///
+    /// Fails at the first variable `variables` returns `None` for.
+    pub fn to_json_with<F>(&self, variables: &mut F) -> Result<Json, UnresolvedVariable>
+    where
+        F: FnMut(&str) -> Option<Json>,
+    {
+        Ok(match self {
+            Value::Variable(name, _) => {
+                variables(name.as_ref()).ok_or_else(|| UnresolvedVariable {
+                    name: name.as_ref().to_string(),
+                })?
+            }
+            Value::Int(n) => match n.as_i64() {
+                Some(i) => Json::Number(i.into()),
+                None => big_int_to_json(&n.to_string()),
+            },
+            Value::Float(f) => JsonNumber::from_f64(*f).map_or(Json::Null, Json::Number),
+            Value::String(s) => Json::String(s.clone()),
+            Value::Boolean(b) => Json::Bool(*b),
+            Value::Null => Json::Null,
+            Value::Enum(e) => Json::String(e.as_ref().to_string()),
+            Value::List(items) => Json::Array(
+                items
+                    .iter()
+                    .map(|item| item.to_json_with(variables))
+                    .collect::<Result<_, _>>()?,
+            ),
+            Value::Object(fields) => {
+                let mut map = Map::new();
+                for (name, value) in fields {
+                    map.insert(name.as_ref().to_string(), value.to_json_with(variables)?);
+                }
+                Json::Object(map)
+            }
+        })
+    }
+}
+
+impl Value<'static, String> {
+    /// Converts a JSON value, such as a variable value, into a value
+    ///
+    /// Integral numbers become [`Value::Int`], including ones written with a
+    /// fraction, like `1.0`, and ones which don't fit in an `i64`. Other
+    /// numbers become [`Value::Float`]. Strings never become enum values.
+    pub fn from_json(json: &Json) -> Self {
+        match json {
+            Json::Null => Value::Null,
+            Json::Bool(b) => Value::Boolean(*b),
+            Json::Number(n) => {
+                if let Some(i) = n.as_i64() {
+                    return Value::Int(Number::from(i));
+                }
+                if let Some(u) = n.as_u64() {
+                    return Value::Int(Number(Int::Big(u.to_string())));
+                }
+                let f = n.as_f64().unwrap_or(f64::NAN);
+                if f.fract() != 0.0 {
+                    Value::Float(f)
+                } else if f >= i64::MIN as f64 && f < i64::MAX as f64 {
+                    Value::Int(Number::from(f as i64))
+                } else {
+                    Value::Int(Number(Int::Big(format!("{:.0}", f))))
+                }
+            }
+            Json::String(s) => Value::String(s.clone()),
+            Json::Array(items) => Value::List(items.iter().map(Value::from_json).collect()),
+            Json::Object(fields) => Value::Object(
+                fields
+                    .iter()
+                    .map(|(name, value)| (name.clone(), Value::from_json(value)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::UnresolvedVariable;
+    use crate::query::{parse_query, Definition, OperationDefinition, Selection, Value};
+
+    fn argument(query: &str) -> Value<'_, &str> {
+        let doc = parse_query::<&str>(query).unwrap();
+        match &doc.definitions[0] {
+            Definition::Operation(OperationDefinition::SelectionSet(set)) => match &set.items[0] {
+                Selection::Field(field) => field.arguments[0].1.clone(),
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn nested() {
+        let value = argument(
+            r#"{ f(x: { a: [1, 2.5, "s", true, null, RED], b: { c: [] }, d: { e: -3 } }) }"#,
+        );
+        assert_eq!(
+            value.to_json().unwrap(),
+            json!({
+                "a": [1, 2.5, "s", true, null, "RED"],
+                "b": { "c": [] },
+                "d": { "e": -3 },
+            })
+        );
+    }
+
+    #[test]
+    fn variables() {
+        let value = argument("{ f(x: [$a, { b: $b }]) }");
+        assert_eq!(
+            value.to_json(),
+            Err(UnresolvedVariable { name: "a".into() })
+        );
+        let json = value.to_json_with(&mut |name| match name {
+            "a" => Some(json!(1)),
+            _ => None,
+        });
+        assert_eq!(json, Err(UnresolvedVariable { name: "b".into() }));
+        let json = value.to_json_with(&mut |name| Some(json!(name)));
+        assert_eq!(json.unwrap(), json!(["a", { "b": "b" }]));
+    }
+
+    #[test]
+    fn from_json() {
+        let json = json!({
+            "a": [1, 2.5, 3.0, "s", true, null, -9007199254740993i64],
+            "b": { "c": [] },
+        });
+        let value = Value::from_json(&json);
+        assert_eq!(
+            value.to_string(),
+            r#"{a: [1, 2.5, 3, "s", true, null, -9007199254740993], b: {c: []}}"#
+        );
+        assert_eq!(
+            value.to_json().unwrap(),
+            json!({
+                "a": [1, 2.5, 3, "s", true, null, -9007199254740993i64],
+                "b": { "c": [] },
+            })
+        );
+        let big = Value::from_json(&json!(u64::MAX));
+        assert_eq!(big.to_string(), "18446744073709551615");
+        assert!(matches!(big, Value::Int(ref n) if n.as_i64().is_none()));
+        assert_eq!(big.to_json().unwrap(), json!(u64::MAX));
+        assert_eq!(Value::from_json(&json!(1e20)).to_string(), "100000000000000000000");
+        assert_eq!(
+            argument("{ f(a: 18446744073709551615) }").to_json().unwrap(),
+            json!(u64::MAX)
+        );
+        assert_eq!(
+            argument("{ f(a: 100000000000000000000) }").to_json().unwrap(),
+            json!(1e20)
+        );
+    }
+}
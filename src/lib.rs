@@ -89,23 +89,61 @@
 //! # }
 //! ```
 //!
+//! Using without `std`
+//! -------------------
+//!
+//! Disabling the default `std` feature makes the crate `no_std`, needing
+//! only `alloc`. What's left are the AST types, with formatting through
+//! `Display` and [`Style`], the visitors, the [`tokenizer`], and
+//! [`minify_query`] and [`schema::minify_schema`]. Parsing, the streaming
+//! writers and the rest of the analysis need `std`, as do the methods
+//! returning hash maps. The `serde` feature works without `std` too.
+//!
+#![cfg_attr(not(feature = "std"), no_std)]
 #![warn(missing_debug_implementations)]
 
+extern crate alloc;
+
 #[cfg(test)]
 #[macro_use]
 extern crate pretty_assertions;
 
 mod common;
+#[cfg(feature = "std")]
+mod embedded;
 #[macro_use]
 mod format;
+mod hash;
+#[cfg(feature = "std")]
 mod helpers;
+#[cfg(feature = "std")]
+mod highlight;
+#[cfg(feature = "json")]
+mod json;
+#[cfg(feature = "std")]
+mod mixed;
+mod options;
 mod position;
 pub mod query;
 pub mod schema;
 pub mod tokenizer;
 
-pub use crate::format::Style;
-pub use crate::position::Pos;
+#[cfg(feature = "std")]
+pub use crate::embedded::{extract_graphql_blocks, TemplateBlock};
+pub use crate::format::{Preset, Style};
+pub use crate::hash::DocumentKey;
+#[cfg(feature = "std")]
+pub use crate::highlight::{highlight, SemanticKind, SemanticToken};
+#[cfg(feature = "json")]
+pub use crate::json::UnresolvedVariable;
+#[cfg(feature = "std")]
+pub use crate::mixed::{parse_mixed_document, MixedDocument};
+pub use crate::options::{ParseLimits, ParseOptions, ParseWarning};
+pub use crate::position::{node_source, Pos, Span};
 pub use crate::query::minify_query;
+#[cfg(feature = "std")]
+pub use crate::query::minify_query_streaming;
+#[cfg(feature = "std")]
 pub use crate::query::parse_query;
+#[cfg(feature = "std")]
 pub use crate::schema::parse_schema;
@@ -0,0 +1,95 @@
+//! Documents mixing executable and type system definitions
+//!
+use combine::{eof, many1, parser, Parser};
+
+use crate::common::Text;
+use crate::query::{self, ParseError};
+use crate::schema;
+use crate::tokenizer::TokenStream;
+
+/// Definitions of a document parsed by [`parse_mixed_document`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct MixedDocument<'a, T: Text<'a>> {
+    /// Operations and fragments
+    pub executable: query::Document<'a, T>,
+    /// Type system definitions and extensions
+    pub type_system: schema::Document<'a, T>,
+}
+
+enum Either<Q, S> {
+    Executable(Q),
+    TypeSystem(S),
+}
+
+/// Parses a document containing both operations and type system definitions
+///
+/// Files with client schema extensions often look like this. Each
+/// definition is put into one of the two documents, which keep the
+/// relative order of their definitions. Unlike
+/// [`parse_query`](crate::parse_query), which rejects type system
+/// definitions, this accepts a document containing only one kind of
+/// definitions too.
+///
+/// This is a function of its own rather than a [`ParseOptions`] setting
+/// because the result has a different type: a
+/// [`query::Document`](crate::query::Document) can't hold type system
+/// definitions. The strict behaviour is what `parse_query` does by default,
+/// failing with "type system definitions are not allowed in executable
+/// documents" at the first such definition.
+///
+/// [`ParseOptions`]: crate::ParseOptions
+pub fn parse_mixed_document<'a, T>(s: &'a str) -> Result<MixedDocument<'a, T>, ParseError>
+where
+    T: Text<'a>,
+{
+    let mut tokens = TokenStream::new(s);
+    let definition = parser(query::definition)
+        .map(Either::Executable)
+        .or(parser(schema::definition).map(Either::TypeSystem));
+    let (definitions, _) = many1::<Vec<_>, _, _>(definition)
+        .skip(eof())
+        .parse_stream(&mut tokens)
+        .into_result()
+        .map_err(|e| e.into_inner().error)?;
+
+    let mut doc = MixedDocument {
+        executable: query::Document {
+            definitions: Vec::new(),
+        },
+        type_system: schema::Document {
+            definitions: Vec::new(),
+        },
+    };
+    for def in definitions {
+        match def {
+            Either::Executable(d) => doc.executable.definitions.push(d),
+            Either::TypeSystem(d) => doc.type_system.definitions.push(d),
+        }
+    }
+    Ok(doc)
+}
+
+#[cfg(test)]
+mod test {
+    use super::parse_mixed_document;
+
+    #[test]
+    fn mixed() {
+        let doc = parse_mixed_document::<&str>(
+            "query { user { id local } }
+             extend type User { local: Boolean }
+             fragment F on User { local }
+             \"Client only\"
+             directive @client on FIELD",
+        )
+        .unwrap();
+        assert_eq!(doc.executable.definitions.len(), 2);
+        assert_eq!(doc.type_system.definitions.len(), 2);
+        assert_eq!(
+            doc.type_system.to_string(),
+            "extend type User {\n  local: Boolean\n}\n\n\"Client only\"\ndirective @client on FIELD\n"
+        );
+        assert!(parse_mixed_document::<&str>("type A { a: Int }").is_ok());
+        assert!(parse_mixed_document::<&str>("query { a } type").is_err());
+    }
+}
@@ -0,0 +1,88 @@
+use alloc::string::String;
+use core::fmt;
+
+use crate::position::Pos;
+
+/// Limits and switches applied while parsing
+///
+/// Use [`parse_query_with_options`](crate::query::parse_query_with_options)
+/// or [`parse_schema_with_options`](crate::schema::parse_schema_with_options)
+/// to parse with non-default options.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct ParseOptions {
+    pub(crate) max_string_length: Option<usize>,
+    pub(crate) single_quoted_strings: bool,
+    pub(crate) limits: ParseLimits,
+}
+
+/// Limits on the size of parsed documents
+///
+/// Nested brackets are parsed recursively, so without a depth limit a
+/// deeply nested document could overflow the stack. Exceeding a limit
+/// fails parsing with a parse error. See
+/// [`parse_query_with_limits`](crate::query::parse_query_with_limits) and
+/// [`ParseOptions::limits`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ParseLimits {
+    /// Maximum number of brackets open at once, counting selection sets,
+    /// arguments, list and object values, and list types, 50 by default
+    pub max_depth: usize,
+    /// Maximum number of tokens in the document, unlimited by default
+    pub max_tokens: Option<usize>,
+}
+
+impl Default for ParseLimits {
+    fn default() -> ParseLimits {
+        ParseLimits {
+            max_depth: 50,
+            max_tokens: None,
+        }
+    }
+}
+
+impl ParseOptions {
+    /// Set the maximum length of a string or block string literal in bytes
+    ///
+    /// The length excludes the quotes. Longer literals are rejected with a
+    /// parse error before they are decoded, so untrusted input can't force
+    /// a large allocation. There is no limit by default.
+    pub fn max_string_length(&mut self, max_string_length: Option<usize>) -> &mut Self {
+        self.max_string_length = max_string_length;
+        self
+    }
+
+    /// Accept strings delimited by single quotes
+    ///
+    /// They are decoded like double-quoted strings, with `\'` escaping a
+    /// quote. Every such string produces a [`ParseWarning`], which is
+    /// returned by
+    /// [`parse_query_with_warnings`](crate::query::parse_query_with_warnings)
+    /// and [`parse_schema_with_warnings`](crate::schema::parse_schema_with_warnings).
+    /// Single-quoted strings are rejected by default, as GraphQL requires.
+    pub fn single_quoted_strings(&mut self, single_quoted_strings: bool) -> &mut Self {
+        self.single_quoted_strings = single_quoted_strings;
+        self
+    }
+
+    /// Set the limits on nesting and the number of tokens
+    ///
+    /// Raising `max_depth` above the default lets untrusted input use more
+    /// stack, so only do that if the thread has enough of it.
+    pub fn limits(&mut self, limits: ParseLimits) -> &mut Self {
+        self.limits = limits;
+        self
+    }
+}
+
+/// Input accepted only because of a lenient [`ParseOptions`] setting
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseWarning {
+    pub position: Pos,
+    pub message: String,
+}
+
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.position, self.message)
+    }
+}
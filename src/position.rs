@@ -1,17 +1,51 @@
-use std::fmt;
+use core::fmt;
 
 /// Original position of element in source code
 #[derive(PartialOrd, Ord, PartialEq, Eq, Clone, Copy, Default, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Pos {
     /// One-based line number
     pub line: usize,
     /// One-based column number
+    ///
+    /// Columns count characters rather than bytes, so multi-byte UTF-8
+    /// characters advance the column by one. A tab advances it by 8, byte
+    /// order marks and carriage returns don't advance it.
     pub column: usize,
+    /// Zero-based byte offset into the source
+    ///
+    /// This can be used to slice the source directly, e.g.
+    /// `&source[pos.offset..]`. Positions serialized before 0.5 don't have
+    /// it and deserialize with an offset of 0.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub offset: usize,
+}
+
+/// Range of source code occupied by an element
+///
+/// `start` points to the first character of the element and `end` points
+/// right past its last character.
+///
+/// Query fields and the schema's type definitions, fields and input values
+/// return their span from a `span()` method. These nodes store the end in
+/// an `end` field next to `position`, and the span starts at `position`:
+/// a field or input value includes its description, a type definition
+/// starts at its keyword. Nodes that weren't created by the parser have an
+/// empty span, usually at `Pos::default()`.
+///
+/// The `end` fields were added in 0.5. Code building these nodes with
+/// struct literals has to set them, `Pos::default()` is fine when there is
+/// no source to point to.
+#[derive(PartialOrd, Ord, PartialEq, Eq, Clone, Copy, Default, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Span {
+    pub start: Pos,
+    pub end: Pos,
 }
 
 impl fmt::Debug for Pos {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Pos({}:{})", self.line, self.column)
+        write!(f, "Pos({}:{}@{})", self.line, self.column, self.offset)
     }
 }
 
@@ -20,3 +54,160 @@ impl fmt::Display for Pos {
         write!(f, "{}:{}", self.line, self.column)
     }
 }
+
+impl Span {
+    pub fn new(start: Pos, end: Pos) -> Span {
+        Span { start, end }
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+/// Converts a byte offset within `source` into a position
+///
+/// Columns are counted the same way the tokenizer counts them: a tab
+/// advances the column by 8, carriage returns and byte order marks do not
+/// advance it at all.
+#[cfg(feature = "std")]
+pub(crate) fn position_at(source: &str, offset: usize) -> Pos {
+    let mut pos = Pos {
+        line: 1,
+        column: 1,
+        offset,
+    };
+    for ch in source[..offset].chars() {
+        match ch {
+            '\n' => {
+                pos.line += 1;
+                pos.column = 1;
+            }
+            '\t' => pos.column += 8,
+            '\r' | '\u{feff}' => {}
+            _ => pos.column += 1,
+        }
+    }
+    pos
+}
+
+/// Formats `message` followed by the line of `source` at `pos` and a
+/// caret under the column
+///
+/// Tabs in the line are expanded to 8 spaces, the width a tab has in
+/// columns, so the caret lines up regardless of the tab stops of the
+/// terminal. A column past the end of the line puts the caret right after
+/// the last character. If `pos` isn't known, only the message is returned.
+#[cfg(feature = "std")]
+pub(crate) fn source_snippet(source: &str, pos: Pos, message: &str) -> String {
+    let message = message.trim_end();
+    if pos.line == 0 {
+        return message.to_string();
+    }
+    let line = source.split('\n').nth(pos.line - 1).unwrap_or("");
+    let mut text = String::with_capacity(line.len());
+    for ch in line.chars() {
+        match ch {
+            '\t' => text.push_str("        "),
+            '\r' | '\u{feff}' => {}
+            _ => text.push(ch),
+        }
+    }
+    let caret = pos.column.saturating_sub(1).min(text.chars().count());
+    let number = pos.line.to_string();
+    format!(
+        "{}\n{} | {}\n{:pad$} | {:caret$}^",
+        message,
+        number,
+        text.trim_end(),
+        "",
+        "",
+        pad = number.len(),
+        caret = caret,
+    )
+}
+
+/// Returns the exact piece of `source` covered by `span`
+///
+/// The `source` must be the same text the node was parsed from. Offsets
+/// past the end of the source are clamped.
+pub fn node_source(source: &str, span: Span) -> &str {
+    let end = span.end.offset.min(source.len());
+    let start = span.start.offset.min(end);
+    &source[start..end]
+}
+
+#[cfg(test)]
+mod test {
+    use super::{node_source, position_at, source_snippet, Pos, Span};
+
+    fn span(source: &str, start: usize, end: usize) -> Span {
+        Span::new(position_at(source, start), position_at(source, end))
+    }
+
+    #[test]
+    fn single_line() {
+        let src = "{ a(x: 1) }";
+        assert_eq!(node_source(src, span(src, 2, 9)), "a(x: 1)");
+    }
+
+    #[test]
+    fn multi_line() {
+        let src = "query {\n  a {\n    b\n  }\n}\n";
+        assert_eq!(node_source(src, span(src, 10, 23)), "a {\n    b\n  }");
+    }
+
+    #[test]
+    fn multibyte_and_tabs() {
+        let src = "# ключ\n{\tfield(x: \"ü\") }";
+        let start = src.find("field").unwrap();
+        let end = src.rfind(')').unwrap() + 1;
+        assert_eq!(node_source(src, span(src, start, end)), "field(x: \"ü\")");
+    }
+
+    #[test]
+    fn clamped() {
+        let end = Pos {
+            offset: 100,
+            ..Pos::default()
+        };
+        assert_eq!(node_source("{ a }", Span::new(position_at("{ a }", 2), end)), "a }");
+    }
+
+    #[test]
+    fn debug() {
+        assert_eq!(format!("{:?}", position_at("{\n  a", 4)), "Pos(2:3@4)");
+    }
+
+    #[test]
+    fn offsets_to_positions() {
+        let src = "a\r\n\tb\u{e9}c";
+        let pos = position_at(src, src.len());
+        assert_eq!((pos.line, pos.column, pos.offset), (2, 12, src.len()));
+        assert_eq!(position_at(src, 0), Pos { line: 1, column: 1, offset: 0 });
+    }
+
+    #[test]
+    fn snippets() {
+        let pos = |line, column| Pos {
+            line,
+            column,
+            offset: 0,
+        };
+        assert_eq!(
+            source_snippet("{\n  a(x: )\n}", pos(2, 8), "error\n"),
+            "error\n2 |   a(x: )\n  |        ^"
+        );
+        assert_eq!(
+            source_snippet("{\n\ta(x: )\n}", pos(2, 14), "error"),
+            "error\n2 |         a(x: )\n  |              ^"
+        );
+        assert_eq!(
+            source_snippet("{ a", pos(1, 10), "error"),
+            "error\n1 | { a\n  |    ^"
+        );
+        assert_eq!(source_snippet("{ a", Pos::default(), "error"), "error");
+    }
+}
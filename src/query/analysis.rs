@@ -0,0 +1,787 @@
+//! Helpers answering questions about operations
+//!
+use std::collections::{HashMap, HashSet};
+
+use crate::common::Text;
+use crate::position::Pos;
+use crate::query::ast::*;
+use crate::query::validate::selection_directives;
+use crate::query::visit::{walk_document, Visitor};
+use crate::schema;
+
+/// Returns `true` if the result of the operation may be cached
+///
+/// Only queries are cacheable, and only if neither the operation nor any
+/// of its selections uses one of the `side_effecting` directives. Note that
+/// selections of fragments spread into the operation are not checked.
+pub fn is_cacheable<'a, T>(op: &OperationDefinition<'a, T>, side_effecting: &[&str]) -> bool
+where
+    T: Text<'a>,
+{
+    let (directives, selection_set) = match op {
+        OperationDefinition::SelectionSet(s) => (&[][..], s),
+        OperationDefinition::Query(q) => (&q.directives[..], &q.selection_set),
+        OperationDefinition::Mutation(_) | OperationDefinition::Subscription(_) => return false,
+    };
+    let is_safe = |dirs: &[Directive<'a, T>]| {
+        dirs.iter()
+            .all(|d| !side_effecting.contains(&d.name.as_ref()))
+    };
+    let mut safe = is_safe(directives);
+    selection_directives(selection_set, &mut |dirs| safe &= is_safe(dirs));
+    safe
+}
+
+/// Returns the type of field `name` of the composite type `parent`
+pub(super) fn field_type<'s, 'a, T>(
+    schema: &'s schema::Document<'a, T>,
+    parent: &str,
+    name: &str,
+) -> Option<&'s Type<'a, T>>
+where
+    T: Text<'a>,
+{
+    use crate::schema::{TypeDefinition as D, TypeExtension as E};
+
+    let view = schema.find_type(parent)?;
+    let definition = view.definition.into_iter().flat_map(|def| match def {
+        D::Object(o) => &o.fields[..],
+        D::Interface(i) => &i.fields[..],
+        _ => &[],
+    });
+    let extensions = view.extensions.into_iter().flat_map(|ext| match ext {
+        E::Object(o) => &o.fields[..],
+        E::Interface(i) => &i.fields[..],
+        _ => &[],
+    });
+    definition
+        .chain(extensions)
+        .find(|f| f.name.as_ref() == name)
+        .map(|f| &f.field_type)
+}
+
+/// Returns enum and scalar types of the leaf fields selected by `op`
+///
+/// Fields are resolved against `schema`, fragments spread into the
+/// operation are looked up in `fragments`, which is usually the result of
+/// [`Document::fragments`]. Only types defined in the schema are returned,
+/// so built-in scalars are not included unless the schema declares them.
+/// Fields which can't be resolved are skipped.
+pub fn leaf_types<'a, T>(
+    op: &OperationDefinition<'a, T>,
+    schema: &schema::Document<'a, T>,
+    fragments: &HashMap<&str, &FragmentDefinition<'a, T>>,
+) -> HashSet<T>
+where
+    T: Text<'a>,
+{
+    struct Collector<'s, 'f, 'a, T: Text<'a>> {
+        schema: &'s schema::Document<'a, T>,
+        fragments: &'f HashMap<&'f str, &'f FragmentDefinition<'a, T>>,
+        visited: HashSet<&'f str>,
+        result: HashSet<T>,
+    }
+
+    impl<'s, 'f, 'a, T: Text<'a>> Collector<'s, 'f, 'a, T> {
+        fn selection_set(&mut self, parent: &str, set: &SelectionSet<'a, T>) {
+            for item in &set.items {
+                match item {
+                    Selection::Field(field) => {
+                        let typ = match field_type(self.schema, parent, field.name.as_ref()) {
+                            Some(typ) => typ.base_name(),
+                            None => continue,
+                        };
+                        let leaf = self
+                            .schema
+                            .find_type(typ.as_ref())
+                            .and_then(|view| match view.definition? {
+                                schema::TypeDefinition::Scalar(s) => Some(&s.name),
+                                schema::TypeDefinition::Enum(e) => Some(&e.name),
+                                _ => None,
+                            });
+                        match leaf {
+                            Some(name) => {
+                                self.result.insert(name.clone());
+                            }
+                            None => self.selection_set(typ.as_ref(), &field.selection_set),
+                        }
+                    }
+                    Selection::InlineFragment(frag) => {
+                        let parent = match frag.type_condition {
+                            Some(TypeCondition::On(ref cond, _)) => cond.as_ref(),
+                            None => parent,
+                        };
+                        self.selection_set(parent, &frag.selection_set);
+                    }
+                    Selection::FragmentSpread(spread) => {
+                        let frag = match self.fragments.get_key_value(spread.fragment_name.as_ref())
+                        {
+                            Some((&name, &frag)) if self.visited.insert(name) => frag,
+                            _ => continue,
+                        };
+                        let TypeCondition::On(ref cond, _) = frag.type_condition;
+                        self.selection_set(cond.as_ref(), &frag.selection_set);
+                    }
+                }
+            }
+        }
+    }
+
+    let root = match schema.root_type_name(op.operation_type()) {
+        Some(root) => root,
+        None => return HashSet::new(),
+    };
+    let mut collector = Collector {
+        schema,
+        fragments,
+        visited: HashSet::new(),
+        result: HashSet::new(),
+    };
+    collector.selection_set(root, op.selection_set());
+    collector.result
+}
+
+/// Calls `f` with the position and the number of selected fields of every
+/// selection set in `op`
+///
+/// Fields of inline fragments and fragment spreads are counted as part of
+/// the enclosing selection set, since they end up in the same response
+/// object. Recursive fragment spreads are skipped. Selection sets within a
+/// fragment are reported once, however many times the fragment is spread.
+pub(super) fn selection_breadths<'a, T, F>(
+    op: &OperationDefinition<'a, T>,
+    fragments: &HashMap<&str, &FragmentDefinition<'a, T>>,
+    f: F,
+) where
+    T: Text<'a>,
+    F: FnMut(Pos, usize),
+{
+    struct Counter<'f, 'a, T: Text<'a>, F> {
+        fragments: &'f HashMap<&'f str, &'f FragmentDefinition<'a, T>>,
+        /// Breadths of fragments counted so far
+        known: HashMap<&'f str, usize>,
+        path: Vec<&'f str>,
+        f: F,
+    }
+
+    impl<'f, 'a, T: Text<'a>, F: FnMut(Pos, usize)> Counter<'f, 'a, T, F> {
+        fn visit(&mut self, set: &SelectionSet<'a, T>) {
+            let count = self.count(set);
+            (self.f)(set.span.0, count);
+        }
+
+        fn count(&mut self, set: &SelectionSet<'a, T>) -> usize {
+            let mut count = 0usize;
+            for item in &set.items {
+                match item {
+                    Selection::Field(field) => {
+                        count = count.saturating_add(1);
+                        if !field.selection_set.items.is_empty() {
+                            self.visit(&field.selection_set);
+                        }
+                    }
+                    Selection::InlineFragment(frag) => {
+                        count = count.saturating_add(self.count(&frag.selection_set))
+                    }
+                    Selection::FragmentSpread(spread) => {
+                        let name = spread.fragment_name.as_ref();
+                        let (name, frag) = match self.fragments.get_key_value(name) {
+                            Some((&name, &frag)) if !self.path.contains(&name) => (name, frag),
+                            _ => continue,
+                        };
+                        if let Some(&known) = self.known.get(name) {
+                            count = count.saturating_add(known);
+                            continue;
+                        }
+                        self.path.push(name);
+                        let breadth = self.count(&frag.selection_set);
+                        self.path.pop();
+                        self.known.insert(name, breadth);
+                        count = count.saturating_add(breadth);
+                    }
+                }
+            }
+            count
+        }
+    }
+
+    Counter {
+        fragments,
+        known: HashMap::new(),
+        path: Vec::new(),
+        f,
+    }
+    .visit(op.selection_set());
+}
+
+/// Returns the largest number of fields selected in a single selection set
+/// of `op`
+///
+/// Aliased selections of the same field count separately. Fields selected
+/// within fragments count towards the selection set the fragment is used
+/// in, fragments are looked up in `fragments`, which is usually the result
+/// of [`Document::fragments`].
+pub fn max_breadth<'a, T>(
+    op: &OperationDefinition<'a, T>,
+    fragments: &HashMap<&str, &FragmentDefinition<'a, T>>,
+) -> usize
+where
+    T: Text<'a>,
+{
+    let mut max = 0;
+    selection_breadths(op, fragments, |_, count| max = max.max(count));
+    max
+}
+
+/// Depth and number of fields of selection sets, see [`depth`] and
+/// [`field_count`]
+struct Size<'f, 'a, T: Text<'a>> {
+    fragments: Option<&'f HashMap<&'f str, &'f FragmentDefinition<'a, T>>>,
+    /// Sizes of fragments measured so far, so spreading a fragment many
+    /// times doesn't measure it again
+    known: HashMap<&'f str, (usize, usize)>,
+    path: Vec<&'f str>,
+}
+
+impl<'f, 'a, T: Text<'a>> Size<'f, 'a, T> {
+    fn new(fragments: Option<&'f HashMap<&'f str, &'f FragmentDefinition<'a, T>>>) -> Self {
+        Size {
+            fragments,
+            known: HashMap::new(),
+            path: Vec::new(),
+        }
+    }
+
+    /// Returns the depth and the number of fields of `set`
+    fn measure(&mut self, set: &SelectionSet<'a, T>) -> (usize, usize) {
+        let (mut depth, mut fields) = (0, 0usize);
+        for item in &set.items {
+            let (item_depth, item_fields) = match item {
+                Selection::Field(field) => {
+                    let (sub_depth, sub_fields) = self.measure(&field.selection_set);
+                    (sub_depth + 1, sub_fields.saturating_add(1))
+                }
+                Selection::InlineFragment(frag) => self.measure(&frag.selection_set),
+                Selection::FragmentSpread(spread) => self.spread(spread.fragment_name.as_ref()),
+            };
+            depth = depth.max(item_depth);
+            fields = fields.saturating_add(item_fields);
+        }
+        (depth, fields)
+    }
+
+    fn spread(&mut self, name: &str) -> (usize, usize) {
+        let (name, frag) = match self.fragments.and_then(|f| f.get_key_value(name)) {
+            Some((&name, &frag)) if !self.path.contains(&name) => (name, frag),
+            _ => return (0, 0),
+        };
+        if let Some(&size) = self.known.get(name) {
+            return size;
+        }
+        self.path.push(name);
+        let size = self.measure(&frag.selection_set);
+        self.path.pop();
+        self.known.insert(name, size);
+        size
+    }
+}
+
+/// Returns the largest number of nested selection sets in `op`
+///
+/// `{ a }` has a depth of 1 and `{ a { b } }` a depth of 2. Inline
+/// fragments don't add to the depth, as their fields are part of the
+/// enclosing selection set. Fragment spreads are ignored, see
+/// [`depth_with_fragments`] to follow them.
+pub fn depth<'a, T>(op: &OperationDefinition<'a, T>) -> usize
+where
+    T: Text<'a>,
+{
+    Size::new(None).measure(op.selection_set()).0
+}
+
+/// Returns the number of fields selected by `op`, at any depth
+///
+/// Fields of inline fragments are counted, fragment spreads are ignored,
+/// see [`field_count_with_fragments`] to follow them. Aliased selections
+/// of the same field count separately.
+pub fn field_count<'a, T>(op: &OperationDefinition<'a, T>) -> usize
+where
+    T: Text<'a>,
+{
+    Size::new(None).measure(op.selection_set()).1
+}
+
+/// Like [`depth`], but includes the selections of spread fragments
+///
+/// Fragments are looked up in `fragments`, which is usually the result of
+/// [`Document::fragments`]. Undefined and recursive spreads are skipped.
+pub fn depth_with_fragments<'a, T>(
+    op: &OperationDefinition<'a, T>,
+    fragments: &HashMap<&str, &FragmentDefinition<'a, T>>,
+) -> usize
+where
+    T: Text<'a>,
+{
+    Size::new(Some(fragments)).measure(op.selection_set()).0
+}
+
+/// Like [`field_count`], but includes the fields of spread fragments
+///
+/// Fields of a fragment count once per spread, as they appear in the
+/// response once per spread, so the result can be much larger than the
+/// document. It saturates at `usize::MAX`. Fragments are looked up like
+/// in [`depth_with_fragments`].
+pub fn field_count_with_fragments<'a, T>(
+    op: &OperationDefinition<'a, T>,
+    fragments: &HashMap<&str, &FragmentDefinition<'a, T>>,
+) -> usize
+where
+    T: Text<'a>,
+{
+    Size::new(Some(fragments)).measure(op.selection_set()).1
+}
+
+/// Returns fragment spreads whose fragment isn't defined in `doc`
+///
+/// Each spread is reported with the name of the fragment and the position
+/// of the spread, in document order, so a fragment spread several times is
+/// listed once per spread.
+pub fn undefined_fragment_spreads<'a, T>(doc: &Document<'a, T>) -> Vec<(T, Pos)>
+where
+    T: Text<'a>,
+{
+    struct Spreads<'f, 'a, T: Text<'a>> {
+        fragments: HashMap<&'f str, &'f FragmentDefinition<'a, T>>,
+        undefined: Vec<(T, Pos)>,
+    }
+
+    impl<'f, 'a, T: Text<'a>> Visitor<'a, T> for Spreads<'f, 'a, T> {
+        fn visit_fragment_spread(&mut self, spread: &FragmentSpread<'a, T>) {
+            if !self.fragments.contains_key(spread.fragment_name.as_ref()) {
+                self.undefined
+                    .push((spread.fragment_name.clone(), spread.position));
+            }
+        }
+    }
+
+    let mut spreads = Spreads {
+        fragments: doc.fragments(),
+        undefined: Vec::new(),
+    };
+    walk_document(&mut spreads, doc);
+    spreads.undefined
+}
+
+/// Returns the names of the variables used by `op`, without the `$`
+///
+/// Arguments of fields and directives are searched recursively, including
+/// lists and input objects. Fragments spread into the operation are looked
+/// up in `fragments`, which is usually the result of
+/// [`Document::fragments`], so variables used only within fragments are
+/// found too. Each variable is listed once, in the order it is first used.
+pub fn collect_variables<'v, 'a, T>(
+    op: &'v OperationDefinition<'a, T>,
+    fragments: &HashMap<&str, &'v FragmentDefinition<'a, T>>,
+) -> Vec<&'v str>
+where
+    T: Text<'a>,
+{
+    struct Collector<'v, 'm, 'a, T: Text<'a>> {
+        fragments: &'m HashMap<&'m str, &'v FragmentDefinition<'a, T>>,
+        visited: HashSet<&'v str>,
+        seen: HashSet<&'v str>,
+        result: Vec<&'v str>,
+    }
+
+    impl<'v, 'm, 'a, T: Text<'a>> Collector<'v, 'm, 'a, T> {
+        fn value(&mut self, value: &'v Value<'a, T>) {
+            match value {
+                Value::Variable(name, _) if self.seen.insert(name.as_ref()) => {
+                    self.result.push(name.as_ref());
+                }
+                Value::List(items) => items.iter().for_each(|v| self.value(v)),
+                Value::Object(fields) => fields.values().for_each(|v| self.value(v)),
+                _ => {}
+            }
+        }
+
+        fn directives(&mut self, directives: &'v [Directive<'a, T>]) {
+            for directive in directives {
+                directive.arguments.iter().for_each(|(_, v)| self.value(v));
+            }
+        }
+
+        fn selection_set(&mut self, set: &'v SelectionSet<'a, T>) {
+            for item in &set.items {
+                match item {
+                    Selection::Field(field) => {
+                        field.arguments.iter().for_each(|(_, v)| self.value(v));
+                        self.directives(&field.directives);
+                        self.selection_set(&field.selection_set);
+                    }
+                    Selection::InlineFragment(frag) => {
+                        self.directives(&frag.directives);
+                        self.selection_set(&frag.selection_set);
+                    }
+                    Selection::FragmentSpread(spread) => {
+                        self.directives(&spread.directives);
+                        let frag = match self.fragments.get(spread.fragment_name.as_ref()) {
+                            Some(&frag) if self.visited.insert(frag.name.as_ref()) => frag,
+                            _ => continue,
+                        };
+                        self.directives(&frag.directives);
+                        self.selection_set(&frag.selection_set);
+                    }
+                }
+            }
+        }
+    }
+
+    let mut collector = Collector {
+        fragments,
+        visited: HashSet::new(),
+        seen: HashSet::new(),
+        result: Vec::new(),
+    };
+    let directives = match op {
+        OperationDefinition::SelectionSet(_) => &[][..],
+        OperationDefinition::Query(q) => &q.directives[..],
+        OperationDefinition::Mutation(m) => &m.directives[..],
+        OperationDefinition::Subscription(s) => &s.directives[..],
+    };
+    collector.directives(directives);
+    collector.selection_set(op.selection_set());
+    collector.result
+}
+
+/// Names of the fragments spread by operations and by each fragment
+#[derive(Default)]
+struct Spreads {
+    /// Fragment being visited, `None` within operations
+    fragment: Option<String>,
+    from_operations: Vec<String>,
+    from_fragments: HashMap<String, Vec<String>>,
+}
+
+impl<'a, T: Text<'a>> Visitor<'a, T> for Spreads {
+    fn enter_fragment_definition(&mut self, fragment: &FragmentDefinition<'a, T>) {
+        self.fragment = Some(fragment.name.as_ref().to_string());
+    }
+    fn leave_fragment_definition(&mut self, _: &FragmentDefinition<'a, T>) {
+        self.fragment = None;
+    }
+    fn visit_fragment_spread(&mut self, spread: &FragmentSpread<'a, T>) {
+        let name = spread.fragment_name.as_ref().to_string();
+        match &self.fragment {
+            Some(fragment) => self
+                .from_fragments
+                .entry(fragment.clone())
+                .or_default()
+                .push(name),
+            None => self.from_operations.push(name),
+        }
+    }
+}
+
+/// Returns the names of fragments which no operation uses, in document
+/// order
+///
+/// A fragment is used if an operation spreads it, directly or through
+/// other fragments. Fragments spread only by unused fragments, including
+/// ones spreading each other, are unused too.
+pub fn unused_fragments<'d, 'a, T>(doc: &'d Document<'a, T>) -> Vec<&'d str>
+where
+    T: Text<'a>,
+{
+    let mut spreads = Spreads::default();
+    walk_document(&mut spreads, doc);
+    let mut used = HashSet::new();
+    let mut queue = spreads.from_operations;
+    while let Some(name) = queue.pop() {
+        if let Some(next) = spreads.from_fragments.get(&name) {
+            if !used.contains(&name) {
+                queue.extend(next.iter().cloned());
+            }
+        }
+        used.insert(name);
+    }
+    doc.definitions
+        .iter()
+        .filter_map(|def| match def {
+            Definition::Fragment(frag) if !used.contains(frag.name.as_ref()) => {
+                Some(frag.name.as_ref())
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Returns the cycles formed by fragments spreading each other
+///
+/// Each cycle is the path of fragment names from a fragment back to itself,
+/// e.g. `["A", "B", "A"]` if `A` spreads `B` and `B` spreads `A`. Spreads
+/// are followed depth first, starting from fragments in document order, and
+/// a cycle is reported for every spread leading back to a fragment on the
+/// current path. So the result is empty if and only if there are no
+/// cycles, but cycles sharing fragments with a reported one may be left
+/// out. Spreads of undefined fragments are ignored.
+pub fn fragment_cycles<'a, T>(doc: &Document<'a, T>) -> Vec<Vec<String>>
+where
+    T: Text<'a>,
+{
+    enum State {
+        OnPath,
+        Done,
+    }
+
+    fn search<'s>(
+        name: &'s str,
+        spreads: &'s HashMap<String, Vec<String>>,
+        states: &mut HashMap<&'s str, State>,
+        path: &mut Vec<&'s str>,
+        cycles: &mut Vec<Vec<String>>,
+    ) {
+        states.insert(name, State::OnPath);
+        path.push(name);
+        for next in spreads.get(name).into_iter().flatten() {
+            match states.get(next.as_str()) {
+                Some(State::OnPath) => {
+                    let start = path.iter().position(|&f| f == next).unwrap_or(0);
+                    let mut cycle = path[start..]
+                        .iter()
+                        .map(|f| f.to_string())
+                        .collect::<Vec<_>>();
+                    cycle.push(next.clone());
+                    cycles.push(cycle);
+                }
+                Some(State::Done) => {}
+                None => search(next, spreads, states, path, cycles),
+            }
+        }
+        path.pop();
+        states.insert(name, State::Done);
+    }
+
+    let mut spreads = Spreads::default();
+    walk_document(&mut spreads, doc);
+    let mut states = HashMap::new();
+    let mut cycles = Vec::new();
+    for def in &doc.definitions {
+        if let Definition::Fragment(frag) = def {
+            let name = frag.name.as_ref();
+            if let Some((name, _)) = spreads.from_fragments.get_key_value(name) {
+                if !states.contains_key(name.as_str()) {
+                    search(
+                        name,
+                        &spreads.from_fragments,
+                        &mut states,
+                        &mut Vec::new(),
+                        &mut cycles,
+                    );
+                }
+            }
+        }
+    }
+    cycles
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        collect_variables, depth, depth_with_fragments, field_count, field_count_with_fragments,
+        fragment_cycles, is_cacheable, leaf_types, max_breadth, undefined_fragment_spreads,
+        unused_fragments,
+    };
+    use crate::query::{parse_query, Definition};
+    use crate::schema::parse_schema;
+
+    fn cacheable(s: &str) -> bool {
+        let doc = parse_query::<&str>(s).unwrap();
+        match doc.definitions[0] {
+            Definition::Operation(ref op) => is_cacheable(op, &["live", "audit"]),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn operation_kinds() {
+        assert!(cacheable("{ a }"));
+        assert!(cacheable("query Q @cached { a }"));
+        assert!(!cacheable("mutation { a }"));
+        assert!(!cacheable("subscription { a }"));
+    }
+
+    #[test]
+    fn side_effecting_directives() {
+        assert!(!cacheable("query @live { a }"));
+        assert!(!cacheable("{ a { ... on B { c @audit } } }"));
+        assert!(cacheable("{ a @include(if: true) { b } }"));
+    }
+
+    #[test]
+    fn leaf_types_through_fragments() {
+        let schema = parse_schema::<&str>(
+            "scalar DateTime
+             enum Role { ADMIN USER }
+             enum Unused { A }
+             type Query { me: User node: Node }
+             interface Node { id: ID! }
+             type User implements Node { id: ID! role: Role friends: [User!] }
+             extend type User { born: DateTime name: String }",
+        )
+        .unwrap();
+        let doc = parse_query::<&str>(
+            "query { me { id ...F friends { ...F } } node { ... on User { born } } }
+             fragment F on User { role name ...F }",
+        )
+        .unwrap();
+        let op = match doc.definitions[0] {
+            Definition::Operation(ref op) => op,
+            _ => unreachable!(),
+        };
+        let mut types: Vec<&str> = leaf_types(op, &schema, &doc.fragments())
+            .into_iter()
+            .collect();
+        types.sort_unstable();
+        assert_eq!(types, vec!["DateTime", "Role"]);
+    }
+
+    #[test]
+    fn breadth_through_fragments() {
+        let doc = parse_query::<&str>(
+            "query { a b { c d e } ... on Query { f ...F } }
+             fragment F on Query { g: a h: a b { ...F } }",
+        )
+        .unwrap();
+        let op = match doc.definitions[0] {
+            Definition::Operation(ref op) => op,
+            _ => unreachable!(),
+        };
+        assert_eq!(max_breadth(op, &doc.fragments()), 6);
+        assert_eq!(max_breadth(op, &Default::default()), 3);
+
+        // Every fragment spreads the next one twice, so walking them without
+        // remembering their breadth takes 2^40 steps
+        let mut source = String::from("{ ...F0 }");
+        for i in 0..40 {
+            source.push_str(&format!(" fragment F{} on Q {{ a ...F{1} b: a ...F{1} }}", i, i + 1));
+        }
+        source.push_str(" fragment F40 on Q { a }");
+        let doc = parse_query::<&str>(&source).unwrap();
+        let op = match doc.definitions[0] {
+            Definition::Operation(ref op) => op,
+            _ => unreachable!(),
+        };
+        assert_eq!(max_breadth(op, &doc.fragments()), 3 * usize::pow(2, 40) - 2);
+    }
+
+    #[test]
+    fn depth_and_field_count() {
+        let doc = parse_query::<&str>(
+            "query { a b { c ... on B { d { e } } ...F } }
+             fragment F on B { f { g { h } } x: f { g { h } } ...G ...F }
+             fragment G on B { i }",
+        )
+        .unwrap();
+        let op = match doc.definitions[0] {
+            Definition::Operation(ref op) => op,
+            _ => unreachable!(),
+        };
+        assert_eq!(depth(op), 3);
+        assert_eq!(field_count(op), 5);
+        let fragments = doc.fragments();
+        assert_eq!(depth_with_fragments(op, &fragments), 4);
+        assert_eq!(field_count_with_fragments(op, &fragments), 12);
+
+        let doc = parse_query::<&str>("{ a }").unwrap();
+        let op = match doc.definitions[0] {
+            Definition::Operation(ref op) => op,
+            _ => unreachable!(),
+        };
+        assert_eq!((depth(op), field_count(op)), (1, 1));
+    }
+
+    #[test]
+    fn undefined_fragments() {
+        let doc = parse_query::<&str>(
+            "query { a { ...Missing ...F } ... on Q { ...Other } }
+             fragment F on A { b ...Missing }",
+        )
+        .unwrap();
+        let undefined = undefined_fragment_spreads(&doc)
+            .into_iter()
+            .map(|(name, pos)| (name, pos.line, pos.column))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            undefined,
+            vec![("Missing", 1, 16), ("Other", 1, 45), ("Missing", 2, 37)]
+        );
+        let doc = parse_query::<&str>("{ ...F } fragment F on Q { a }").unwrap();
+        assert!(undefined_fragment_spreads(&doc).is_empty());
+    }
+
+    #[test]
+    fn variables_through_fragments() {
+        let doc = parse_query::<&str>(
+            "query Q($a: Int, $b: Int, $c: [Int], $d: Boolean, $e: Int, $unused: Int) @x(v: $e) {
+               f(x: $a, y: { list: [$c, $a] }) @include(if: $d) { ...F }
+               ... on T @skip(if: $d) { g(x: $a) }
+             }
+             fragment F on T { h(x: $b) ...F ...G }
+             fragment G on T { i(x: $b, y: $c) }",
+        )
+        .unwrap();
+        let op = match doc.definitions[0] {
+            Definition::Operation(ref op) => op,
+            _ => unreachable!(),
+        };
+        assert_eq!(
+            collect_variables(op, &doc.fragments()),
+            vec!["e", "a", "c", "d", "b"]
+        );
+        assert_eq!(
+            collect_variables(op, &Default::default()),
+            vec!["e", "a", "c", "d"]
+        );
+    }
+
+    #[test]
+    fn unused() {
+        let doc = parse_query::<&str>(
+            "fragment B on T { b }
+             query { ...A ... on T { ...C } }
+             fragment A on T { a ...B }
+             fragment C on T { c ...C }
+             fragment D on T { d ...E }
+             fragment E on T { e ...D }
+             fragment F on T { f ...B }",
+        )
+        .unwrap();
+        assert_eq!(unused_fragments(&doc), vec!["D", "E", "F"]);
+        let doc = parse_query::<&str>("fragment A on T { a }").unwrap();
+        assert_eq!(unused_fragments(&doc), vec!["A"]);
+    }
+
+    #[test]
+    fn cycles() {
+        let doc = parse_query::<&str>(
+            "query { ...A }
+             fragment A on T { a ...B ...Missing }
+             fragment B on T { ... on T { b { ...C } } ...A }
+             fragment C on T { c ...A ...C }
+             fragment D on T { ...B }
+             fragment E on T { e }",
+        )
+        .unwrap();
+        assert_eq!(
+            fragment_cycles(&doc),
+            vec![
+                vec!["A", "B", "C", "A"],
+                vec!["C", "C"],
+                vec!["A", "B", "A"],
+            ]
+        );
+        let doc = parse_query::<&str>("fragment A on T { ...B } fragment B on T { b }").unwrap();
+        assert!(fragment_cycles(&doc).is_empty());
+    }
+}
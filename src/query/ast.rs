@@ -5,12 +5,20 @@
 //!
 //! [graphql grammar]: http://facebook.github.io/graphql/October2016/#sec-Appendix-Grammar-Summary
 //!
-pub use crate::common::{Directive, Number, Text, Type, Value};
-use crate::position::Pos;
-use std::marker::PhantomData;
+pub use crate::common::{Comment, Directive, Number, Text, Type, Value};
+use crate::common::{StructuralEq, TextMapper};
+use crate::position::{Pos, Span};
+use crate::schema;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use core::marker::PhantomData;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
 
 /// Root of query data
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Document<'a, T: Text<'a>> {
     pub definitions: Vec<Definition<'a, T>>,
 }
@@ -30,17 +38,274 @@ impl<'a> Document<'a, String> {
         //
         // This is safe because no references are present.
         // Just the PhantomData lifetime reference is transmuted away.
-        unsafe { std::mem::transmute::<_, Document<'static, String>>(self) }
+        unsafe { core::mem::transmute::<_, Document<'static, String>>(self) }
+    }
+}
+
+impl<'a, T: Text<'a>> Document<'a, T> {
+    /// Returns fragment definitions by name
+    ///
+    /// If a fragment is defined more than once, the first definition wins.
+    #[cfg(feature = "std")]
+    pub fn fragments(&self) -> HashMap<&str, &FragmentDefinition<'a, T>> {
+        let mut result = HashMap::new();
+        for def in &self.definitions {
+            if let Definition::Fragment(f) = def {
+                result.entry(f.name.as_ref()).or_insert(f);
+            }
+        }
+        result
+    }
+
+    /// Returns a hash of the document's content, suitable as a cache key
+    ///
+    /// The hash ignores positions and formatting of the source, and is the
+    /// same across processes, platforms and releases of this crate that
+    /// don't change how documents are formatted. Documents differing only
+    /// in things the formatter keeps, such as the order of fields or
+    /// `{ a }` versus `query { a }`, hash differently.
+    ///
+    /// To use documents themselves as keys, wrap them in
+    /// [`DocumentKey`](crate::DocumentKey).
+    pub fn content_hash(&self) -> u64 {
+        crate::hash::content_hash(self)
+    }
+
+    /// Returns mutable references to the types of all variable definitions,
+    /// in source order
+    ///
+    /// See [`schema::Document::types_mut`] for the types of a schema.
+    pub fn types_mut(&mut self) -> impl Iterator<Item = &mut Type<'a, T>> {
+        self.definitions
+            .iter_mut()
+            .filter_map(|def| match def {
+                Definition::Operation(OperationDefinition::Query(q)) => {
+                    Some(&mut q.variable_definitions)
+                }
+                Definition::Operation(OperationDefinition::Mutation(m)) => {
+                    Some(&mut m.variable_definitions)
+                }
+                Definition::Operation(OperationDefinition::Subscription(s)) => {
+                    Some(&mut s.variable_definitions)
+                }
+                Definition::Operation(OperationDefinition::SelectionSet(_))
+                | Definition::Fragment(_) => None,
+            })
+            .flat_map(|vars| vars.iter_mut().map(|v| &mut v.var_type))
+    }
+
+    /// Returns all directives applied anywhere in the document, in source
+    /// order
+    ///
+    /// Directives of operations, fragment definitions, fields, inline
+    /// fragments and fragment spreads are included. See
+    /// [`schema::Document::all_directives`] for the directives of a schema.
+    pub fn all_directives(&self) -> impl Iterator<Item = &Directive<'a, T>> {
+        fn selection_set<'d, 'a, T: Text<'a>>(
+            result: &mut Vec<&'d Directive<'a, T>>,
+            set: &'d SelectionSet<'a, T>,
+        ) {
+            for item in &set.items {
+                match item {
+                    Selection::Field(f) => {
+                        result.extend(&f.directives);
+                        selection_set(result, &f.selection_set);
+                    }
+                    Selection::FragmentSpread(s) => result.extend(&s.directives),
+                    Selection::InlineFragment(f) => {
+                        result.extend(&f.directives);
+                        selection_set(result, &f.selection_set);
+                    }
+                }
+            }
+        }
+
+        let mut result = Vec::new();
+        for def in &self.definitions {
+            let (dirs, set) = match def {
+                Definition::Operation(OperationDefinition::SelectionSet(s)) => (&[][..], s),
+                Definition::Operation(OperationDefinition::Query(q)) => {
+                    (&q.directives[..], &q.selection_set)
+                }
+                Definition::Operation(OperationDefinition::Mutation(m)) => {
+                    (&m.directives[..], &m.selection_set)
+                }
+                Definition::Operation(OperationDefinition::Subscription(s)) => {
+                    (&s.directives[..], &s.selection_set)
+                }
+                Definition::Fragment(f) => (&f.directives[..], &f.selection_set),
+            };
+            result.extend(dirs);
+            selection_set(&mut result, set);
+        }
+        result.into_iter()
+    }
+
+    /// Converts the document to another text type by calling `f` on every
+    /// name in it
+    ///
+    /// Names of operations, fragments, fields, arguments, variables, types
+    /// and enum values are converted, and so are object keys. String
+    /// values and descriptions are `String`s regardless of the text type,
+    /// so they are kept as is. Converting from `&str` to `String` this way
+    /// makes a document independent of the source:
+    ///
+    /// ```rust
+    /// # use graphql_parser::query::{parse_query, Document};
+    /// let owned: Document<'static, String> = {
+    ///     let source = String::from("{ user(id: 1) { name } }");
+    ///     parse_query::<&str>(&source).unwrap().map_text(String::from)
+    /// };
+    /// ```
+    pub fn map_text<'b, U, F>(self, f: F) -> Document<'b, U>
+    where
+        U: Text<'b>,
+        F: FnMut(T) -> U,
+    {
+        let mut mapper = TextMapper::new(f);
+        Document {
+            definitions: self
+                .definitions
+                .into_iter()
+                .map(|def| match def {
+                    Definition::Operation(op) => Definition::Operation(mapper.operation(op)),
+                    Definition::Fragment(frag) => Definition::Fragment(mapper.fragment(frag)),
+                })
+                .collect(),
+        }
+    }
+
+    /// Copies every name into a `String`, making the document independent
+    /// of the source it was parsed from
+    ///
+    /// Unlike [`Document::into_static`], this works for any text type.
+    pub fn into_owned(self) -> Document<'static, String> {
+        self.map_text(|t| t.as_ref().to_string())
+    }
+
+    /// Moves every position in the document `line_delta` lines and
+    /// `offset_delta` bytes further into the source
+    ///
+    /// This turns positions relative to a snippet into positions within a
+    /// larger file the snippet starts at line `line_delta + 1` of. Columns
+    /// are left unchanged, so the snippet should start at the beginning of
+    /// a line. Positions of nodes not created by the parser, which are
+    /// `Pos::default()`, are left unchanged too.
+    pub fn shift_positions(&mut self, line_delta: usize, offset_delta: usize) {
+        for pos in self.positions_mut() {
+            if *pos != Pos::default() {
+                pos.line += line_delta;
+                pos.offset += offset_delta;
+            }
+        }
+    }
+
+    /// Returns `true` if the documents are equal when positions of the
+    /// nodes are ignored
+    ///
+    /// Everything else must match, including the order of definitions,
+    /// fields and arguments.
+    pub fn eq_ignore_positions(&self, other: &Self) -> bool {
+        self.definitions.structural_eq(&other.definitions, false)
+    }
+
+    /// Returns mutable references to all positions in the document
+    fn positions_mut(&mut self) -> Vec<&mut Pos> {
+        fn directives<'d, 'a, T: Text<'a>>(
+            result: &mut Vec<&'d mut Pos>,
+            directives: &'d mut [Directive<'a, T>],
+        ) {
+            result.extend(directives.iter_mut().map(|d| &mut d.position));
+        }
+        fn selection_set<'d, 'a, T: Text<'a>>(
+            result: &mut Vec<&'d mut Pos>,
+            set: &'d mut SelectionSet<'a, T>,
+        ) {
+            result.push(&mut set.span.0);
+            result.push(&mut set.span.1);
+            for item in &mut set.items {
+                match item {
+                    Selection::Field(f) => {
+                        result.push(&mut f.position);
+                        result.push(&mut f.end);
+                        directives(result, &mut f.directives);
+                        selection_set(result, &mut f.selection_set);
+                    }
+                    Selection::FragmentSpread(s) => {
+                        result.push(&mut s.position);
+                        directives(result, &mut s.directives);
+                    }
+                    Selection::InlineFragment(f) => {
+                        result.push(&mut f.position);
+                        directives(result, &mut f.directives);
+                        selection_set(result, &mut f.selection_set);
+                    }
+                }
+            }
+        }
+
+        let mut positions = Vec::new();
+        for def in &mut self.definitions {
+            let (position, variables, dirs, set) = match def {
+                Definition::Operation(OperationDefinition::SelectionSet(s)) => {
+                    selection_set(&mut positions, s);
+                    continue;
+                }
+                Definition::Operation(OperationDefinition::Query(q)) => (
+                    &mut q.position,
+                    &mut q.variable_definitions,
+                    &mut q.directives,
+                    &mut q.selection_set,
+                ),
+                Definition::Operation(OperationDefinition::Mutation(m)) => (
+                    &mut m.position,
+                    &mut m.variable_definitions,
+                    &mut m.directives,
+                    &mut m.selection_set,
+                ),
+                Definition::Operation(OperationDefinition::Subscription(s)) => (
+                    &mut s.position,
+                    &mut s.variable_definitions,
+                    &mut s.directives,
+                    &mut s.selection_set,
+                ),
+                Definition::Fragment(f) => {
+                    positions.push(&mut f.position);
+                    directives(&mut positions, &mut f.directives);
+                    selection_set(&mut positions, &mut f.selection_set);
+                    continue;
+                }
+            };
+            positions.push(position);
+            positions.extend(variables.iter_mut().map(|v| &mut v.position));
+            directives(&mut positions, dirs);
+            selection_set(&mut positions, set);
+        }
+        positions
+    }
+
+    /// Returns names of all operations in source order, `None` for
+    /// anonymous ones
+    pub fn operation_names(&self) -> Vec<Option<&T>> {
+        self.definitions
+            .iter()
+            .filter_map(|def| match def {
+                Definition::Operation(op) => Some(op.name()),
+                Definition::Fragment(_) => None,
+            })
+            .collect()
     }
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Definition<'a, T: Text<'a>> {
     Operation(OperationDefinition<'a, T>),
     Fragment(FragmentDefinition<'a, T>),
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FragmentDefinition<'a, T: Text<'a>> {
     pub position: Pos,
     pub name: T,
@@ -50,6 +315,7 @@ pub struct FragmentDefinition<'a, T: Text<'a>> {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum OperationDefinition<'a, T: Text<'a>> {
     SelectionSet(SelectionSet<'a, T>),
     Query(Query<'a, T>),
@@ -57,7 +323,82 @@ pub enum OperationDefinition<'a, T: Text<'a>> {
     Subscription(Subscription<'a, T>),
 }
 
+impl<'a, T: Text<'a>> OperationDefinition<'a, T> {
+    /// Returns the name of the operation, `None` if it's anonymous
+    pub fn name(&self) -> Option<&T> {
+        match *self {
+            OperationDefinition::SelectionSet(_) => None,
+            OperationDefinition::Query(ref q) => q.name.as_ref(),
+            OperationDefinition::Mutation(ref m) => m.name.as_ref(),
+            OperationDefinition::Subscription(ref s) => s.name.as_ref(),
+        }
+    }
+
+    /// Returns variables declared by the operation
+    ///
+    /// Shorthand `{ ... }` operations can't declare variables, so the result
+    /// is empty for them.
+    pub fn variable_definitions(&self) -> &[VariableDefinition<'a, T>] {
+        match *self {
+            OperationDefinition::SelectionSet(_) => &[],
+            OperationDefinition::Query(ref q) => &q.variable_definitions,
+            OperationDefinition::Mutation(ref m) => &m.variable_definitions,
+            OperationDefinition::Subscription(ref s) => &s.variable_definitions,
+        }
+    }
+
+    /// Returns the kind of the operation, shorthand operations are queries
+    pub fn operation_type(&self) -> schema::OperationType {
+        match *self {
+            OperationDefinition::SelectionSet(_) | OperationDefinition::Query(_) => {
+                schema::OperationType::Query
+            }
+            OperationDefinition::Mutation(_) => schema::OperationType::Mutation,
+            OperationDefinition::Subscription(_) => schema::OperationType::Subscription,
+        }
+    }
+
+    /// Returns the selection set of the operation
+    pub fn selection_set(&self) -> &SelectionSet<'a, T> {
+        match *self {
+            OperationDefinition::SelectionSet(ref s) => s,
+            OperationDefinition::Query(ref q) => &q.selection_set,
+            OperationDefinition::Mutation(ref m) => &m.selection_set,
+            OperationDefinition::Subscription(ref s) => &s.selection_set,
+        }
+    }
+
+    /// Builds an input object type named `name` having a field per variable
+    ///
+    /// Field types and default values are copied from the variable
+    /// definitions, positions of fields point to the respective variables.
+    pub fn variables_as_input_object(&self, name: T) -> schema::InputObjectType<'a, T> {
+        let mut input = schema::InputObjectType::new(name);
+        input.position = match *self {
+            OperationDefinition::SelectionSet(ref s) => s.span.0,
+            OperationDefinition::Query(ref q) => q.position,
+            OperationDefinition::Mutation(ref m) => m.position,
+            OperationDefinition::Subscription(ref s) => s.position,
+        };
+        input.fields = self
+            .variable_definitions()
+            .iter()
+            .map(|var| schema::InputValue {
+                position: var.position,
+                end: Pos::default(),
+                description: None,
+                name: var.name.clone(),
+                value_type: var.var_type.clone(),
+                default_value: var.default_value.clone(),
+                directives: Vec::new(),
+            })
+            .collect();
+        input
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Query<'a, T: Text<'a>> {
     pub position: Pos,
     pub name: Option<T>,
@@ -67,6 +408,7 @@ pub struct Query<'a, T: Text<'a>> {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Mutation<'a, T: Text<'a>> {
     pub position: Pos,
     pub name: Option<T>,
@@ -76,6 +418,7 @@ pub struct Mutation<'a, T: Text<'a>> {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Subscription<'a, T: Text<'a>> {
     pub position: Pos,
     pub name: Option<T>,
@@ -85,12 +428,240 @@ pub struct Subscription<'a, T: Text<'a>> {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SelectionSet<'a, T: Text<'a>> {
     pub span: (Pos, Pos),
     pub items: Vec<Selection<'a, T>>,
 }
 
+impl<'a, T: Text<'a>> SelectionSet<'a, T> {
+    /// Returns `true` if the set directly selects `__typename` without an
+    /// alias
+    ///
+    /// Only fields of the set itself are checked, not the ones within
+    /// fragments, because they may not apply to every object.
+    pub fn has_typename(&self) -> bool {
+        self.items.iter().any(|item| match item {
+            Selection::Field(f) => f.alias.is_none() && f.name.as_ref() == "__typename",
+            _ => false,
+        })
+    }
+
+    /// Adds `__typename` to every nested selection set of a field which
+    /// doesn't select it yet
+    ///
+    /// The set itself is treated as the root of an operation, which always
+    /// has a known type, so it's left as is. Selection sets of inline
+    /// fragments are not modified either, since the enclosing selection set
+    /// already gets `__typename`, but fields within them are. Fields without
+    /// sub-selections (leaf fields) are left as is.
+    pub fn inject_typename(&mut self) {
+        for item in &mut self.items {
+            match item {
+                Selection::Field(f) if !f.selection_set.items.is_empty() => {
+                    f.selection_set.push_typename();
+                    f.selection_set.inject_typename();
+                }
+                Selection::Field(_) | Selection::FragmentSpread(_) => {}
+                Selection::InlineFragment(f) => f.selection_set.inject_typename(),
+            }
+        }
+    }
+
+    /// Merges inline fragments with the same type condition and directives
+    ///
+    /// Selections of every such fragment are appended to the first of them,
+    /// the rest are removed. Directives are compared by name and arguments,
+    /// ignoring their positions. Nested selection sets are merged too.
+    pub fn merge_inline_fragments(&mut self) {
+        let mut items: Vec<Selection<'a, T>> = Vec::with_capacity(self.items.len());
+        for item in self.items.drain(..) {
+            if let Selection::InlineFragment(frag) = item {
+                let existing = items.iter_mut().find_map(|other| match other {
+                    Selection::InlineFragment(other)
+                        if other.type_condition == frag.type_condition
+                            && same_directives(&other.directives, &frag.directives) =>
+                    {
+                        Some(other)
+                    }
+                    _ => None,
+                });
+                match existing {
+                    Some(other) => other.selection_set.items.extend(frag.selection_set.items),
+                    None => items.push(Selection::InlineFragment(frag)),
+                }
+            } else {
+                items.push(item);
+            }
+        }
+        for item in &mut items {
+            match item {
+                Selection::Field(f) => f.selection_set.merge_inline_fragments(),
+                Selection::InlineFragment(f) => f.selection_set.merge_inline_fragments(),
+                Selection::FragmentSpread(_) => {}
+            }
+        }
+        self.items = items;
+    }
+
+    fn push_typename(&mut self) {
+        if self.has_typename() {
+            return;
+        }
+        self.items.push(Selection::Field(Field {
+            position: self.span.0,
+            end: self.span.0,
+            alias: None,
+            name: T::from("__typename"),
+            arguments: Vec::new(),
+            directives: Vec::new(),
+            selection_set: SelectionSet {
+                span: (self.span.0, self.span.0),
+                items: Vec::new(),
+            },
+        }));
+    }
+}
+
+fn same_directives<'a, T: Text<'a>>(a: &[Directive<'a, T>], b: &[Directive<'a, T>]) -> bool {
+    a.len() == b.len()
+        && a.iter()
+            .zip(b)
+            .all(|(a, b)| a.name == b.name && a.arguments == b.arguments)
+}
+
+/// Returns `true` if selections `a` and `b` can be merged into one
+fn mergeable<'a, T: Text<'a>>(a: &Selection<'a, T>, b: &Selection<'a, T>) -> bool {
+    match (a, b) {
+        (Selection::Field(a), Selection::Field(b)) => {
+            a.alias == b.alias
+                && a.name == b.name
+                && a.arguments == b.arguments
+                && same_directives(&a.directives, &b.directives)
+        }
+        (Selection::InlineFragment(a), Selection::InlineFragment(b)) => {
+            a.type_condition == b.type_condition && same_directives(&a.directives, &b.directives)
+        }
+        (Selection::FragmentSpread(a), Selection::FragmentSpread(b)) => {
+            a.fragment_name == b.fragment_name && same_directives(&a.directives, &b.directives)
+        }
+        _ => false,
+    }
+}
+
+/// Error returned by [`SelectionSet::merge`] when two fields selected under
+/// the same response key can't be merged
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldConflict {
+    /// The alias or name of both fields
+    pub response_key: String,
+    /// Position of the field already in the selection set
+    pub existing: Pos,
+    /// Position of the field being merged
+    pub merged: Pos,
+}
+
+impl fmt::Display for FieldConflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "fields `{}` at {} and {} select different fields or arguments",
+            self.response_key, self.existing, self.merged
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FieldConflict {}
+
+impl<'a, T: Text<'a>> SelectionSet<'a, T> {
+    /// Appends selections, merging each into an equivalent one if the set
+    /// already has it
+    ///
+    /// Fields with the same alias, name, arguments and directives are merged
+    /// into one, recursively merging their selection sets. Inline fragments
+    /// with the same type condition and directives are merged the same way,
+    /// repeated fragment spreads are dropped.
+    ///
+    /// Fields with the same response key must select the same field with the
+    /// same arguments, otherwise the response would be ambiguous. When they
+    /// don't, an error is returned and the set is left unchanged.
+    pub fn merge<I>(&mut self, selections: I) -> Result<(), FieldConflict>
+    where
+        I: IntoIterator<Item = Selection<'a, T>>,
+    {
+        let mut merged = self.clone();
+        merged.merge_items(selections)?;
+        *self = merged;
+        Ok(())
+    }
+
+    /// Appends the selections of `other`, see [`SelectionSet::merge`]
+    ///
+    /// Merging can fail on conflicting fields, so unlike
+    /// [`Extend::extend`] this returns a `Result`, which is why
+    /// `SelectionSet` doesn't implement that trait.
+    pub fn extend(&mut self, other: SelectionSet<'a, T>) -> Result<(), FieldConflict> {
+        self.merge(other.items)
+    }
+
+    fn merge_items<I>(&mut self, selections: I) -> Result<(), FieldConflict>
+    where
+        I: IntoIterator<Item = Selection<'a, T>>,
+    {
+        for item in selections {
+            if let Selection::Field(ref field) = item {
+                let key = field.alias.as_ref().unwrap_or(&field.name);
+                let conflict = self.items.iter().find_map(|other| match other {
+                    Selection::Field(other)
+                        if other.alias.as_ref().unwrap_or(&other.name) == key
+                            && (other.name != field.name || other.arguments != field.arguments) =>
+                    {
+                        Some(other)
+                    }
+                    _ => None,
+                });
+                if let Some(other) = conflict {
+                    return Err(FieldConflict {
+                        response_key: key.as_ref().to_string(),
+                        existing: other.position,
+                        merged: field.position,
+                    });
+                }
+            }
+            let idx = self.items.iter().position(|other| mergeable(other, &item));
+            let idx = match idx {
+                Some(idx) => idx,
+                None => {
+                    self.items.push(item);
+                    continue;
+                }
+            };
+            match (&mut self.items[idx], item) {
+                (Selection::Field(target), Selection::Field(field)) => {
+                    target.selection_set.merge_items(field.selection_set)?
+                }
+                (Selection::InlineFragment(target), Selection::InlineFragment(frag)) => {
+                    target.selection_set.merge_items(frag.selection_set)?
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a, T: Text<'a>> IntoIterator for SelectionSet<'a, T> {
+    type Item = Selection<'a, T>;
+    type IntoIter = alloc::vec::IntoIter<Selection<'a, T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.into_iter()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct VariableDefinition<'a, T: Text<'a>> {
     pub position: Pos,
     pub name: T,
@@ -99,6 +670,7 @@ pub struct VariableDefinition<'a, T: Text<'a>> {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Selection<'a, T: Text<'a>> {
     Field(Field<'a, T>),
     FragmentSpread(FragmentSpread<'a, T>),
@@ -106,8 +678,11 @@ pub enum Selection<'a, T: Text<'a>> {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Field<'a, T: Text<'a>> {
     pub position: Pos,
+    /// Position right past the last token, see [`Span`]
+    pub end: Pos,
     pub alias: Option<T>,
     pub name: T,
     pub arguments: Vec<(T, Value<'a, T>)>,
@@ -115,7 +690,23 @@ pub struct Field<'a, T: Text<'a>> {
     pub selection_set: SelectionSet<'a, T>,
 }
 
+impl<'a, T: Text<'a>> Field<'a, T> {
+    /// Returns the argument `name` and its value, if it was given
+    ///
+    /// Like [`schema::Field::argument`], this returns the whole argument
+    /// rather than just its value.
+    pub fn argument(&self, name: &str) -> Option<&(T, Value<'a, T>)> {
+        self.arguments.iter().find(|(arg, _)| arg.as_ref() == name)
+    }
+
+    /// Returns the [`Span`] the field was parsed from
+    pub fn span(&self) -> Span {
+        Span::new(self.position, self.end)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FragmentSpread<'a, T: Text<'a>> {
     pub position: Pos,
     pub fragment_name: T,
@@ -123,14 +714,113 @@ pub struct FragmentSpread<'a, T: Text<'a>> {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TypeCondition<'a, T: Text<'a>> {
     On(T, PhantomData<&'a ()>),
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct InlineFragment<'a, T: Text<'a>> {
     pub position: Pos,
     pub type_condition: Option<TypeCondition<'a, T>>,
     pub directives: Vec<Directive<'a, T>>,
     pub selection_set: SelectionSet<'a, T>,
 }
+
+impl<'a, 'b, T, U, F> TextMapper<'a, 'b, T, U, F>
+where
+    T: Text<'a>,
+    U: Text<'b>,
+    F: FnMut(T) -> U,
+{
+    fn operation(&mut self, op: OperationDefinition<'a, T>) -> OperationDefinition<'b, U> {
+        match op {
+            OperationDefinition::SelectionSet(s) => {
+                OperationDefinition::SelectionSet(self.selection_set(s))
+            }
+            OperationDefinition::Query(q) => OperationDefinition::Query(Query {
+                position: q.position,
+                name: q.name.map(|n| self.text(n)),
+                variable_definitions: self.variable_definitions(q.variable_definitions),
+                directives: self.directives(q.directives),
+                selection_set: self.selection_set(q.selection_set),
+            }),
+            OperationDefinition::Mutation(m) => OperationDefinition::Mutation(Mutation {
+                position: m.position,
+                name: m.name.map(|n| self.text(n)),
+                variable_definitions: self.variable_definitions(m.variable_definitions),
+                directives: self.directives(m.directives),
+                selection_set: self.selection_set(m.selection_set),
+            }),
+            OperationDefinition::Subscription(s) => OperationDefinition::Subscription(Subscription {
+                position: s.position,
+                name: s.name.map(|n| self.text(n)),
+                variable_definitions: self.variable_definitions(s.variable_definitions),
+                directives: self.directives(s.directives),
+                selection_set: self.selection_set(s.selection_set),
+            }),
+        }
+    }
+
+    fn variable_definitions(
+        &mut self,
+        vars: Vec<VariableDefinition<'a, T>>,
+    ) -> Vec<VariableDefinition<'b, U>> {
+        vars.into_iter()
+            .map(|var| VariableDefinition {
+                position: var.position,
+                name: self.text(var.name),
+                var_type: self.typ(var.var_type),
+                default_value: var.default_value.map(|v| self.value(v)),
+            })
+            .collect()
+    }
+
+    fn fragment(&mut self, frag: FragmentDefinition<'a, T>) -> FragmentDefinition<'b, U> {
+        FragmentDefinition {
+            position: frag.position,
+            name: self.text(frag.name),
+            type_condition: self.type_condition(frag.type_condition),
+            directives: self.directives(frag.directives),
+            selection_set: self.selection_set(frag.selection_set),
+        }
+    }
+
+    fn type_condition(&mut self, cond: TypeCondition<'a, T>) -> TypeCondition<'b, U> {
+        let TypeCondition::On(name, _) = cond;
+        TypeCondition::On(self.text(name), PhantomData)
+    }
+
+    fn selection_set(&mut self, set: SelectionSet<'a, T>) -> SelectionSet<'b, U> {
+        SelectionSet {
+            span: set.span,
+            items: set
+                .items
+                .into_iter()
+                .map(|item| match item {
+                    Selection::Field(field) => Selection::Field(Field {
+                        position: field.position,
+                        end: field.end,
+                        alias: field.alias.map(|a| self.text(a)),
+                        name: self.text(field.name),
+                        arguments: self.arguments(field.arguments),
+                        directives: self.directives(field.directives),
+                        selection_set: self.selection_set(field.selection_set),
+                    }),
+                    Selection::FragmentSpread(spread) => Selection::FragmentSpread(FragmentSpread {
+                        position: spread.position,
+                        fragment_name: self.text(spread.fragment_name),
+                        directives: self.directives(spread.directives),
+                    }),
+                    Selection::InlineFragment(frag) => Selection::InlineFragment(InlineFragment {
+                        position: frag.position,
+                        type_condition: frag.type_condition.map(|c| self.type_condition(c)),
+                        directives: self.directives(frag.directives),
+                        selection_set: self.selection_set(frag.selection_set),
+                    }),
+                })
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,160 @@
+//! Writing queries in a canonical form
+//!
+use crate::common::{Directive, Text};
+use crate::format::Style;
+use crate::query::ast::*;
+use crate::query::minify::minify_query_streaming;
+use crate::query::visit::{walk_document_mut, VisitControl, VisitorMut};
+
+fn sort_arguments<'a, T: Text<'a>>(directives: &mut [Directive<'a, T>]) {
+    for directive in directives {
+        directive
+            .arguments
+            .sort_by(|a, b| a.0.as_ref().cmp(b.0.as_ref()));
+    }
+}
+
+struct Canonicalize;
+
+impl<'a, T: Text<'a>> VisitorMut<'a, T> for Canonicalize {
+    fn visit_operation(&mut self, operation: &mut OperationDefinition<'a, T>) -> VisitControl {
+        let (variable_definitions, directives) = match operation {
+            OperationDefinition::SelectionSet(_) => return VisitControl::Continue,
+            OperationDefinition::Query(q) => (&mut q.variable_definitions, &mut q.directives),
+            OperationDefinition::Mutation(m) => (&mut m.variable_definitions, &mut m.directives),
+            OperationDefinition::Subscription(s) => {
+                (&mut s.variable_definitions, &mut s.directives)
+            }
+        };
+        variable_definitions.sort_by(|a, b| a.name.as_ref().cmp(b.name.as_ref()));
+        sort_arguments(directives);
+        VisitControl::Continue
+    }
+    fn visit_fragment_definition(
+        &mut self,
+        fragment: &mut FragmentDefinition<'a, T>,
+    ) -> VisitControl {
+        sort_arguments(&mut fragment.directives);
+        VisitControl::Continue
+    }
+    fn visit_field(&mut self, field: &mut Field<'a, T>) -> VisitControl {
+        field
+            .arguments
+            .sort_by(|a, b| a.0.as_ref().cmp(b.0.as_ref()));
+        sort_arguments(&mut field.directives);
+        VisitControl::Continue
+    }
+    fn visit_inline_fragment(&mut self, fragment: &mut InlineFragment<'a, T>) -> VisitControl {
+        sort_arguments(&mut fragment.directives);
+        VisitControl::Continue
+    }
+    fn visit_fragment_spread(&mut self, spread: &mut FragmentSpread<'a, T>) -> VisitControl {
+        sort_arguments(&mut spread.directives);
+        VisitControl::Continue
+    }
+}
+
+/// Writes `doc` in a canonical form, suitable as a cache key
+///
+/// Operations come first, followed by fragments, each sorted by name with
+/// anonymous operations first. Arguments of fields and directives, and
+/// variable definitions, are sorted by name. Selections and directives keep
+/// their order, since it's significant. The result is minified, so it
+/// doesn't depend on the formatting of the source.
+pub fn canonicalize<'a, T>(doc: &Document<'a, T>) -> String
+where
+    T: Text<'a>,
+{
+    let mut doc = doc.clone();
+    doc.definitions.sort_by(|a, b| {
+        let key = |def: &Definition<'a, T>| match def {
+            Definition::Operation(op) => (0, op.name().map(|name| name.as_ref().to_string())),
+            Definition::Fragment(f) => (1, Some(f.name.as_ref().to_string())),
+        };
+        key(a).cmp(&key(b))
+    });
+    walk_document_mut(&mut Canonicalize, &mut doc);
+
+    let mut style = Style::default();
+    style.block_strings(false);
+    let mut result = Vec::new();
+    minify_query_streaming(&doc.format(&style), &mut result).expect("formatted queries are valid");
+    String::from_utf8(result).expect("tokens are valid utf-8")
+}
+
+/// Returns the lowercase hex SHA-256 of [`canonicalize`]d `doc`
+///
+/// This is the hash automatic persisted queries identify a query with,
+/// provided clients send the canonical form as the query text. Since the
+/// hash is computed from the canonical form, it's the same for every
+/// document parsed from an equivalent query.
+#[cfg(feature = "sha2")]
+pub fn fingerprint<'a, T>(doc: &Document<'a, T>) -> String
+where
+    T: Text<'a>,
+{
+    use sha2::{Digest, Sha256};
+    use std::fmt::Write;
+
+    let digest = Sha256::digest(canonicalize(doc).as_bytes());
+    let mut result = String::with_capacity(digest.len() * 2);
+    for byte in digest {
+        write!(result, "{:02x}", byte).expect("writing to a string never fails");
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::canonicalize;
+    use crate::query::parse_query;
+
+    #[test]
+    fn canonical_form() {
+        let a = parse_query::<&str>(
+            r#"fragment F on User @dir(b: 2, a: 1) { name }
+               query Q($z: Int, $a: String = "x") {
+                 user(z: $z, a: $a) @include(if: true) { ...F b a }
+               }
+               query A { c(y: { b: 1, a: 2 }, x: "multi") }"#,
+        )
+        .unwrap();
+        let b = parse_query::<&str>(
+            r#"query A { c(x: "multi", y: { a: 2, b: 1 }) }
+               query Q($a: String = "x", $z: Int) {
+                 user(a: $a, z: $z) @include(if: true) { ...F b a }
+               }
+               fragment F on User @dir(a: 1, b: 2) { name }"#,
+        )
+        .unwrap();
+        let canonical = canonicalize(&a);
+        assert_eq!(canonical, canonicalize(&b));
+        assert_eq!(
+            canonical,
+            concat!(
+                r#"query A{c(x:"multi" y:{a:2 b:1})}"#,
+                r#"query Q($a:String="x"$z:Int){user(a:$a z:$z)@include(if:true){...F b a}}"#,
+                "fragment F on User@dir(a:1 b:2){name}",
+            )
+        );
+    }
+
+    #[test]
+    fn selection_order() {
+        let a = parse_query::<&str>("{ a b }").unwrap();
+        let b = parse_query::<&str>("{ b a }").unwrap();
+        assert_ne!(canonicalize(&a), canonicalize(&b));
+        assert_eq!(canonicalize(&a), "{a b}");
+    }
+
+    #[cfg(feature = "sha2")]
+    #[test]
+    fn fingerprint() {
+        // Example hash from the Apollo persisted queries documentation
+        let doc = parse_query::<&str>("{\n  __typename\n}").unwrap();
+        assert_eq!(
+            super::fingerprint(&doc),
+            "ecf4edb46db40b5132295c0291d62fb65d6759a9eedfa4d5d612dd5ec54a6b38"
+        );
+    }
+}
@@ -0,0 +1,89 @@
+//! Comparing queries by structure only
+//!
+use crate::common::{StructuralEq, Text};
+use crate::query::ast::*;
+
+impl<'a, T: Text<'a>> StructuralEq for Definition<'a, T> {
+    fn structural_eq(&self, other: &Self, loose: bool) -> bool {
+        match (self, other) {
+            (Definition::Operation(a), Definition::Operation(b)) => a.structural_eq(b, loose),
+            (Definition::Fragment(a), Definition::Fragment(b)) => {
+                a.name == b.name
+                    && a.type_condition == b.type_condition
+                    && a.directives.structural_eq(&b.directives, loose)
+                    && a.selection_set.structural_eq(&b.selection_set, loose)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> StructuralEq for OperationDefinition<'a, T> {
+    fn structural_eq(&self, other: &Self, loose: bool) -> bool {
+        use crate::query::OperationDefinition::*;
+        match (self, other) {
+            (SelectionSet(a), SelectionSet(b)) => a.structural_eq(b, loose),
+            (Query(a), Query(b)) => a.structural_eq(b, loose),
+            (Mutation(a), Mutation(b)) => a.structural_eq(b, loose),
+            (Subscription(a), Subscription(b)) => a.structural_eq(b, loose),
+            _ => false,
+        }
+    }
+}
+
+macro_rules! impl_operation_eq {
+    ($($op:ident),*) => {
+        $(
+            impl<'a, T: Text<'a>> StructuralEq for $op<'a, T> {
+                fn structural_eq(&self, other: &Self, loose: bool) -> bool {
+                    self.name == other.name
+                        && self
+                            .variable_definitions
+                            .structural_eq(&other.variable_definitions, loose)
+                        && self.directives.structural_eq(&other.directives, loose)
+                        && self.selection_set.structural_eq(&other.selection_set, loose)
+                }
+            }
+        )*
+    };
+}
+
+impl_operation_eq!(Query, Mutation, Subscription);
+
+impl<'a, T: Text<'a>> StructuralEq for VariableDefinition<'a, T> {
+    fn structural_eq(&self, other: &Self, _loose: bool) -> bool {
+        self.name == other.name
+            && self.var_type == other.var_type
+            && self.default_value == other.default_value
+    }
+}
+
+impl<'a, T: Text<'a>> StructuralEq for SelectionSet<'a, T> {
+    fn structural_eq(&self, other: &Self, loose: bool) -> bool {
+        self.items.structural_eq(&other.items, loose)
+    }
+}
+
+impl<'a, T: Text<'a>> StructuralEq for Selection<'a, T> {
+    fn structural_eq(&self, other: &Self, loose: bool) -> bool {
+        match (self, other) {
+            (Selection::Field(a), Selection::Field(b)) => {
+                a.alias == b.alias
+                    && a.name == b.name
+                    && a.arguments == b.arguments
+                    && a.directives.structural_eq(&b.directives, loose)
+                    && a.selection_set.structural_eq(&b.selection_set, loose)
+            }
+            (Selection::FragmentSpread(a), Selection::FragmentSpread(b)) => {
+                a.fragment_name == b.fragment_name
+                    && a.directives.structural_eq(&b.directives, loose)
+            }
+            (Selection::InlineFragment(a), Selection::InlineFragment(b)) => {
+                a.type_condition == b.type_condition
+                    && a.directives.structural_eq(&b.directives, loose)
+                    && a.selection_set.structural_eq(&b.selection_set, loose)
+            }
+            _ => false,
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use combine::easy::Errors;
 use thiserror::Error;
 
-use crate::position::Pos;
+use crate::position::{source_snippet, Pos};
 use crate::tokenizer::Token;
 
 pub type InternalError<'a> = Errors<Token<'a>, Token<'a>, Pos>;
@@ -11,11 +11,32 @@ pub type InternalError<'a> = Errors<Token<'a>, Token<'a>, Pos>;
 /// This structure is opaque for forward compatibility. We are exploring a
 /// way to improve both error message and API.
 #[derive(Error, Debug)]
-#[error("query parse error: {}", _0)]
-pub struct ParseError(String);
+#[error("query parse error: {}", message)]
+pub struct ParseError {
+    message: String,
+    position: Pos,
+}
+
+impl ParseError {
+    /// Returns the position of the token the error was found at
+    pub fn position(&self) -> Pos {
+        self.position
+    }
+
+    /// Formats the error followed by the offending line of `src` with a
+    /// caret under the error position
+    ///
+    /// `src` must be the text that failed to parse.
+    pub fn display_with_source(&self, src: &str) -> String {
+        source_snippet(src, self.position, &self.to_string())
+    }
+}
 
 impl<'a> From<InternalError<'a>> for ParseError {
     fn from(e: InternalError<'a>) -> ParseError {
-        ParseError(format!("{}", e))
+        ParseError {
+            message: format!("{}", e),
+            position: e.position,
+        }
     }
 }
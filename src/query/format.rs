@@ -1,4 +1,6 @@
-use std::fmt;
+use alloc::format;
+use alloc::string::{String, ToString};
+use core::fmt;
 
 use crate::format::{format_directives, Displayable, Formatter, Style};
 
@@ -14,6 +16,32 @@ where
         self.display(&mut formatter);
         formatter.into_string()
     }
+
+    /// Format a document according to style, putting `comments` back into
+    /// it
+    ///
+    /// Comments are usually the ones returned by
+    /// [`parse_query_with_comments`](crate::query::parse_query_with_comments)
+    /// along with the document. Each comment is written on its own line
+    /// before the definition or selection following it in the source, or
+    /// before the closing brace if there is none. So a comment at the end
+    /// of a line is moved to the next line, and comments within arguments
+    /// and variable definitions end up before the next selection.
+    pub fn format_with_comments(&self, style: &Style, comments: &[Comment]) -> String {
+        let mut formatter = Formatter::with_comments(style, comments);
+        self.display(&mut formatter);
+        formatter.into_string()
+    }
+}
+
+/// Writes the block of selections along with the comments in it
+fn format_selections<'a, T: Text<'a>>(set: &SelectionSet<'a, T>, f: &mut Formatter) {
+    f.start_block();
+    for item in &set.items {
+        item.display(f);
+    }
+    f.comments_before(set.span.1);
+    f.end_block();
 }
 
 fn to_string<T: Displayable>(v: &T) -> String {
@@ -31,6 +59,7 @@ where
         for item in &self.definitions {
             item.display(f);
         }
+        f.remaining_comments();
     }
 }
 
@@ -66,6 +95,7 @@ where
 {
     fn display(&self, f: &mut Formatter) {
         f.margin();
+        f.comments_before(self.position);
         f.indent();
         f.write("fragment ");
         f.write(self.name.as_ref());
@@ -73,11 +103,7 @@ where
         self.type_condition.display(f);
         format_directives(&self.directives, f);
         f.write(" ");
-        f.start_block();
-        for item in &self.selection_set.items {
-            item.display(f);
-        }
-        f.end_block();
+        format_selections(&self.selection_set, f);
     }
 }
 
@@ -87,12 +113,9 @@ where
 {
     fn display(&self, f: &mut Formatter) {
         f.margin();
+        f.comments_before(self.span.0);
         f.indent();
-        f.start_block();
-        for item in &self.items {
-            item.display(f);
-        }
-        f.end_block();
+        format_selections(self, f);
     }
 }
 
@@ -109,7 +132,7 @@ where
     }
 }
 
-fn format_arguments<'a, T: Text<'a>>(arguments: &[(T, Value<'a, T>)], f: &mut Formatter)
+fn format_arguments<'a, T>(arguments: &[(T, Value<'a, T>)], f: &mut Formatter)
 where
     T: Text<'a>,
 {
@@ -135,21 +158,25 @@ where
     T: Text<'a>,
 {
     fn display(&self, f: &mut Formatter) {
+        f.comments_before(self.position);
         f.indent();
         if let Some(ref alias) = self.alias {
             f.write(alias.as_ref());
             f.write(": ");
         }
         f.write(self.name.as_ref());
+        if !self.arguments.is_empty() {
+            // directives end up on the same line as the arguments
+            f.wrap_next_block(|f| {
+                format_arguments(&self.arguments, f);
+                format_directives(&self.directives, f);
+            });
+        }
         format_arguments(&self.arguments, f);
         format_directives(&self.directives, f);
         if !self.selection_set.items.is_empty() {
             f.write(" ");
-            f.start_block();
-            for item in &self.selection_set.items {
-                item.display(f);
-            }
-            f.end_block();
+            format_selections(&self.selection_set, f);
         } else {
             f.endline();
         }
@@ -162,28 +189,17 @@ where
 {
     fn display(&self, f: &mut Formatter) {
         f.margin();
+        f.comments_before(self.position);
         f.indent();
         f.write("query");
         if let Some(ref name) = self.name {
             f.write(" ");
             f.write(name.as_ref());
         }
-        if !self.variable_definitions.is_empty() {
-            f.write("(");
-            self.variable_definitions[0].display(f);
-            for var in &self.variable_definitions[1..] {
-                f.write(", ");
-                var.display(f);
-            }
-            f.write(")");
-        }
+        f.write_list(&self.variable_definitions, |var, f| var.display(f));
         format_directives(&self.directives, f);
         f.write(" ");
-        f.start_block();
-        for item in &self.selection_set.items {
-            item.display(f);
-        }
-        f.end_block();
+        format_selections(&self.selection_set, f);
     }
 }
 
@@ -193,28 +209,17 @@ where
 {
     fn display(&self, f: &mut Formatter) {
         f.margin();
+        f.comments_before(self.position);
         f.indent();
         f.write("mutation");
         if let Some(ref name) = self.name {
             f.write(" ");
             f.write(name.as_ref());
         }
-        if !self.variable_definitions.is_empty() {
-            f.write("(");
-            self.variable_definitions[0].display(f);
-            for var in &self.variable_definitions[1..] {
-                f.write(", ");
-                var.display(f);
-            }
-            f.write(")");
-        }
+        f.write_list(&self.variable_definitions, |var, f| var.display(f));
         format_directives(&self.directives, f);
         f.write(" ");
-        f.start_block();
-        for item in &self.selection_set.items {
-            item.display(f);
-        }
-        f.end_block();
+        format_selections(&self.selection_set, f);
     }
 }
 
@@ -224,26 +229,17 @@ where
 {
     fn display(&self, f: &mut Formatter) {
         f.margin();
+        f.comments_before(self.position);
         f.indent();
         f.write("subscription");
         if let Some(ref name) = self.name {
             f.write(" ");
             f.write(name.as_ref());
-            if !self.variable_definitions.is_empty() {
-                f.write("(");
-                for var in &self.variable_definitions {
-                    var.display(f);
-                }
-                f.write(")");
-            }
         }
+        f.write_list(&self.variable_definitions, |var, f| var.display(f));
         format_directives(&self.directives, f);
         f.write(" ");
-        f.start_block();
-        for item in &self.selection_set.items {
-            item.display(f);
-        }
-        f.end_block();
+        format_selections(&self.selection_set, f);
     }
 }
 
@@ -293,9 +289,11 @@ where
                 f.write("$");
                 f.write(name.as_ref());
             }
-            Value::Int(ref num) => f.write(&format!("{}", num.0)),
-            Value::Float(val) => f.write(&format!("{}", val)),
-            Value::String(ref val) => f.write_quoted(val.as_ref()),
+            Value::Int(ref num) => f.write(&num.to_string()),
+            // `Debug` keeps the fraction of whole numbers, so they don't
+            // read back as `Int`, and uses exponents for long numbers
+            Value::Float(val) => f.write(&format!("{:?}", val)),
+            Value::String(ref val) => f.write_quoted(val),
             Value::Boolean(true) => f.write("true"),
             Value::Boolean(false) => f.write("false"),
             Value::Null => f.write("null"),
@@ -338,6 +336,7 @@ where
     T: Text<'a>,
 {
     fn display(&self, f: &mut Formatter) {
+        f.comments_before(self.position);
         f.indent();
         f.write("...");
         if let Some(ref cond) = self.type_condition {
@@ -346,11 +345,7 @@ where
         }
         format_directives(&self.directives, f);
         f.write(" ");
-        f.start_block();
-        for item in &self.selection_set.items {
-            item.display(f);
-        }
-        f.end_block();
+        format_selections(&self.selection_set, f);
     }
 }
 
@@ -373,6 +368,7 @@ where
     T: Text<'a>,
 {
     fn display(&self, f: &mut Formatter) {
+        f.comments_before(self.position);
         f.indent();
         f.write("...");
         f.write(self.fragment_name.as_ref());
@@ -1,13 +1,16 @@
-use combine::{eof, many1, optional, position, StdParseResult};
+use combine::easy::Error;
+use combine::error::StreamError;
+use combine::{attempt, eof, many1, optional, position, satisfy, StdParseResult};
 use combine::{parser, Parser};
 use std::marker::PhantomData;
 
 use crate::common::Directive;
 use crate::common::{arguments, default_value, directives, parse_type};
-use crate::helpers::{ident, name, punct};
+use crate::helpers::{end_position, ident, kind, name, punct};
+use crate::options::{ParseLimits, ParseOptions, ParseWarning};
 use crate::query::ast::*;
 use crate::query::error::ParseError;
-use crate::tokenizer::TokenStream;
+use crate::tokenizer::{Kind, Token, TokenStream};
 
 pub fn field<'a, S>(input: &mut TokenStream<'a>) -> StdParseResult<Field<'a, S>, TokenStream<'a>>
     where
@@ -20,15 +23,17 @@ pub fn field<'a, S>(input: &mut TokenStream<'a>) -> StdParseResult<Field<'a, S>,
         parser(arguments),
         parser(directives),
         optional(parser(selection_set)),
+        parser(end_position),
     )
         .map(
-            |(position, name_or_alias, opt_name, arguments, directives, sel)| {
+            |(position, name_or_alias, opt_name, arguments, directives, sel, end)| {
                 let (name, alias) = match opt_name {
                     Some(name) => (name, Some(name_or_alias)),
                     None => (name_or_alias, None),
                 };
                 Field {
                     position,
+                    end,
                     name,
                     alias,
                     arguments,
@@ -122,7 +127,7 @@ pub fn selection_set<'a, S>(
         .into_result()
 }
 
-pub fn query<'a, T: Text<'a>>(
+pub fn query<'a, T>(
     input: &mut TokenStream<'a>,
 ) -> StdParseResult<Query<'a, T>, TokenStream<'a>>
     where
@@ -153,36 +158,14 @@ type OperationCommon<'a, T: Text<'a>> = (
     SelectionSet<'a, T>,
 );
 
-pub fn operation_common<'a, T: Text<'a>>(
+pub fn operation_common<'a, T>(
     input: &mut TokenStream<'a>,
 ) -> StdParseResult<OperationCommon<'a, T>, TokenStream<'a>>
     where
         T: Text<'a>,
 {
     optional(name::<'a, T>())
-        .and(
-            optional(
-                punct("(")
-                    .with(many1(
-                        (
-                            position(),
-                            punct("$").with(name::<'a, T>()).skip(punct(":")),
-                            parser(parse_type),
-                            optional(punct("=").with(parser(default_value))),
-                        )
-                            .map(
-                                |(position, name, var_type, default_value)| VariableDefinition {
-                                    position,
-                                    name,
-                                    var_type,
-                                    default_value,
-                                },
-                            ),
-                    ))
-                    .skip(punct(")")),
-            )
-                .map(|vars| vars.unwrap_or_default()),
-        )
+        .and(parser(variable_definitions))
         .and(parser(directives))
         .and(parser(selection_set))
         .map(|(((a, b), c), d)| (a, b, c, d))
@@ -190,7 +173,39 @@ pub fn operation_common<'a, T: Text<'a>>(
         .into_result()
 }
 
-pub fn mutation<'a, T: Text<'a>>(
+/// Optional parenthesized list of variable definitions of an operation
+pub fn variable_definitions<'a, T>(
+    input: &mut TokenStream<'a>,
+) -> StdParseResult<Vec<VariableDefinition<'a, T>>, TokenStream<'a>>
+    where
+        T: Text<'a>,
+{
+    optional(
+        punct("(")
+            .with(many1(
+                (
+                    position(),
+                    punct("$").with(name::<'a, T>()).skip(punct(":")),
+                    parser(parse_type),
+                    optional(punct("=").with(parser(default_value))),
+                )
+                    .map(
+                        |(position, name, var_type, default_value)| VariableDefinition {
+                            position,
+                            name,
+                            var_type,
+                            default_value,
+                        },
+                    ),
+            ))
+            .skip(punct(")")),
+    )
+    .map(|vars| vars.unwrap_or_default())
+    .parse_stream(input)
+    .into_result()
+}
+
+pub fn mutation<'a, T>(
     input: &mut TokenStream<'a>,
 ) -> StdParseResult<Mutation<'a, T>, TokenStream<'a>>
     where
@@ -212,7 +227,7 @@ pub fn mutation<'a, T: Text<'a>>(
         .into_result()
 }
 
-pub fn subscription<'a, T: Text<'a>>(
+pub fn subscription<'a, T>(
     input: &mut TokenStream<'a>,
 ) -> StdParseResult<Subscription<'a, T>, TokenStream<'a>>
     where
@@ -249,7 +264,19 @@ pub fn operation_definition<'a, S>(
         .into_result()
 }
 
-pub fn fragment_definition<'a, T: Text<'a>>(
+/// Name of a fragment, which is any name except `on`
+pub fn fragment_name<'a, T>(input: &mut TokenStream<'a>) -> StdParseResult<T, TokenStream<'a>>
+    where
+        T: Text<'a>,
+{
+    satisfy(|t: Token<'a>| t.kind == Kind::Name && t.value != "on")
+        .map(|t: Token<'a>| T::from(t.value))
+        .expected("fragment name")
+        .parse_stream(input)
+        .into_result()
+}
+
+pub fn fragment_definition<'a, T>(
     input: &mut TokenStream<'a>,
 ) -> StdParseResult<FragmentDefinition<'a, T>, TokenStream<'a>>
     where
@@ -257,7 +284,7 @@ pub fn fragment_definition<'a, T: Text<'a>>(
 {
     (
         position().skip(ident("fragment")),
-        name::<'a, T>(),
+        parser(fragment_name),
         ident("on")
             .with(name::<'a, T>())
             .map(|v| TypeCondition::On(v, PhantomData)),
@@ -290,20 +317,142 @@ pub fn definition<'a, S>(
         .into_result()
 }
 
+/// Keywords starting type system definitions and extensions
+const TYPE_SYSTEM_KEYWORDS: &[&str] = &[
+    "schema",
+    "scalar",
+    "type",
+    "interface",
+    "union",
+    "enum",
+    "input",
+    "directive",
+    "extend",
+];
+
+/// Fails with a descriptive error at the start of a type system definition
+///
+/// Executable documents can't contain type system definitions, but
+/// without this the error would only list the expected keywords.
+pub(crate) fn type_system_definition<'a, S>(
+    input: &mut TokenStream<'a>,
+) -> StdParseResult<Definition<'a, S>, TokenStream<'a>>
+    where
+        S: Text<'a>,
+{
+    attempt((
+        optional(kind(Kind::StringValue).or(kind(Kind::BlockString))),
+        satisfy(|t: Token<'a>| t.kind == Kind::Name && TYPE_SYSTEM_KEYWORDS.contains(&t.value)),
+    ))
+    .silent()
+    .and_then(|_| {
+        Err(Error::message_static_message(
+            "type system definitions are not allowed in executable documents",
+        ))
+    })
+    .parse_stream(input)
+    .into_result()
+}
+
 /// Parses a piece of query language and returns an AST
+///
+/// With `S = &'a str` the names in the document borrow from `s` instead of
+/// being copied. To parse into an arena, allocate the source there, e.g.
+/// `parse_query::<&str>(bump.alloc_str(source))` with a `bumpalo::Bump`.
+/// Lists of nodes and string values are still allocated on the heap, as
+/// the AST types aren't generic over the allocator.
 pub fn parse_query<'a, S>(s: &'a str) -> Result<Document<'a, S>, ParseError>
     where
         S: Text<'a>,
 {
-    let mut tokens = TokenStream::new(s);
-    let (doc, _) = many1(parser(definition))
+    parse_query_with_options(s, &ParseOptions::default())
+}
+
+/// Parses a piece of query language, failing if it exceeds `limits`
+///
+/// Use this for untrusted input, such as queries sent to a server, so
+/// that deeply nested or huge queries are rejected with a parse error.
+pub fn parse_query_with_limits<'a, S>(
+    s: &'a str,
+    limits: ParseLimits,
+) -> Result<Document<'a, S>, ParseError>
+    where
+        S: Text<'a>,
+{
+    let mut options = ParseOptions::default();
+    options.limits(limits);
+    parse_query_with_options(s, &options)
+}
+
+/// Parses a piece of query language with the given options
+pub fn parse_query_with_options<'a, S>(
+    s: &'a str,
+    options: &ParseOptions,
+) -> Result<Document<'a, S>, ParseError>
+    where
+        S: Text<'a>,
+{
+    parse_query_with_warnings(s, options).map(|(doc, _)| doc)
+}
+
+/// Parses a piece of query language with the given options, returning
+/// warnings about input accepted only because of lenient options
+pub fn parse_query_with_warnings<'a, S>(
+    s: &'a str,
+    options: &ParseOptions,
+) -> Result<(Document<'a, S>, Vec<ParseWarning>), ParseError>
+    where
+        S: Text<'a>,
+{
+    let mut tokens = TokenStream::with_options(s, options);
+    let (doc, _) = many1(parser(definition).or(parser(type_system_definition)))
         .map(|d| Document { definitions: d })
         .skip(eof())
         .parse_stream(&mut tokens)
         .into_result()
         .map_err(|e| e.into_inner().error)?;
 
-    Ok(doc)
+    Ok((doc, tokens.take_warnings()))
+}
+
+/// Parses a piece of query language, returning the comments in it too
+///
+/// Comments are returned in source order. Passing them to
+/// [`Document::format_with_comments`] puts them back into the formatted
+/// document.
+pub fn parse_query_with_comments<'a, S>(
+    s: &'a str,
+) -> Result<(Document<'a, S>, Vec<Comment>), ParseError>
+    where
+        S: Text<'a>,
+{
+    let mut tokens = TokenStream::with_comments(s);
+    let (doc, _) = many1(parser(definition).or(parser(type_system_definition)))
+        .map(|d| Document { definitions: d })
+        .skip(eof())
+        .parse_stream(&mut tokens)
+        .into_result()
+        .map_err(|e| e.into_inner().error)?;
+
+    Ok((doc, tokens.take_comments()))
+}
+
+/// Parses a selection set written without the surrounding braces
+///
+/// This is the format of the `fields` argument of federation directives
+/// such as `@key(fields: "id organization { id }")`.
+pub fn parse_field_set<'a, S>(s: &'a str) -> Result<SelectionSet<'a, S>, ParseError>
+    where
+        S: Text<'a>,
+{
+    let mut tokens = TokenStream::new(s);
+    let (set, _) = parser(raw_selection_set)
+        .skip(eof())
+        .parse_stream(&mut tokens)
+        .into_result()
+        .map_err(|e| e.into_inner().error)?;
+
+    Ok(set)
 }
 
 /// Parses a single ExecutableDefinition and returns an AST as well as the
@@ -313,7 +462,9 @@ pub fn consume_definition<'a, S>(s: &'a str) -> Result<(Definition<'a, S>, &'a s
         S: Text<'a>,
 {
     let tokens = TokenStream::new(s);
-    let (doc, tokens) = parser(definition).parse(tokens)?;
+    let (doc, tokens) = parser(definition)
+        .or(parser(type_system_definition))
+        .parse(tokens)?;
 
     Ok((doc, &s[tokens.offset()..]))
 }
@@ -324,7 +475,7 @@ mod test {
     use crate::position::Pos;
     use crate::query::grammar::*;
 
-    fn ast(s: &str) -> Document<String> {
+    fn ast(s: &str) -> Document<'_, String> {
         parse_query::<String>(s).unwrap().to_owned()
     }
 
@@ -335,15 +486,16 @@ mod test {
             Document {
                 definitions: vec![Definition::Operation(OperationDefinition::SelectionSet(
                     SelectionSet {
-                        span: (Pos { line: 1, column: 1 }, Pos { line: 1, column: 5 }),
+                        span: (Pos { line: 1, column: 1, offset: 0 }, Pos { line: 1, column: 5, offset: 4 }),
                         items: vec![Selection::Field(Field {
-                            position: Pos { line: 1, column: 3 },
+                            position: Pos { line: 1, column: 3, offset: 2 },
+                            end: Pos { line: 1, column: 4, offset: 3 },
                             alias: None,
                             name: "a".into(),
                             arguments: Vec::new(),
                             directives: Vec::new(),
                             selection_set: SelectionSet {
-                                span: (Pos { line: 1, column: 3 }, Pos { line: 1, column: 3 }),
+                                span: (Pos { line: 1, column: 3, offset: 2 }, Pos { line: 1, column: 3, offset: 2 }),
                                 items: Vec::new(),
                             },
                         }), ],
@@ -361,14 +513,16 @@ mod test {
                 definitions: vec![Definition::Operation(OperationDefinition::SelectionSet(
                     SelectionSet {
                         span: (
-                            Pos { line: 1, column: 1 },
+                            Pos { line: 1, column: 1, offset: 0 },
                             Pos {
                                 line: 1,
                                 column: 33,
+                                offset: 32,
                             }
                         ),
                         items: vec![Selection::Field(Field {
-                            position: Pos { line: 1, column: 3 },
+                            position: Pos { line: 1, column: 3, offset: 2 },
+                            end: Pos { line: 1, column: 32, offset: 31 },
                             alias: None,
                             name: "a".into(),
                             arguments: vec![
@@ -378,7 +532,7 @@ mod test {
                             ],
                             directives: Vec::new(),
                             selection_set: SelectionSet {
-                                span: (Pos { line: 1, column: 3 }, Pos { line: 1, column: 3 }),
+                                span: (Pos { line: 1, column: 3, offset: 2 }, Pos { line: 1, column: 3, offset: 2 }),
                                 items: Vec::new(),
                             },
                         }), ],
@@ -394,9 +548,520 @@ mod test {
     }
 
     #[test]
-    #[should_panic(expected = "number too large")]
+    fn block_string_argument() {
+        let doc = ast("{ a(x: \"\"\"\n    one\n      two \\\"\"\"\n  \"\"\", y: \"\\u0041\\n\") }");
+        match doc.definitions[0] {
+            Definition::Operation(OperationDefinition::SelectionSet(ref set)) => match set.items[0] {
+                Selection::Field(ref field) => {
+                    assert_eq!(field.arguments[0].1.as_string(), Some("one\n  two \"\"\""));
+                    assert_eq!(field.arguments[1].1.as_string(), Some("A\n"));
+                }
+                _ => panic!("field expected"),
+            },
+            _ => panic!("selection set expected"),
+        }
+    }
+
+    #[test]
+    fn variables_as_input_object() {
+        let doc = ast("query Q($id: ID!, $first: Int = 10, $tags: [String!]) { a }");
+        let input = match doc.definitions[0] {
+            Definition::Operation(ref op) => op.variables_as_input_object("QVariables".into()),
+            _ => panic!("operation expected"),
+        };
+        assert_eq!(
+            input.to_string(),
+            "input QVariables {\n  id: ID!\n  first: Int = 10\n  tags: [String!]\n}\n"
+        );
+        assert_eq!(input.fields[0].position, Pos { line: 1, column: 9, offset: 8 });
+    }
+
+    #[test]
+    fn on_as_name() {
+        let doc = "fragment F on on {\n  on: on(on: on)\n  ... on on {\n    a\n  }\n  ...onX\n}\n";
+        assert_eq!(ast(doc).to_string(), doc);
+        assert_eq!(ast("query on { a }").to_string(), "query on {\n  a\n}\n");
+    }
+
+    #[test]
+    fn fragment_named_on() {
+        assert!(parse_query::<String>("fragment on on T { a }").is_err());
+        assert!(parse_query::<String>("{ ...on }").is_err());
+        assert!(parse_query::<String>("{ ... on { a } }").is_err());
+    }
+
+    #[test]
+    fn field_spans() {
+        use crate::position::node_source;
+
+        let src = "{\n  u: user(id: 1) @skip(if: false) {\n    name # comment\n  }\n  ... on T { a }\n}";
+        let doc = parse_query::<&str>(src).unwrap();
+        let items = match &doc.definitions[0] {
+            Definition::Operation(OperationDefinition::SelectionSet(set)) => &set.items,
+            _ => unreachable!(),
+        };
+        let user = match &items[0] {
+            Selection::Field(f) => f,
+            _ => unreachable!(),
+        };
+        assert_eq!(
+            node_source(src, user.span()),
+            "u: user(id: 1) @skip(if: false) {\n    name # comment\n  }"
+        );
+        match &user.selection_set.items[0] {
+            Selection::Field(f) => assert_eq!(node_source(src, f.span()), "name"),
+            _ => unreachable!(),
+        }
+        assert_eq!(user.span().start, user.position);
+    }
+
+    #[test]
+    fn empty_selection_set() {
+        assert!(parse_query::<String>("{}").is_err());
+        assert!(parse_query::<String>("{ a {} }").is_err());
+        assert!(parse_query::<String>("query { a { ... on T {} } }").is_err());
+    }
+
+    #[test]
+    fn operation_names() {
+        let doc = parse_query::<&str>(
+            "query A { a } fragment F on T { b } { c } mutation B { d } subscription { e }",
+        )
+        .unwrap();
+        assert_eq!(doc.operation_names(), vec![Some(&"A"), None, Some(&"B"), None]);
+    }
+
+    #[test]
+    fn field_set() {
+        let set = parse_field_set::<&str>("id organization { id ... on Org { name } }").unwrap();
+        assert_eq!(set.items.len(), 2);
+        match set.items[1] {
+            Selection::Field(ref f) => {
+                assert_eq!(f.name, "organization");
+                assert_eq!(f.selection_set.items.len(), 2);
+            }
+            _ => panic!("field expected"),
+        }
+        assert!(parse_field_set::<&str>("{ id }").is_err());
+        assert!(parse_field_set::<&str>("id }").is_err());
+        assert!(parse_field_set::<&str>("").is_err());
+    }
+
+    #[test]
+    fn merge_selection_sets() {
+        fn set(s: &str) -> SelectionSet<'_, &str> {
+            match parse_query::<&str>(s).unwrap().definitions.remove(0) {
+                Definition::Operation(OperationDefinition::SelectionSet(s)) => s,
+                _ => unreachable!(),
+            }
+        }
+        let mut a = set("{ id user(id: 1) { name } ... on T { x } ...F n: name }");
+        a.merge(set(
+            "{ id user(id: 1) { email name } u: user(id: 2) { age } ... on T { y } ...F name }",
+        ))
+        .unwrap();
+        assert_eq!(
+            OperationDefinition::SelectionSet(a.clone()).to_string(),
+            "{
+  id
+  user(id: 1) {
+    name
+    email
+  }
+  ... on T {
+    x
+    y
+  }
+  ...F
+  n: name
+  u: user(id: 2) {
+    age
+  }
+  name
+}
+"
+        );
+
+        let before = a.clone();
+        let err = a
+            .merge(set("{ user(id: 1) { name email } ... on T { x: z } }"))
+            .unwrap_err();
+        assert_eq!(err.response_key, "x");
+        assert_eq!((err.existing.line, err.existing.column), (1, 38));
+        assert_eq!((err.merged.line, err.merged.column), (1, 41));
+        assert_eq!(a, before);
+        let err = a.merge(set("{ user(id: 2) { name } }")).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "fields `user` at 1:6 and 1:3 select different fields or arguments"
+        );
+
+        let mut b = set("{ id user(id: 1) { name } }");
+        b.extend(set("{ user(id: 1) { email } }")).unwrap();
+        assert_eq!(b.items.len(), 2);
+        assert!(b.extend(set("{ id: name }")).is_err());
+    }
+
+    #[test]
+    fn inject_typename() {
+        let mut doc = ast("{ a { b t: __typename ... on T { c { d } } ...F } e } \
+             fragment F on T { __typename f { g } }");
+        let mut sets = Vec::new();
+        for def in &mut doc.definitions {
+            match def {
+                Definition::Operation(OperationDefinition::SelectionSet(s)) => sets.push(s),
+                Definition::Fragment(f) => sets.push(&mut f.selection_set),
+                _ => unreachable!(),
+            }
+        }
+        assert!(!sets[0].has_typename());
+        assert!(sets[1].has_typename());
+        for set in sets {
+            set.inject_typename();
+        }
+        assert_eq!(
+            doc.to_string(),
+            "{
+  a {
+    b
+    t: __typename
+    ... on T {
+      c {
+        d
+        __typename
+      }
+    }
+    ...F
+    __typename
+  }
+  e
+}
+
+fragment F on T {
+  __typename
+  f {
+    g
+    __typename
+  }
+}
+"
+        );
+    }
+
+    #[test]
+    fn merge_inline_fragments() {
+        let mut doc = ast("{ x { ... on Foo { a ... on Bar { b } } c ... on Foo { ... on Bar { d } } \
+             ... on Foo @include(if: $v) { e } ...F ... on Foo @include(if: $v) { f } \
+             ... { g } ... { h } } }");
+        match doc.definitions[0] {
+            Definition::Operation(OperationDefinition::SelectionSet(ref mut s)) => {
+                s.merge_inline_fragments()
+            }
+            _ => unreachable!(),
+        }
+        assert_eq!(
+            doc.to_string(),
+            "{
+  x {
+    ... on Foo {
+      a
+      ... on Bar {
+        b
+        d
+      }
+    }
+    c
+    ... on Foo @include(if: $v) {
+      e
+      f
+    }
+    ...F
+    ... {
+      g
+      h
+    }
+  }
+}
+"
+        );
+    }
+
+    #[test]
+    fn object_field_order() {
+        let doc = ast("{ a(x: {a: 1, b: {d: [{f: 2, e: 3}], c: 3}}) b(x: {b: {c: 3, d: [{e: 3, f: 2}]}, a: 1}) }");
+        let items = match doc.definitions[0] {
+            Definition::Operation(OperationDefinition::SelectionSet(ref s)) => &s.items,
+            _ => unreachable!(),
+        };
+        let args = items
+            .iter()
+            .map(|item| match item {
+                Selection::Field(f) => &f.arguments,
+                _ => unreachable!(),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(args[0], args[1]);
+        assert_eq!(args[0][0].1.to_string(), "{a: 1, b: {c: 3, d: [{e: 3, f: 2}]}}");
+    }
+
+    #[test]
+    fn source_order() {
+        let doc = ast("query Q($z: Int, $a: Int, $m: Int) @z @a { z a: y ...F b @z @a }");
+        let q = match doc.definitions[0] {
+            Definition::Operation(OperationDefinition::Query(ref q)) => q,
+            _ => unreachable!(),
+        };
+        let vars = q.variable_definitions.iter().map(|v| v.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(vars, vec!["z", "a", "m"]);
+        let dirs = q.directives.iter().map(|d| d.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(dirs, vec!["z", "a"]);
+        assert_eq!(
+            doc.to_string(),
+            "query Q($z: Int, $a: Int, $m: Int) @z @a {\n  z\n  a: y\n  ...F\n  b @z @a\n}\n"
+        );
+    }
+
+    #[test]
+    fn content_hash() {
+        let a = parse_query::<&str>("query Q($id: ID) { user(id: $id) { name } }").unwrap();
+        let b = parse_query::<String>("query Q(\n  $id: ID\n) {\n  user(id: $id) {\n    name # comment\n  }\n}").unwrap();
+        let c = parse_query::<&str>("query Q($id: ID) { user(id: $id) { id } }").unwrap();
+        assert_eq!(a.content_hash(), b.content_hash());
+        assert_ne!(a.content_hash(), c.content_hash());
+        assert_eq!(a.content_hash(), 0x311a_69a8_1509_7234);
+    }
+
+    #[test]
+    fn single_quoted_strings() {
+        let query = r#"{ a(x: 'it\'s "quoted"', y: "b") { c(z: 'd') } }"#;
+        let err = parse_query::<&str>(query).unwrap_err().to_string();
+        assert!(err.starts_with(
+            "query parse error: Parse error at 1:8\n\
+             Unexpected single-quoted string, GraphQL strings use double quotes\n"
+        ));
+
+        let mut options = crate::ParseOptions::default();
+        options.single_quoted_strings(true);
+        let (doc, warnings) = parse_query_with_warnings::<&str>(query, &options).unwrap();
+        assert_eq!(
+            doc.to_string(),
+            "{\n  a(x: \"it's \\\"quoted\\\"\", y: \"b\") {\n    c(z: \"d\")\n  }\n}\n"
+        );
+        let warnings = warnings.iter().map(|w| w.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            warnings,
+            vec![
+                "1:8: single-quoted string, GraphQL requires double quotes",
+                "1:41: single-quoted string, GraphQL requires double quotes",
+            ]
+        );
+    }
+
+    #[test]
+    fn null_default_value() {
+        let doc = ast("query Q($a: Int, $b: Int = null) { f }");
+        let vars = match doc.definitions[0] {
+            Definition::Operation(OperationDefinition::Query(ref q)) => &q.variable_definitions,
+            _ => unreachable!(),
+        };
+        assert_eq!(vars[0].default_value, None);
+        assert_eq!(vars[1].default_value, Some(Value::Null));
+    }
+
+    #[test]
+    fn types_mut() {
+        let mut doc = parse_query::<&str>(
+            "query Q($a: Int, $b: [ID]) { f } mutation($c: Input!) { g } { h }",
+        )
+        .unwrap();
+        for ty in doc.types_mut() {
+            if let Type::NamedType(name) = ty {
+                *name = "Renamed";
+            }
+        }
+        assert_eq!(
+            doc.to_string(),
+            "query Q($a: Renamed, $b: [ID]) {\n  f\n}\n\n\
+             mutation($c: Input!) {\n  g\n}\n\n\
+             {\n  h\n}\n"
+        );
+    }
+
+    #[test]
+    fn byte_offsets() {
+        let src = "# привет\n{\r\n\tfield(x: \"ü\") other }";
+        let doc = parse_query::<&str>(src).unwrap();
+        let items = match doc.definitions[0] {
+            Definition::Operation(OperationDefinition::SelectionSet(ref s)) => &s.items,
+            _ => unreachable!(),
+        };
+        let position = |item: &Selection<&str>| match item {
+            Selection::Field(f) => f.position,
+            _ => unreachable!(),
+        };
+        let field = position(&items[0]);
+        assert_eq!((field.line, field.column), (3, 9));
+        assert!(src[field.offset..].starts_with("field("));
+        let other = position(&items[1]);
+        assert_eq!(&src[other.offset..other.offset + 5], "other");
+    }
+
+    #[test]
+    fn comments() {
+        let src = "{ a(x: 1) # after a\n  b(y: [1 # inside\n]) }";
+        let (doc, comments) = parse_query_with_comments::<&str>(src).unwrap();
+        let found = comments
+            .iter()
+            .map(|c| (c.position.line, c.position.column, c.text.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(found, vec![(1, 11, " after a"), (2, 11, " inside")]);
+        assert_eq!(
+            doc.format_with_comments(&crate::Style::default(), &comments),
+            "{\n  a(x: 1)\n  # after a\n  b(y: [1])\n  # inside\n}\n"
+        );
+        assert_eq!(doc, parse_query::<&str>(src).unwrap());
+    }
+
+    #[test]
+    fn error_with_source() {
+        let src = "query {\n\tuser(id: ) { name }\n}";
+        let err = parse_query::<&str>(src).unwrap_err();
+        let rendered = err.display_with_source(src);
+        assert!(rendered.starts_with("query parse error: Parse error at 2:18\n"));
+        assert!(rendered.ends_with("\n2 |         user(id: ) { name }\n  |                  ^"));
+    }
+
+    #[test]
+    fn field_arguments() {
+        let doc = parse_query::<&str>("{ user(id: 1, name: $n) { id } }").unwrap();
+        let field = match &doc.definitions[0] {
+            Definition::Operation(OperationDefinition::SelectionSet(set)) => match &set.items[0] {
+                Selection::Field(field) => field,
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        };
+        assert_eq!(field.argument("id"), Some(&("id", Value::Int(1.into()))));
+        assert_eq!(field.argument("name").and_then(|(_, v)| v.as_variable()), Some(&"n"));
+        assert_eq!(field.argument("missing"), None);
+    }
+
+    #[test]
+    fn map_text() {
+        let source = include_str!("../../tests/queries/kitchen-sink.graphql");
+        let borrowed = parse_query::<&str>(source).unwrap();
+        let owned: Document<'static, String> = borrowed.clone().map_text(String::from);
+        assert_eq!(owned, parse_query::<String>(source).unwrap());
+        assert_eq!(borrowed.clone().into_owned(), owned);
+
+        let source = "query Q($v: [In!] = [A]) { f(a: {k: $v}) @d(x: E) { ...F } }";
+        let doc = parse_query::<&str>(source)
+            .unwrap()
+            .map_text(|t| t.to_uppercase());
+        assert_eq!(
+            doc.to_string(),
+            "query Q($V: [IN!] = [A]) {\n  F(A: {K: $V}) @D(X: E) {\n    ...F\n  }\n}\n"
+        );
+    }
+
+    #[test]
+    fn into_owned() {
+        let doc = {
+            let source = String::from("query Q($a: Int = 1) { f(a: $a) { ...F } }");
+            parse_query::<&str>(&source).unwrap().into_owned()
+        };
+        assert_eq!(doc.operation_names(), vec![Some(&"Q".to_string())]);
+        let same = parse_query::<String>("{ a }").unwrap().into_static();
+        assert_eq!(same.clone().into_owned(), same);
+    }
+
+    #[test]
+    fn shift_positions() {
+        let source = include_str!("../../tests/queries/kitchen-sink.graphql");
+        let mut doc = parse_query::<&str>(source).unwrap();
+        let padded = format!("\n\n  \n{}", source);
+        let expected = parse_query::<&str>(&padded).unwrap();
+        assert_ne!(doc, expected);
+        doc.shift_positions(3, 5);
+        assert_eq!(doc, expected);
+    }
+
+    #[test]
+    fn eq_ignore_positions() {
+        let a = parse_query::<&str>("query Q($v: Int) { a(x: 1) @d { ...F } }").unwrap();
+        let b = parse_query::<&str>("query Q(\n  $v: Int\n) {\n  a(x:1)@d{...F}\n}").unwrap();
+        assert_ne!(a, b);
+        assert!(a.eq_ignore_positions(&b));
+        let c = parse_query::<&str>("query Q($v: Int) { a(x: 2) @d { ...F } }").unwrap();
+        assert!(!a.eq_ignore_positions(&c));
+    }
+
+    #[test]
+    fn document_key() {
+        use crate::DocumentKey;
+        use std::collections::HashMap;
+
+        let a = parse_query::<&str>("query Q { a(x: 1.5) { ...F } }").unwrap();
+        let b = parse_query::<&str>("\n\nquery Q {\n  a(x: 1.5) {\n    ...F\n  }\n}").unwrap();
+        let c = parse_query::<&str>("query Q { a(x: 2.5) { ...F } }").unwrap();
+        let mut cache = HashMap::new();
+        cache.insert(DocumentKey(a), 1);
+        assert_eq!(cache.get(&DocumentKey(b)), Some(&1));
+        assert_eq!(cache.get(&DocumentKey(c)), None);
+    }
+
+    #[test]
+    fn all_directives() {
+        let doc = parse_query::<&str>(
+            "query Q @a { f @b { g @c ...F @d ... on T @e { h @f } } }
+             fragment F on T @g { i @h }
+             { j @i }",
+        )
+        .unwrap();
+        let names = doc.all_directives().map(|d| d.name).collect::<Vec<_>>();
+        assert_eq!(names, vec!["a", "b", "c", "d", "e", "f", "g", "h", "i"]);
+    }
+
+    #[test]
+    fn max_string_length() {
+        let mut options = crate::ParseOptions::default();
+        options.max_string_length(Some(5));
+        let parse = |s| parse_query_with_options::<&str>(s, &options).map(|_| ());
+        assert!(parse(r#"{ a(x: "12345", y: """12345""") }"#).is_ok());
+        let err = parse(r#"{ a(x: "123456") }"#).unwrap_err().to_string();
+        assert!(err.starts_with(
+            "query parse error: Parse error at 1:8\n\
+             Unexpected string value of 6 bytes exceeds the limit of 5 bytes\n"
+        ));
+        assert!(parse(r#"{ a(x: """123456""") }"#).is_err());
+        assert!(parse_query::<&str>(r#"{ a(x: "123456") }"#).is_ok());
+    }
+
+    #[test]
+    fn parse_limits() {
+        let limits = |max_depth, max_tokens| crate::ParseLimits {
+            max_depth,
+            max_tokens,
+        };
+        let parse = |s, l| parse_query_with_limits::<&str>(s, l).map(|_| ());
+        assert!(parse("{ a { b(x: [1]) } }", limits(4, Some(13))).is_ok());
+        let err = parse("{ a { b(x: [1]) } }", limits(3, None)).unwrap_err().to_string();
+        assert!(err.starts_with("query parse error: Parse error at 1:12\n"));
+        assert!(err.ends_with("\nRecursion limit exceeded\n"));
+        let err = parse("{ a { b(x: [1]) } }", limits(4, Some(12))).unwrap_err();
+        assert!(err.to_string().contains("Token limit exceeded"));
+
+        let deep = format!("{}{}", "{ a ".repeat(100_000), "}".repeat(100_000));
+        assert!(parse(&deep, Default::default()).is_err());
+        assert!(parse_query::<&str>(&deep).is_err());
+    }
+
+    #[test]
     fn large_integer() {
-        ast("{ a(x: 10000000000000000000000000000 }");
+        assert_eq!(
+            ast("{ a(x: 10000000000000000000000000000) }").to_string(),
+            "{\n  a(x: 10000000000000000000000000000)\n}\n"
+        );
     }
 
     #[test]
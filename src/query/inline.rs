@@ -0,0 +1,214 @@
+//! Replacing fragment spreads with the fragments they refer to
+//!
+use std::collections::HashMap;
+
+use thiserror::Error;
+
+use crate::common::Text;
+use crate::position::Pos;
+use crate::query::ast::*;
+
+/// Error inlining fragments with [`inline_fragments`]
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum InlineError {
+    #[error("{position}: fragment {name:?} is not defined")]
+    UndefinedFragment { name: String, position: Pos },
+    /// Spread at `position` closes a cycle, `names` lists the fragments of
+    /// the cycle starting and ending with the same fragment
+    #[error("{position}: fragments spread each other: {}", names.join(" -> "))]
+    FragmentCycle { names: Vec<String>, position: Pos },
+}
+
+struct Inliner<'d, 'a, T: Text<'a>> {
+    fragments: HashMap<&'d str, &'d FragmentDefinition<'a, T>>,
+    /// Fragments being inlined, outermost first
+    stack: Vec<&'d str>,
+}
+
+impl<'d, 'a, T: Text<'a>> Inliner<'d, 'a, T> {
+    fn selection_set(
+        &mut self,
+        set: &SelectionSet<'a, T>,
+    ) -> Result<SelectionSet<'a, T>, InlineError> {
+        let mut items = Vec::with_capacity(set.items.len());
+        for item in &set.items {
+            items.push(match item {
+                Selection::Field(field) => Selection::Field(Field {
+                    selection_set: self.selection_set(&field.selection_set)?,
+                    ..field.clone()
+                }),
+                Selection::InlineFragment(frag) => Selection::InlineFragment(InlineFragment {
+                    position: frag.position,
+                    type_condition: frag.type_condition.clone(),
+                    directives: frag.directives.clone(),
+                    selection_set: self.selection_set(&frag.selection_set)?,
+                }),
+                Selection::FragmentSpread(spread) => {
+                    Selection::InlineFragment(self.spread(spread)?)
+                }
+            });
+        }
+        Ok(SelectionSet {
+            span: set.span,
+            items,
+        })
+    }
+
+    fn spread(
+        &mut self,
+        spread: &FragmentSpread<'a, T>,
+    ) -> Result<InlineFragment<'a, T>, InlineError> {
+        let name = spread.fragment_name.as_ref();
+        let def = match self.fragments.get(name) {
+            Some(&def) => def,
+            None => {
+                return Err(InlineError::UndefinedFragment {
+                    name: name.to_string(),
+                    position: spread.position,
+                })
+            }
+        };
+        if let Some(start) = self.stack.iter().position(|&f| f == name) {
+            let mut names = self.stack[start..]
+                .iter()
+                .map(|f| f.to_string())
+                .collect::<Vec<_>>();
+            names.push(name.to_string());
+            return Err(InlineError::FragmentCycle {
+                names,
+                position: spread.position,
+            });
+        }
+
+        self.stack.push(def.name.as_ref());
+        let selection_set = self.selection_set(&def.selection_set)?;
+        self.stack.pop();
+        Ok(InlineFragment {
+            position: spread.position,
+            type_condition: Some(def.type_condition.clone()),
+            directives: spread.directives.clone(),
+            selection_set,
+        })
+    }
+}
+
+/// Replaces every fragment spread with an inline fragment
+///
+/// The inline fragment has the type condition and selections of the
+/// fragment definition, and the directives and position of the spread.
+/// Directives of fragment definitions can't be applied to inline fragments,
+/// so they are dropped. The returned document only contains the
+/// operations of `doc`, fragment definitions are removed.
+///
+/// Fails if a fragment spread by an operation, directly or through other
+/// fragments, isn't defined or spreads itself. Fragments which aren't
+/// spread by any operation aren't checked.
+pub fn inline_fragments<'a, T>(doc: &Document<'a, T>) -> Result<Document<'a, T>, InlineError>
+where
+    T: Text<'a>,
+{
+    let mut inliner = Inliner {
+        fragments: doc.fragments(),
+        stack: Vec::new(),
+    };
+    let mut definitions = Vec::new();
+    for def in &doc.definitions {
+        let op = match def {
+            Definition::Operation(op) => op,
+            Definition::Fragment(_) => continue,
+        };
+        let op = match op {
+            OperationDefinition::SelectionSet(s) => {
+                OperationDefinition::SelectionSet(inliner.selection_set(s)?)
+            }
+            OperationDefinition::Query(q) => OperationDefinition::Query(Query {
+                selection_set: inliner.selection_set(&q.selection_set)?,
+                ..q.clone()
+            }),
+            OperationDefinition::Mutation(m) => OperationDefinition::Mutation(Mutation {
+                selection_set: inliner.selection_set(&m.selection_set)?,
+                ..m.clone()
+            }),
+            OperationDefinition::Subscription(s) => {
+                OperationDefinition::Subscription(Subscription {
+                    selection_set: inliner.selection_set(&s.selection_set)?,
+                    ..s.clone()
+                })
+            }
+        };
+        definitions.push(Definition::Operation(op));
+    }
+    Ok(Document { definitions })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{inline_fragments, InlineError};
+    use crate::position::Pos;
+    use crate::query::parse_query;
+
+    #[test]
+    fn inline() {
+        let doc = parse_query::<&str>(
+            "query Q { user { ...UserFields @include(if: true) ... on Admin { ...Perms } } }
+             fragment UserFields on User @dir { name friends { ...Name } }
+             fragment Name on User { name }
+             fragment Perms on Admin { perms }
+             fragment Unused on User { id }",
+        )
+        .unwrap();
+        let inlined = inline_fragments(&doc).unwrap();
+        assert_eq!(
+            inlined.to_string(),
+            "query Q {
+  user {
+    ... on User @include(if: true) {
+      name
+      friends {
+        ... on User {
+          name
+        }
+      }
+    }
+    ... on Admin {
+      ... on Admin {
+        perms
+      }
+    }
+  }
+}
+"
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let doc = parse_query::<&str>(
+            "{ ...A }
+             fragment A on T { ...B }
+             fragment B on T { x { ...C } }
+             fragment C on T { ...A }",
+        )
+        .unwrap();
+        match inline_fragments(&doc) {
+            Err(InlineError::FragmentCycle { names, position }) => {
+                assert_eq!(names, vec!["A", "B", "C", "A"]);
+                assert_eq!(position.line, 4);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        let doc = parse_query::<&str>("{ a { ...Missing } }").unwrap();
+        assert_eq!(
+            inline_fragments(&doc),
+            Err(InlineError::UndefinedFragment {
+                name: "Missing".into(),
+                position: Pos {
+                    line: 1,
+                    column: 10,
+                    offset: 9,
+                },
+            })
+        );
+    }
+}
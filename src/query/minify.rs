@@ -1,36 +1,73 @@
-use crate::tokenizer::{Kind, Token, TokenStream};
-use combine::StreamOnce;
-use thiserror::Error;
+use alloc::string::{String, ToString};
+use core::fmt;
+#[cfg(feature = "std")]
+use std::io::{self, Write};
+
+use crate::tokenizer::{Kind, LexError, TokenStream};
 
 /// Error minifying query
-#[derive(Error, Debug)]
-#[error("query minify error: {}", _0)]
+#[derive(Debug)]
 pub struct MinifyError(String);
 
+impl fmt::Display for MinifyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "query minify error: {}", self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MinifyError {}
+
 pub fn minify_query(source: String) -> Result<String, MinifyError> {
-    let mut bits: Vec<&str> = Vec::new();
-    let mut stream = TokenStream::new(source.as_str());
+    let mut result = String::with_capacity(source.len());
+    minify_tokens(&source, |token| {
+        result.push_str(token);
+        Ok(())
+    })?;
+    Ok(result)
+}
+
+/// Writes a minified query to `w` token by token, without parsing it
+///
+/// Tokens are separated by a space only where they would otherwise merge
+/// into one. Since every token is written separately, `w` should usually
+/// be buffered. The writer is flushed at the end.
+#[cfg(feature = "std")]
+pub fn minify_query_streaming<W: Write>(source: &str, w: &mut W) -> Result<(), MinifyError> {
+    minify_tokens(source, |token| w.write_all(token.as_bytes()).map_err(io_error))?;
+    w.flush().map_err(io_error)
+}
+
+/// Passes the tokens of `source` to `write`, along with the spaces needed
+/// between them
+fn minify_tokens<F>(source: &str, mut write: F) -> Result<(), MinifyError>
+where
+    F: FnMut(&str) -> Result<(), MinifyError>,
+{
+    let mut stream = TokenStream::new(source);
     let mut prev_was_punctuator = false;
 
     loop {
-        match stream.uncons() {
-            Ok(x) => {
-                let token: Token = x;
+        match stream.next_token() {
+            Ok(token) => {
                 let is_non_punctuator = token.kind != Kind::Punctuator;
 
                 if prev_was_punctuator && is_non_punctuator {
-                    bits.push(" ");
+                    write(" ")?;
                 }
 
-                bits.push(token.value);
+                write(token.value)?;
                 prev_was_punctuator = is_non_punctuator;
             }
-            Err(ref e) if e == &combine::easy::Error::end_of_input() => break,
+            Err(LexError::EndOfInput) => return Ok(()),
             Err(e) => return Err(MinifyError(e.to_string())),
         }
     }
+}
 
-    Ok(bits.join(""))
+#[cfg(feature = "std")]
+fn io_error(e: io::Error) -> MinifyError {
+    MinifyError(e.to_string())
 }
 
 #[cfg(test)]
@@ -73,7 +110,19 @@ mod tests {
 
         assert_eq!(
             minified.unwrap_err().to_string(),
-            "query minify error: Unexpected unexpected character ';'"
+            "query minify error: unexpected character ';'"
+        );
+    }
+
+    #[test]
+    fn streaming() {
+        let source = "query Q($a: Int = 1) { f(a: $a, b: \"x y\") @skip(if: false) { ... F } }";
+        let mut buf = Vec::new();
+        super::minify_query_streaming(source, &mut buf).expect("minification failed");
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "query Q($a:Int=1){f(a:$a b:\"x y\")@skip(if:false){...F}}"
         );
+        assert!(super::minify_query_streaming("{ a; }", &mut Vec::new()).is_err());
     }
 }
@@ -1,12 +1,64 @@
 //! Query language AST and parsing utilities
 //!
+#[cfg(feature = "std")]
+mod analysis;
 mod ast;
+#[cfg(feature = "std")]
+mod canonical;
+mod compare;
+#[cfg(feature = "std")]
 mod error;
 mod format;
+#[cfg(feature = "std")]
 mod grammar;
+#[cfg(feature = "std")]
+mod inline;
 mod minify;
+#[cfg(feature = "std")]
+mod recover;
+#[cfg(feature = "std")]
+mod rename;
+#[cfg(feature = "std")]
+mod skip_include;
+#[cfg(feature = "std")]
+mod substitute;
+#[cfg(feature = "std")]
+mod tree;
+#[cfg(feature = "std")]
+mod validate;
+pub mod visit;
 
+#[cfg(feature = "std")]
+pub use self::analysis::{
+    collect_variables, depth, depth_with_fragments, field_count, field_count_with_fragments,
+    fragment_cycles, is_cacheable, leaf_types, max_breadth, undefined_fragment_spreads,
+    unused_fragments,
+};
 pub use self::ast::*;
+#[cfg(feature = "std")]
+pub use self::canonical::canonicalize;
+#[cfg(all(feature = "std", feature = "sha2"))]
+pub use self::canonical::fingerprint;
+#[cfg(feature = "std")]
 pub use self::error::ParseError;
+#[cfg(feature = "std")]
 pub use self::grammar::*;
-pub use self::minify::minify_query;
+#[cfg(feature = "std")]
+pub use self::inline::{inline_fragments, InlineError};
+pub use self::minify::{minify_query, MinifyError};
+#[cfg(feature = "std")]
+pub use self::minify::minify_query_streaming;
+#[cfg(feature = "std")]
+pub use self::recover::parse_recovering;
+#[cfg(feature = "std")]
+pub use self::rename::rename_field_selections;
+#[cfg(feature = "std")]
+pub use self::skip_include::apply_skip_include;
+#[cfg(feature = "std")]
+pub use self::substitute::substitute_variables;
+#[cfg(feature = "std")]
+pub use self::tree::{to_selection_tree, SelectionTree};
+#[cfg(feature = "std")]
+pub use self::validate::{
+    check_skip_include, check_unique_arguments, validate_breadth, ValidationError,
+};
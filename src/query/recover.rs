@@ -0,0 +1,391 @@
+//! Parsing queries which contain syntax errors
+//!
+use std::marker::PhantomData;
+
+use combine::stream::ResetStream;
+use combine::{optional, parser, position, Parser, Positioned, StreamOnce};
+
+use crate::common::{arguments, directives};
+use crate::helpers::{ident, name, punct};
+use crate::query::ast::*;
+use crate::query::error::ParseError;
+use crate::query::grammar::{
+    definition, fragment_name, selection, type_system_definition, variable_definitions,
+};
+use crate::tokenizer::{Kind, Token, TokenStream};
+
+fn is_punct(tok: &Token, value: &str) -> bool {
+    tok.kind == Kind::Punctuator && tok.value == value
+}
+
+struct Recovering<'a> {
+    tokens: TokenStream<'a>,
+    errors: Vec<ParseError>,
+    /// Set once the input ended or can't be tokenized any further
+    finished: bool,
+}
+
+impl<'a> Recovering<'a> {
+    fn peek(&mut self) -> Option<Token<'a>> {
+        let checkpoint = self.tokens.checkpoint();
+        let tok = self.tokens.uncons().ok();
+        self.tokens.reset(checkpoint).ok();
+        tok
+    }
+
+    fn run<P>(&mut self, mut p: P) -> Result<P::Output, ParseError>
+    where
+        P: Parser<TokenStream<'a>>,
+    {
+        let checkpoint = self.tokens.checkpoint();
+        match p.parse_stream(&mut self.tokens).into_result() {
+            Ok((output, _)) => Ok(output),
+            Err(e) => {
+                let error = e.into_inner().error;
+                // Rewind to the unexpected token, which may have been consumed
+                self.tokens.reset(checkpoint).ok();
+                while self.tokens.position() < error.position {
+                    if self.tokens.uncons().is_err() {
+                        break;
+                    }
+                }
+                Err(error.into())
+            }
+        }
+    }
+
+    /// Records an error unless an earlier one already ended the input
+    fn error(&mut self, error: ParseError) {
+        if !self.finished {
+            self.errors.push(error);
+        }
+        if self.peek().is_none() {
+            self.finished = true;
+        }
+    }
+
+    /// Skips the rest of a malformed selection
+    ///
+    /// Stops before the next name, `...` or `}` outside of brackets, but
+    /// only after the input advanced past `start`, so that the same
+    /// selection isn't parsed again.
+    fn skip_selection(&mut self, start: usize) {
+        let mut depth = 0usize;
+        while let Some(tok) = self.peek() {
+            if depth == 0 {
+                if is_punct(&tok, "}") {
+                    return;
+                }
+                let boundary = tok.kind == Kind::Name || is_punct(&tok, "...");
+                if boundary && self.tokens.offset() > start {
+                    return;
+                }
+            }
+            if tok.kind == Kind::Punctuator {
+                match tok.value {
+                    "(" | "[" | "{" => depth += 1,
+                    ")" | "]" | "}" => depth = depth.saturating_sub(1),
+                    _ => {}
+                }
+            }
+            self.tokens.uncons().ok();
+        }
+    }
+
+    /// Skips the rest of a malformed definition
+    ///
+    /// Stops before the next `query`, `mutation`, `subscription` or
+    /// `fragment` keyword outside of brackets, or right after a `}` closing
+    /// an outermost block, so a selection set following the error is
+    /// skipped as part of the definition. As in `skip_selection`, the input
+    /// is advanced past `start` first.
+    fn skip_definition(&mut self, start: usize) {
+        let mut depth = 0usize;
+        while let Some(tok) = self.peek() {
+            if depth == 0 && tok.kind == Kind::Name && self.tokens.offset() > start {
+                if let "query" | "mutation" | "subscription" | "fragment" = tok.value {
+                    break;
+                }
+            }
+            self.tokens.uncons().ok();
+            if tok.kind == Kind::Punctuator {
+                match tok.value {
+                    "(" | "[" | "{" => depth += 1,
+                    "}" if depth == 1 => break,
+                    ")" | "]" | "}" => depth = depth.saturating_sub(1),
+                    _ => {}
+                }
+            }
+        }
+        if self.peek().is_none() {
+            self.finished = true;
+        }
+    }
+
+    fn selection_set<S: Text<'a>>(&mut self) -> Result<SelectionSet<'a, S>, ParseError> {
+        let start = self.run(position().skip(punct("{")))?;
+        let errors = self.errors.len();
+        let mut items = Vec::new();
+        loop {
+            match self.peek() {
+                Some(ref tok) if is_punct(tok, "}") => {
+                    if items.is_empty() && self.errors.len() == errors {
+                        // Reports the missing selection
+                        if let Err(e) = self.run(parser(selection::<S>)) {
+                            self.error(e);
+                        }
+                    }
+                    break;
+                }
+                Some(_) => {}
+                None => break,
+            }
+            let offset = self.tokens.offset();
+            match self.selection() {
+                Ok(item) => items.push(item),
+                Err(e) => {
+                    self.error(e);
+                    self.skip_selection(offset);
+                }
+            }
+        }
+        let end = match self.run(position().skip(punct("}"))) {
+            Ok(end) => end,
+            Err(e) => {
+                self.error(e);
+                self.tokens.position()
+            }
+        };
+        Ok(SelectionSet {
+            span: (start, end),
+            items,
+        })
+    }
+
+    fn selection<S: Text<'a>>(&mut self) -> Result<Selection<'a, S>, ParseError> {
+        if self.run(optional(punct("...")))?.is_none() {
+            let (position, name_or_alias, opt_name, arguments, directives) = self.run((
+                position(),
+                name::<'a, S>(),
+                optional(punct(":").with(name::<'a, S>())),
+                parser(arguments),
+                parser(directives),
+            ))?;
+            let (name, alias) = match opt_name {
+                Some(name) => (name, Some(name_or_alias)),
+                None => (name_or_alias, None),
+            };
+            let selection_set = match self.peek() {
+                Some(ref tok) if is_punct(tok, "{") => self.selection_set()?,
+                _ => SelectionSet {
+                    span: (position, position),
+                    items: Vec::new(),
+                },
+            };
+            return Ok(Selection::Field(Field {
+                position,
+                end: self.tokens.end_position(),
+                alias,
+                name,
+                arguments,
+                directives,
+                selection_set,
+            }));
+        }
+        let position = self.run(position())?;
+        if let Some(fragment_name) = self.run(optional(parser(fragment_name::<S>)))? {
+            let directives = self.run(parser(directives))?;
+            return Ok(Selection::FragmentSpread(FragmentSpread {
+                position,
+                fragment_name,
+                directives,
+            }));
+        }
+        let (type_condition, directives) = self.run((
+            optional(
+                ident("on")
+                    .with(name::<'a, S>())
+                    .map(|v| TypeCondition::On(v, PhantomData)),
+            ),
+            parser(directives),
+        ))?;
+        let selection_set = self.selection_set()?;
+        Ok(Selection::InlineFragment(InlineFragment {
+            position,
+            type_condition,
+            directives,
+            selection_set,
+        }))
+    }
+
+    fn operation<S: Text<'a>>(
+        &mut self,
+        keyword: &'static str,
+    ) -> Result<Definition<'a, S>, ParseError> {
+        let (position, name, variable_definitions, directives) = self.run((
+            position().skip(ident(keyword)),
+            optional(name::<'a, S>()),
+            parser(variable_definitions),
+            parser(directives),
+        ))?;
+        let selection_set = self.selection_set()?;
+        Ok(Definition::Operation(match keyword {
+            "query" => OperationDefinition::Query(Query {
+                position,
+                name,
+                variable_definitions,
+                directives,
+                selection_set,
+            }),
+            "mutation" => OperationDefinition::Mutation(Mutation {
+                position,
+                name,
+                variable_definitions,
+                directives,
+                selection_set,
+            }),
+            _ => OperationDefinition::Subscription(Subscription {
+                position,
+                name,
+                variable_definitions,
+                directives,
+                selection_set,
+            }),
+        }))
+    }
+
+    fn definition<S: Text<'a>>(&mut self) -> Result<Definition<'a, S>, ParseError> {
+        let tok = self.peek();
+        match tok.map(|t| (t.kind, t.value)) {
+            Some((Kind::Punctuator, "{")) => Ok(Definition::Operation(
+                OperationDefinition::SelectionSet(self.selection_set()?),
+            )),
+            Some((Kind::Name, "query")) => self.operation("query"),
+            Some((Kind::Name, "mutation")) => self.operation("mutation"),
+            Some((Kind::Name, "subscription")) => self.operation("subscription"),
+            Some((Kind::Name, "fragment")) => {
+                let (position, name, type_condition, directives) = self.run((
+                    position().skip(ident("fragment")),
+                    parser(fragment_name),
+                    ident("on")
+                        .with(name::<'a, S>())
+                        .map(|v| TypeCondition::On(v, PhantomData)),
+                    parser(directives),
+                ))?;
+                let selection_set = self.selection_set()?;
+                Ok(Definition::Fragment(FragmentDefinition {
+                    position,
+                    name,
+                    type_condition,
+                    directives,
+                    selection_set,
+                }))
+            }
+            // Reports the error the same way as `parse_query`
+            _ => self.run(parser(definition).or(parser(type_system_definition))),
+        }
+    }
+}
+
+/// Parses a query, skipping over malformed selections and definitions
+///
+/// Returns the document along with all syntax errors found, each of which
+/// has the [position](ParseError::position) of the offending token. When a
+/// selection can't be parsed, the error is recorded and parsing resumes at
+/// the next field, fragment spread or inline fragment of the same selection
+/// set, so the other selections are kept. A selection set missing its
+/// closing brace is kept too. Any other error, for example in variable
+/// definitions, leaves the definition out of the document, and parsing
+/// resumes at the next definition keyword or after the closing brace of
+/// the malformed definition.
+///
+/// The document is returned even if it's empty. If there are no errors it
+/// is the same as the one returned by [`parse_query`](crate::parse_query).
+pub fn parse_recovering<'a, S>(s: &'a str) -> (Document<'a, S>, Vec<ParseError>)
+where
+    S: Text<'a>,
+{
+    let mut state = Recovering {
+        tokens: TokenStream::new(s),
+        errors: Vec::new(),
+        finished: false,
+    };
+    let mut definitions = Vec::new();
+    while !state.finished && (definitions.is_empty() || state.tokens.offset() < s.len()) {
+        let offset = state.tokens.offset();
+        match state.definition() {
+            Ok(def) => definitions.push(def),
+            Err(e) => {
+                state.error(e);
+                state.skip_definition(offset);
+            }
+        }
+    }
+    (Document { definitions }, state.errors)
+}
+
+#[cfg(test)]
+mod test {
+    use super::parse_recovering;
+    use crate::query::parse_query;
+
+    fn recover(s: &str) -> (String, Vec<String>) {
+        let (doc, errors) = parse_recovering::<&str>(s);
+        (
+            doc.to_string(),
+            errors.into_iter().map(|e| e.to_string()).collect(),
+        )
+    }
+
+    #[test]
+    fn valid() {
+        let query = "query Q($a: Int) { a(x: $a) ...F ... on T { b } } fragment F on T { c }";
+        let (doc, errors) = parse_recovering::<&str>(query);
+        assert!(errors.is_empty());
+        assert_eq!(doc, parse_query::<&str>(query).unwrap());
+    }
+
+    #[test]
+    fn malformed_selections() {
+        let (doc, errors) =
+            recover("{ a b(x: ) { c } d { e f( } ...on T { g: } h ... @ }\n{ i ! j }");
+        assert_eq!(
+            doc,
+            "{\n  a\n  d {\n    e\n  }\n  ... on T {\n  }\n  h\n}\n\n{\n  i\n  j\n}\n"
+        );
+        assert_eq!(errors.len(), 5);
+        assert!(errors[0].starts_with("query parse error: Parse error at 1:10\n"));
+    }
+
+    #[test]
+    fn unclosed() {
+        let (doc, errors) = recover("query { a { b c(x: 1) ");
+        assert_eq!(doc, "query {\n  a {\n    b\n    c(x: 1)\n  }\n}\n");
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn malformed_definitions() {
+        let (doc, errors) = recover("{ a } query Q($x) { b } { c }");
+        assert_eq!(doc, "{\n  a\n}\n\n{\n  c\n}\n");
+        assert_eq!(errors.len(), 1);
+        let (doc, errors) = recover(
+            "type T { a: Int }\nquery Q($x: [Int) { b }\nfragment F T { c }\nquery R { d }",
+        );
+        assert_eq!(doc, "query R {\n  d\n}\n");
+        assert_eq!(errors.len(), 3);
+        let (doc, errors) = recover("");
+        assert_eq!(doc, "");
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn error_positions() {
+        let (_, errors) = parse_recovering::<&str>("{ a(x: ) }\nquery ($y) { b }");
+        let positions = errors
+            .iter()
+            .map(|e| (e.position().line, e.position().column))
+            .collect::<Vec<_>>();
+        assert_eq!(positions, vec![(1, 8), (2, 10)]);
+    }
+}
@@ -0,0 +1,165 @@
+//! Renaming selected fields after a schema change
+//!
+use std::collections::HashSet;
+
+use crate::common::Text;
+use crate::query::analysis::field_type;
+use crate::query::ast::*;
+use crate::schema;
+use crate::schema::field_owners;
+
+struct Renamer<'s, 'a, T: Text<'a>> {
+    schema: &'s schema::Document<'a, T>,
+    owners: HashSet<String>,
+    from: &'s str,
+    to: T,
+    renamed: usize,
+}
+
+impl<'s, 'a, T: Text<'a>> Renamer<'s, 'a, T> {
+    fn selection_set(&mut self, parent: &str, set: &mut SelectionSet<'a, T>) {
+        for item in &mut set.items {
+            match item {
+                Selection::Field(field) => {
+                    let affected = self.owners.contains(parent) && field.name.as_ref() == self.from;
+                    let mut typ = field_type(self.schema, parent, field.name.as_ref());
+                    if typ.is_none() && affected {
+                        // The schema may already have the new name
+                        typ = field_type(self.schema, parent, self.to.as_ref());
+                    }
+                    if affected {
+                        field.name = self.to.clone();
+                        self.renamed += 1;
+                    }
+                    if let Some(typ) = typ {
+                        self.selection_set(typ.base_name().as_ref(), &mut field.selection_set);
+                    }
+                }
+                Selection::InlineFragment(frag) => match frag.type_condition {
+                    Some(TypeCondition::On(ref cond, _)) => {
+                        let cond = cond.clone();
+                        self.selection_set(cond.as_ref(), &mut frag.selection_set);
+                    }
+                    None => self.selection_set(parent, &mut frag.selection_set),
+                },
+                Selection::FragmentSpread(_) => {}
+            }
+        }
+    }
+}
+
+/// Renames selections of field `from` of the type `type_name` to `to`
+///
+/// This is the query counterpart of
+/// [`schema::rename_field`](crate::schema::rename_field): when `type_name`
+/// is an interface, selections of the field on implementing types are
+/// renamed too. Field types are resolved against `schema`, which may be
+/// either the original or the renamed schema. Aliases are kept as is, so
+/// an unaliased selection changes its response key. Returns the number of
+/// renamed selections.
+pub fn rename_field_selections<'a, T>(
+    doc: &mut Document<'a, T>,
+    schema: &schema::Document<'a, T>,
+    type_name: &str,
+    from: &str,
+    to: T,
+) -> usize
+where
+    T: Text<'a>,
+{
+    let mut renamer = Renamer {
+        schema,
+        owners: field_owners(schema, type_name),
+        from,
+        to,
+        renamed: 0,
+    };
+    for def in &mut doc.definitions {
+        match def {
+            Definition::Operation(op) => {
+                let root = match schema.root_type_name(op.operation_type()) {
+                    Some(root) => root,
+                    None => continue,
+                };
+                let set = match op {
+                    OperationDefinition::SelectionSet(s) => s,
+                    OperationDefinition::Query(q) => &mut q.selection_set,
+                    OperationDefinition::Mutation(m) => &mut m.selection_set,
+                    OperationDefinition::Subscription(s) => &mut s.selection_set,
+                };
+                renamer.selection_set(root, set);
+            }
+            Definition::Fragment(frag) => {
+                let TypeCondition::On(ref cond, _) = frag.type_condition;
+                let cond = cond.clone();
+                renamer.selection_set(cond.as_ref(), &mut frag.selection_set);
+            }
+        }
+    }
+    renamer.renamed
+}
+
+#[cfg(test)]
+mod test {
+    use super::rename_field_selections;
+    use crate::query::parse_query;
+    use crate::schema::{parse_schema, rename_field};
+
+    const SCHEMA: &str = "
+        type Query { node: Node me: User }
+        interface Node { id: ID! }
+        type User implements Node { id: ID! friends: [User] }
+        type Post { id: ID! }
+    ";
+
+    const QUERY: &str = "
+        query { node { id ... on User { id } ... on Post { id } } me { key: id friends { id } } }
+        fragment F on User { id }
+    ";
+
+    const EXPECTED: &str = "query {
+  node {
+    key
+    ... on User {
+      key
+    }
+    ... on Post {
+      id
+    }
+  }
+  me {
+    key: key
+    friends {
+      key
+    }
+  }
+}
+
+fragment F on User {
+  key
+}
+";
+
+    #[test]
+    fn original_schema() {
+        let schema = parse_schema::<&str>(SCHEMA).unwrap();
+        let mut doc = parse_query::<&str>(QUERY).unwrap();
+        assert_eq!(
+            rename_field_selections(&mut doc, &schema, "Node", "id", "key"),
+            5
+        );
+        assert_eq!(doc.to_string(), EXPECTED);
+    }
+
+    #[test]
+    fn renamed_schema() {
+        let mut schema = parse_schema::<&str>(SCHEMA).unwrap();
+        assert!(rename_field(&mut schema, "Node", "id", "key"));
+        let mut doc = parse_query::<&str>(QUERY).unwrap();
+        assert_eq!(
+            rename_field_selections(&mut doc, &schema, "Node", "id", "key"),
+            5
+        );
+        assert_eq!(doc.to_string(), EXPECTED);
+    }
+}
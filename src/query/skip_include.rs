@@ -0,0 +1,155 @@
+//! Evaluating `@skip` and `@include` ahead of execution
+//!
+use std::collections::HashMap;
+
+use crate::common::{Directive, Text};
+use crate::query::ast::*;
+
+struct SkipInclude<'v> {
+    vars: HashMap<&'v str, bool>,
+}
+
+impl<'v> SkipInclude<'v> {
+    /// Returns the value of the `if` argument of `@skip` or `@include`
+    fn condition<'a, T: Text<'a>>(&self, directive: &Directive<'a, T>) -> Option<bool> {
+        let name = directive.name.as_ref();
+        if name != "skip" && name != "include" {
+            return None;
+        }
+        match directive.argument("if")? {
+            Value::Boolean(b) => Some(*b),
+            Value::Variable(var, _) => self.vars.get(var.as_ref()).copied(),
+            _ => None,
+        }
+    }
+
+    /// Removes resolved directives, returns `false` if the node is excluded
+    fn directives<'a, T: Text<'a>>(&self, directives: &mut Vec<Directive<'a, T>>) -> bool {
+        let mut included = true;
+        directives.retain(|dir| match self.condition(dir) {
+            Some(cond) => {
+                included &= cond != (dir.name.as_ref() == "skip");
+                false
+            }
+            None => true,
+        });
+        included
+    }
+
+    /// Removes excluded selections of `set`, returns `false` if nothing is
+    /// left of a set which had selections
+    fn selection_set<'a, T: Text<'a>>(&self, set: &mut SelectionSet<'a, T>) -> bool {
+        if set.items.is_empty() {
+            return true;
+        }
+        let mut idx = 0;
+        while idx < set.items.len() {
+            let keep = match &mut set.items[idx] {
+                Selection::Field(field) => {
+                    self.directives(&mut field.directives)
+                        && self.selection_set(&mut field.selection_set)
+                }
+                Selection::InlineFragment(frag) => {
+                    self.directives(&mut frag.directives)
+                        && self.selection_set(&mut frag.selection_set)
+                }
+                Selection::FragmentSpread(spread) => self.directives(&mut spread.directives),
+            };
+            if keep {
+                idx += 1;
+            } else {
+                set.items.remove(idx);
+            }
+        }
+        !set.items.is_empty()
+    }
+}
+
+/// Removes selections of `op` excluded by `@skip` or `@include`
+///
+/// The `if` argument of each directive is either a boolean literal or a
+/// variable, looked up in `vars` or, if missing there, in the default
+/// values of the operation's variables. Selections which a directive
+/// excludes are removed, along with everything in them. Directives which
+/// are resolved but don't exclude the selection are removed from it, while
+/// ones using variables without a value are kept.
+///
+/// A field or inline fragment left with no selections is removed too, and
+/// so on up to the operation, whose selection set may end up empty.
+///
+/// Fragments spread into the operation are separate definitions, so their
+/// selections aren't changed.
+pub fn apply_skip_include<'a, T>(op: &mut OperationDefinition<'a, T>, vars: &HashMap<String, bool>)
+where
+    T: Text<'a>,
+{
+    let (variable_definitions, selection_set) = match op {
+        OperationDefinition::SelectionSet(s) => (&[][..], s),
+        OperationDefinition::Query(q) => (&q.variable_definitions[..], &mut q.selection_set),
+        OperationDefinition::Mutation(m) => (&m.variable_definitions[..], &mut m.selection_set),
+        OperationDefinition::Subscription(s) => (&s.variable_definitions[..], &mut s.selection_set),
+    };
+    let mut values = vars
+        .iter()
+        .map(|(name, value)| (name.as_str(), *value))
+        .collect::<HashMap<_, _>>();
+    for var in variable_definitions {
+        if let Some(Value::Boolean(default)) = var.default_value {
+            values.entry(var.name.as_ref()).or_insert(default);
+        }
+    }
+    SkipInclude { vars: values }.selection_set(selection_set);
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::apply_skip_include;
+    use crate::query::{parse_query, Definition};
+
+    #[test]
+    fn prune() {
+        let mut doc = parse_query::<&str>(
+            "query ($a: Boolean!, $b: Boolean!, $c: Boolean = false, $d: Boolean!) {
+               a @include(if: $a) { x }
+               b @skip(if: $a) { y }
+               c @include(if: $b) @skip(if: $d) @other
+               d @include(if: true) @skip(if: $c)
+               ... on T @skip(if: true) { e }
+               ...F @include(if: $missing)
+               ... @include(if: $b) { f @skip(if: false) }
+               g { h @skip(if: $a) ... on T { i @include(if: $d) } }
+               j { k @skip(if: $a) l }
+             }",
+        )
+        .unwrap();
+        let op = match &mut doc.definitions[0] {
+            Definition::Operation(op) => op,
+            _ => unreachable!(),
+        };
+        let mut vars = HashMap::new();
+        vars.insert("a".to_string(), true);
+        vars.insert("b".to_string(), true);
+        vars.insert("d".to_string(), false);
+        apply_skip_include(op, &vars);
+        assert_eq!(
+            doc.to_string(),
+            "query($a: Boolean!, $b: Boolean!, $c: Boolean = false, $d: Boolean!) {
+  a {
+    x
+  }
+  c @other
+  d
+  ...F @include(if: $missing)
+  ... {
+    f
+  }
+  j {
+    l
+  }
+}
+"
+        );
+    }
+}
@@ -0,0 +1,201 @@
+//! Inlining variable values into operations
+//!
+use std::collections::HashMap;
+
+use crate::common::Text;
+use crate::query::analysis::collect_variables;
+use crate::query::ast::*;
+use crate::query::visit::{walk_selection_set_mut, VisitControl, VisitorMut};
+
+struct Substitution<'a, T: Text<'a>> {
+    values: HashMap<String, Value<'a, T>>,
+    unresolved: Vec<T>,
+}
+
+impl<'a, T: Text<'a>> Substitution<'a, T> {
+    fn value(&mut self, value: &mut Value<'a, T>) {
+        match value {
+            Value::Variable(name, _) => match self.values.get(name.as_ref()) {
+                Some(v) => *value = v.clone(),
+                None => {
+                    if !self.unresolved.contains(name) {
+                        self.unresolved.push(name.clone());
+                    }
+                }
+            },
+            Value::List(items) => items.iter_mut().for_each(|v| self.value(v)),
+            Value::Object(fields) => fields.values_mut().for_each(|v| self.value(v)),
+            _ => {}
+        }
+    }
+
+    fn arguments(&mut self, arguments: &mut [(T, Value<'a, T>)]) {
+        arguments.iter_mut().for_each(|(_, v)| self.value(v));
+    }
+
+    fn directives(&mut self, directives: &mut [Directive<'a, T>]) {
+        for directive in directives {
+            self.arguments(&mut directive.arguments);
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> VisitorMut<'a, T> for Substitution<'a, T> {
+    fn visit_field(&mut self, field: &mut Field<'a, T>) -> VisitControl {
+        self.arguments(&mut field.arguments);
+        self.directives(&mut field.directives);
+        VisitControl::Continue
+    }
+    fn visit_inline_fragment(&mut self, fragment: &mut InlineFragment<'a, T>) -> VisitControl {
+        self.directives(&mut fragment.directives);
+        VisitControl::Continue
+    }
+    fn visit_fragment_spread(&mut self, spread: &mut FragmentSpread<'a, T>) -> VisitControl {
+        self.directives(&mut spread.directives);
+        VisitControl::Continue
+    }
+}
+
+/// Replaces variables used by `op` with their values
+///
+/// Each variable is replaced with a copy of its value in `vars`, or with
+/// its default value if `vars` doesn't have it. Arguments of fields and
+/// directives are searched recursively, including lists and input objects.
+///
+/// Variables with neither a value nor a default are left in place, and
+/// their names are returned, each once, in the order they are first used.
+/// Fragments spread into the operation are separate definitions, so
+/// variables used within them aren't replaced. Definitions of variables
+/// which are no longer used by the operation, nor by the fragments it
+/// spreads, are removed. Fragments are looked up in `fragments`, which is
+/// usually the result of [`Document::fragments`].
+pub fn substitute_variables<'a, T>(
+    op: &mut OperationDefinition<'a, T>,
+    fragments: &HashMap<&str, &FragmentDefinition<'a, T>>,
+    vars: &HashMap<String, Value<'a, T>>,
+) -> Vec<T>
+where
+    T: Text<'a>,
+{
+    let (variable_definitions, directives, selection_set) = match op {
+        OperationDefinition::SelectionSet(s) => (None, None, s),
+        OperationDefinition::Query(q) => (
+            Some(&mut q.variable_definitions),
+            Some(&mut q.directives),
+            &mut q.selection_set,
+        ),
+        OperationDefinition::Mutation(m) => (
+            Some(&mut m.variable_definitions),
+            Some(&mut m.directives),
+            &mut m.selection_set,
+        ),
+        OperationDefinition::Subscription(s) => (
+            Some(&mut s.variable_definitions),
+            Some(&mut s.directives),
+            &mut s.selection_set,
+        ),
+    };
+
+    let mut values = vars.clone();
+    for var in variable_definitions.iter().flat_map(|vars| vars.iter()) {
+        if let Some(default) = &var.default_value {
+            values
+                .entry(var.name.as_ref().to_string())
+                .or_insert_with(|| default.clone());
+        }
+    }
+    let mut substitution = Substitution {
+        values,
+        unresolved: Vec::new(),
+    };
+    if let Some(directives) = directives {
+        substitution.directives(directives);
+    }
+    walk_selection_set_mut(&mut substitution, selection_set);
+
+    let used: Vec<String> = collect_variables(op, fragments)
+        .into_iter()
+        .map(|name| name.to_string())
+        .collect();
+    let variable_definitions = match op {
+        OperationDefinition::SelectionSet(_) => return substitution.unresolved,
+        OperationDefinition::Query(q) => &mut q.variable_definitions,
+        OperationDefinition::Mutation(m) => &mut m.variable_definitions,
+        OperationDefinition::Subscription(s) => &mut s.variable_definitions,
+    };
+    variable_definitions.retain(|var| used.iter().any(|name| name == var.name.as_ref()));
+    substitution.unresolved
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::substitute_variables;
+    use crate::query::{parse_query, Definition, Value};
+
+    #[test]
+    fn substitute() {
+        let mut doc = parse_query::<&str>(
+            "query Q($id: ID!, $n: Int = 10, $flag: Boolean!, $missing: String, $unused: Int)
+               @cached(ttl: $n) {
+               user(id: $id) @include(if: $flag) {
+                 friends(first: $n, filter: { name: $missing, tags: [$id] }) { name }
+                 ... on User @skip(if: $flag) { ...F @include(if: $missing) }
+               }
+             }",
+        )
+        .unwrap();
+        let mut vars = HashMap::new();
+        vars.insert("id".to_string(), Value::String("u1".into()));
+        vars.insert("flag".to_string(), Value::Boolean(true));
+        vars.insert("n".to_string(), Value::Int(3.into()));
+        let op = match &mut doc.definitions[0] {
+            Definition::Operation(op) => op,
+            _ => unreachable!(),
+        };
+        assert_eq!(substitute_variables(op, &HashMap::new(), &vars), vec!["missing"]);
+        assert_eq!(
+            doc.to_string(),
+            r#"query Q($missing: String) @cached(ttl: 3) {
+  user(id: "u1") @include(if: true) {
+    friends(first: 3, filter: {name: $missing, tags: ["u1"]}) {
+      name
+    }
+    ... on User @skip(if: true) {
+      ...F @include(if: $missing)
+    }
+  }
+}
+"#
+        );
+    }
+
+    #[test]
+    fn defaults() {
+        let mut doc = parse_query::<&str>("query ($n: Int = 10) { a(n: $n) }").unwrap();
+        let op = match &mut doc.definitions[0] {
+            Definition::Operation(op) => op,
+            _ => unreachable!(),
+        };
+        assert!(substitute_variables(op, &HashMap::new(), &HashMap::new()).is_empty());
+        assert_eq!(doc.to_string(), "query {\n  a(n: 10)\n}\n");
+    }
+
+    #[test]
+    fn used_in_fragments() {
+        let source = "query Q($id: ID!) { ...F } fragment F on Query { user(id: $id) }";
+        let mut doc = parse_query::<&str>(source).unwrap();
+        let fragments = parse_query::<&str>(source).unwrap();
+        let op = match &mut doc.definitions[0] {
+            Definition::Operation(op) => op,
+            _ => unreachable!(),
+        };
+        let mut vars = HashMap::new();
+        vars.insert("id".to_string(), Value::String("u1".into()));
+        assert!(substitute_variables(op, &fragments.fragments(), &vars).is_empty());
+        assert_eq!(op.to_string(), "query Q($id: ID!) {\n  ...F\n}\n");
+        assert!(substitute_variables(op, &HashMap::new(), &vars).is_empty());
+        assert_eq!(op.to_string(), "query Q {\n  ...F\n}\n");
+    }
+}
@@ -0,0 +1,136 @@
+//! Plain tree of selected fields
+//!
+use std::collections::HashMap;
+
+use crate::common::{Text, Value};
+use crate::query::ast::*;
+
+/// Field selected by an operation, with fragments inlined
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SelectionTree<'a, T: Text<'a>> {
+    pub field: T,
+    pub alias: Option<T>,
+    pub arguments: Vec<(T, Value<'a, T>)>,
+    /// Type condition of the innermost fragment the field was selected in
+    pub type_condition: Option<T>,
+    pub children: Vec<SelectionTree<'a, T>>,
+}
+
+struct Builder<'f, 'a, T: Text<'a>> {
+    fragments: &'f HashMap<&'f str, &'f FragmentDefinition<'a, T>>,
+    path: Vec<&'f str>,
+}
+
+impl<'f, 'a, T: Text<'a>> Builder<'f, 'a, T> {
+    fn selection_set(
+        &mut self,
+        set: &SelectionSet<'a, T>,
+        type_condition: Option<&T>,
+        result: &mut Vec<SelectionTree<'a, T>>,
+    ) {
+        for item in &set.items {
+            match item {
+                Selection::Field(field) => {
+                    let mut children = Vec::new();
+                    self.selection_set(&field.selection_set, None, &mut children);
+                    result.push(SelectionTree {
+                        field: field.name.clone(),
+                        alias: field.alias.clone(),
+                        arguments: field.arguments.clone(),
+                        type_condition: type_condition.cloned(),
+                        children,
+                    });
+                }
+                Selection::InlineFragment(frag) => {
+                    let cond = match frag.type_condition {
+                        Some(TypeCondition::On(ref cond, _)) => Some(cond),
+                        None => type_condition,
+                    };
+                    self.selection_set(&frag.selection_set, cond, result);
+                }
+                Selection::FragmentSpread(spread) => {
+                    let name = spread.fragment_name.as_ref();
+                    let frag = match self.fragments.get_key_value(name) {
+                        Some((&name, &frag)) if !self.path.contains(&name) => {
+                            self.path.push(name);
+                            frag
+                        }
+                        _ => continue,
+                    };
+                    let TypeCondition::On(ref cond, _) = frag.type_condition;
+                    self.selection_set(&frag.selection_set, Some(cond), result);
+                    self.path.pop();
+                }
+            }
+        }
+    }
+}
+
+/// Builds a tree of fields selected by `op`, returning the top-level fields
+///
+/// Inline fragments and fragment spreads are replaced by the fields they
+/// select, the fragment's type condition is recorded in each field instead.
+/// Fragments are looked up in `fragments`, which is usually the result of
+/// [`Document::fragments`]; unknown and recursive spreads are skipped.
+/// Directives are not represented, so fields with `@skip` or `@include`
+/// are included unconditionally.
+pub fn to_selection_tree<'a, T>(
+    op: &OperationDefinition<'a, T>,
+    fragments: &HashMap<&str, &FragmentDefinition<'a, T>>,
+) -> Vec<SelectionTree<'a, T>>
+where
+    T: Text<'a>,
+{
+    let mut result = Vec::new();
+    Builder {
+        fragments,
+        path: Vec::new(),
+    }
+    .selection_set(op.selection_set(), None, &mut result);
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::{to_selection_tree, SelectionTree};
+    use crate::query::{parse_query, Definition};
+
+    fn leaf<'a>(field: &'a str, type_condition: Option<&'a str>) -> SelectionTree<'a, &'a str> {
+        SelectionTree {
+            field,
+            alias: None,
+            arguments: Vec::new(),
+            type_condition,
+            children: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn fragments_inlined() {
+        let doc = parse_query::<&str>(
+            "query { u: user(id: 1) { id ...F ... on Admin { level } ... @include(if: $x) { n } } }
+             fragment F on User { name ... { email } ...F }",
+        )
+        .unwrap();
+        let op = match doc.definitions[0] {
+            Definition::Operation(ref op) => op,
+            _ => unreachable!(),
+        };
+        let tree = to_selection_tree(op, &doc.fragments());
+        assert_eq!(tree.len(), 1);
+        assert_eq!(tree[0].field, "user");
+        assert_eq!(tree[0].alias, Some("u"));
+        assert_eq!(tree[0].arguments[0].0, "id");
+        assert_eq!(
+            tree[0].children,
+            vec![
+                leaf("id", None),
+                leaf("name", Some("User")),
+                leaf("email", Some("User")),
+                leaf("level", Some("Admin")),
+                leaf("n", None),
+            ]
+        );
+    }
+}
@@ -0,0 +1,295 @@
+//! Validation rules for executable documents
+//!
+use std::collections::HashMap;
+
+use thiserror::Error;
+
+use crate::common::{Directive, Text, Value};
+use crate::position::Pos;
+use crate::query::analysis::selection_breadths;
+use crate::query::ast::*;
+
+/// Error found by one of the query validators
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("{position}: {message}")]
+pub struct ValidationError {
+    pub position: Pos,
+    pub message: String,
+}
+
+impl ValidationError {
+    fn new(position: Pos, message: String) -> ValidationError {
+        ValidationError { position, message }
+    }
+}
+
+/// Calls `f` with directives of every selection in the selection set,
+/// recursively
+pub(super) fn selection_directives<'d, 'a, T, F>(set: &'d SelectionSet<'a, T>, f: &mut F)
+where
+    T: Text<'a>,
+    F: FnMut(&'d [Directive<'a, T>]),
+{
+    for item in &set.items {
+        match item {
+            Selection::Field(field) => {
+                f(&field.directives);
+                selection_directives(&field.selection_set, f);
+            }
+            Selection::FragmentSpread(spread) => f(&spread.directives),
+            Selection::InlineFragment(frag) => {
+                f(&frag.directives);
+                selection_directives(&frag.selection_set, f);
+            }
+        }
+    }
+}
+
+fn selection_sets<'d, 'a, T: Text<'a>>(doc: &'d Document<'a, T>) -> Vec<&'d SelectionSet<'a, T>> {
+    doc.definitions
+        .iter()
+        .map(|def| match def {
+            Definition::Operation(OperationDefinition::SelectionSet(s)) => s,
+            Definition::Operation(OperationDefinition::Query(q)) => &q.selection_set,
+            Definition::Operation(OperationDefinition::Mutation(m)) => &m.selection_set,
+            Definition::Operation(OperationDefinition::Subscription(s)) => &s.selection_set,
+            Definition::Fragment(f) => &f.selection_set,
+        })
+        .collect()
+}
+
+/// Checks that `@skip` and `@include` have a single boolean `if` argument
+///
+/// The argument must be either a boolean literal or a variable, since the
+/// type of a variable is not known without a schema.
+pub fn check_skip_include<'a, T>(doc: &Document<'a, T>) -> Vec<ValidationError>
+where
+    T: Text<'a>,
+{
+    let mut errors = Vec::new();
+    let mut check = |directives: &[Directive<'a, T>]| {
+        for dir in directives {
+            let name = dir.name.as_ref();
+            if name != "skip" && name != "include" {
+                continue;
+            }
+            let mut has_if = false;
+            for (arg, value) in &dir.arguments {
+                if arg.as_ref() != "if" {
+                    errors.push(ValidationError::new(
+                        dir.position,
+                        format!(
+                            "unknown argument \"{}\" on directive \"@{}\"",
+                            arg.as_ref(),
+                            name
+                        ),
+                    ));
+                    continue;
+                }
+                has_if = true;
+                if !matches!(value, Value::Boolean(_) | Value::Variable(..)) {
+                    errors.push(ValidationError::new(
+                        dir.position,
+                        format!(
+                            "argument \"if\" of directive \"@{}\" must be a boolean, found {}",
+                            name, value,
+                        ),
+                    ));
+                }
+            }
+            if !has_if {
+                errors.push(ValidationError::new(
+                    dir.position,
+                    format!("directive \"@{}\" requires argument \"if\"", name),
+                ));
+            }
+        }
+    };
+    for set in selection_sets(doc) {
+        selection_directives(set, &mut check);
+    }
+    errors
+}
+
+/// Checks that no field or directive has two arguments with the same name
+///
+/// Errors point to the field or directive having the duplicate argument,
+/// each duplicate name is reported once.
+pub fn check_unique_arguments<'a, T>(doc: &Document<'a, T>) -> Vec<ValidationError>
+where
+    T: Text<'a>,
+{
+    fn check<'a, T: Text<'a>>(
+        position: Pos,
+        what: &str,
+        arguments: &[(T, Value<'a, T>)],
+        errors: &mut Vec<ValidationError>,
+    ) {
+        for (idx, (name, _)) in arguments.iter().enumerate() {
+            let seen = |arg: &(T, Value<'a, T>)| arg.0 == *name;
+            if arguments[..idx].iter().any(seen) || !arguments[idx + 1..].iter().any(seen) {
+                continue;
+            }
+            errors.push(ValidationError::new(
+                position,
+                format!(
+                    "there can be only one argument named \"{}\" on {}",
+                    name.as_ref(),
+                    what
+                ),
+            ));
+        }
+    }
+
+    fn directives<'a, T: Text<'a>>(dirs: &[Directive<'a, T>], errors: &mut Vec<ValidationError>) {
+        for dir in dirs {
+            let what = format!("directive \"@{}\"", dir.name.as_ref());
+            check(dir.position, &what, &dir.arguments, errors);
+        }
+    }
+
+    fn selection_set<'a, T: Text<'a>>(set: &SelectionSet<'a, T>, errors: &mut Vec<ValidationError>) {
+        for item in &set.items {
+            match item {
+                Selection::Field(field) => {
+                    let what = format!("field \"{}\"", field.name.as_ref());
+                    check(field.position, &what, &field.arguments, errors);
+                    directives(&field.directives, errors);
+                    selection_set(&field.selection_set, errors);
+                }
+                Selection::FragmentSpread(spread) => directives(&spread.directives, errors),
+                Selection::InlineFragment(frag) => {
+                    directives(&frag.directives, errors);
+                    selection_set(&frag.selection_set, errors);
+                }
+            }
+        }
+    }
+
+    let mut errors = Vec::new();
+    for (def, set) in doc.definitions.iter().zip(selection_sets(doc)) {
+        let dirs = match def {
+            Definition::Operation(OperationDefinition::SelectionSet(_)) => &[][..],
+            Definition::Operation(OperationDefinition::Query(q)) => &q.directives,
+            Definition::Operation(OperationDefinition::Mutation(m)) => &m.directives,
+            Definition::Operation(OperationDefinition::Subscription(s)) => &s.directives,
+            Definition::Fragment(f) => &f.directives,
+        };
+        directives(dirs, &mut errors);
+        selection_set(set, &mut errors);
+    }
+    errors
+}
+
+/// Checks that no selection set of `op` selects more than `limit` fields
+///
+/// Fields are counted the same way as in [`max_breadth`], an error is
+/// reported for every selection set over the limit.
+///
+/// [`max_breadth`]: crate::query::max_breadth
+pub fn validate_breadth<'a, T>(
+    op: &OperationDefinition<'a, T>,
+    fragments: &HashMap<&str, &FragmentDefinition<'a, T>>,
+    limit: usize,
+) -> Vec<ValidationError>
+where
+    T: Text<'a>,
+{
+    let mut errors = Vec::new();
+    selection_breadths(op, fragments, |position, count| {
+        if count > limit {
+            errors.push(ValidationError::new(
+                position,
+                format!(
+                    "selection set has {} fields, which is more than the limit of {}",
+                    count, limit
+                ),
+            ));
+        }
+    });
+    errors
+}
+
+#[cfg(test)]
+mod test {
+    use super::{check_skip_include, check_unique_arguments, validate_breadth};
+    use crate::query::{parse_query, Definition};
+
+    fn errors(s: &str) -> Vec<String> {
+        let doc = parse_query::<&str>(s).unwrap();
+        check_skip_include(&doc)
+            .into_iter()
+            .map(|e| e.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn valid() {
+        assert!(errors(
+            "query($v: Boolean!) { a @skip(if: true) ...F @include(if: $v) \
+             ... @skip(if: false) { b } } fragment F on T { c @other(x: 1) }"
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(
+            errors("{ a { b @include(unless: $v) } ... on T @skip(if: \"yes\") { c } } fragment F on T { d @skip }"),
+            vec![
+                "1:9: unknown argument \"unless\" on directive \"@include\"",
+                "1:9: directive \"@include\" requires argument \"if\"",
+                "1:41: argument \"if\" of directive \"@skip\" must be a boolean, found \"yes\"",
+                "1:86: directive \"@skip\" requires argument \"if\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn breadth() {
+        let doc = parse_query::<&str>(
+            "{ a: f b: f c: f d { e } ...F }
+             fragment F on Query { d { e f g h } }",
+        )
+        .unwrap();
+        let op = match doc.definitions[0] {
+            Definition::Operation(ref op) => op,
+            _ => unreachable!(),
+        };
+        let errors: Vec<String> = validate_breadth(op, &doc.fragments(), 3)
+            .into_iter()
+            .map(|e| e.to_string())
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                "2:38: selection set has 4 fields, which is more than the limit of 3",
+                "1:1: selection set has 5 fields, which is more than the limit of 3",
+            ]
+        );
+        assert!(validate_breadth(op, &doc.fragments(), 5).is_empty());
+    }
+
+    #[test]
+    fn unique_arguments() {
+        let doc = parse_query::<&str>(
+            "query Q @live(a: 1, a: 2) { f(x: 1, y: 2, x: 3, x: 4) @foo(a: 1, b: 2) { g(y: 1, y: 1) } }
+             fragment F on T @bar(a: 1, a: 1) { ...G @baz(c: 1, c: 2) }",
+        )
+        .unwrap();
+        let errors: Vec<String> = check_unique_arguments(&doc)
+            .into_iter()
+            .map(|e| e.to_string())
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                "1:9: there can be only one argument named \"a\" on directive \"@live\"",
+                "1:29: there can be only one argument named \"x\" on field \"f\"",
+                "1:74: there can be only one argument named \"y\" on field \"g\"",
+                "2:30: there can be only one argument named \"a\" on directive \"@bar\"",
+                "2:54: there can be only one argument named \"c\" on directive \"@baz\"",
+            ]
+        );
+    }
+}
@@ -0,0 +1,348 @@
+//! Traversing the query AST
+//!
+use crate::common::Text;
+use crate::query::ast::*;
+
+/// Callbacks invoked by [`walk_document`]
+///
+/// All methods do nothing by default. Every `enter_*` call is matched by a
+/// `leave_*` call for the same node after its children are visited, so a
+/// visitor can maintain a stack of the nodes it is inside of. The empty
+/// selection sets of leaf fields are not visited.
+pub trait Visitor<'a, T: Text<'a>> {
+    fn enter_operation(&mut self, _operation: &OperationDefinition<'a, T>) {}
+    fn leave_operation(&mut self, _operation: &OperationDefinition<'a, T>) {}
+    fn enter_fragment_definition(&mut self, _fragment: &FragmentDefinition<'a, T>) {}
+    fn leave_fragment_definition(&mut self, _fragment: &FragmentDefinition<'a, T>) {}
+    fn enter_selection_set(&mut self, _selection_set: &SelectionSet<'a, T>) {}
+    fn leave_selection_set(&mut self, _selection_set: &SelectionSet<'a, T>) {}
+    fn enter_field(&mut self, _field: &Field<'a, T>) {}
+    fn leave_field(&mut self, _field: &Field<'a, T>) {}
+    fn enter_inline_fragment(&mut self, _fragment: &InlineFragment<'a, T>) {}
+    fn leave_inline_fragment(&mut self, _fragment: &InlineFragment<'a, T>) {}
+    /// Fragment spreads have no children, so they are visited only once
+    fn visit_fragment_spread(&mut self, _spread: &FragmentSpread<'a, T>) {}
+}
+
+/// Visits every definition of `doc` in document order
+///
+/// Fragment spreads are not followed, fragment definitions are visited
+/// where they appear in the document instead.
+pub fn walk_document<'a, T, V>(visitor: &mut V, doc: &Document<'a, T>)
+where
+    T: Text<'a>,
+    V: Visitor<'a, T> + ?Sized,
+{
+    for def in &doc.definitions {
+        match def {
+            Definition::Operation(op) => {
+                visitor.enter_operation(op);
+                walk_selection_set(visitor, op.selection_set());
+                visitor.leave_operation(op);
+            }
+            Definition::Fragment(frag) => {
+                visitor.enter_fragment_definition(frag);
+                walk_selection_set(visitor, &frag.selection_set);
+                visitor.leave_fragment_definition(frag);
+            }
+        }
+    }
+}
+
+/// Visits `set` and all selections in it recursively
+pub fn walk_selection_set<'a, T, V>(visitor: &mut V, set: &SelectionSet<'a, T>)
+where
+    T: Text<'a>,
+    V: Visitor<'a, T> + ?Sized,
+{
+    visitor.enter_selection_set(set);
+    for item in &set.items {
+        match item {
+            Selection::Field(field) => {
+                visitor.enter_field(field);
+                if !field.selection_set.items.is_empty() {
+                    walk_selection_set(visitor, &field.selection_set);
+                }
+                visitor.leave_field(field);
+            }
+            Selection::InlineFragment(frag) => {
+                visitor.enter_inline_fragment(frag);
+                walk_selection_set(visitor, &frag.selection_set);
+                visitor.leave_inline_fragment(frag);
+            }
+            Selection::FragmentSpread(spread) => visitor.visit_fragment_spread(spread),
+        }
+    }
+    visitor.leave_selection_set(set);
+}
+
+/// What [`walk_document_mut`] does with a node after visiting it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VisitControl {
+    /// Visit the children of the node
+    Continue,
+    /// Keep the node but don't visit its children
+    SkipChildren,
+    /// Remove the node from its parent without visiting its children
+    Remove,
+}
+
+/// Callbacks invoked by [`walk_document_mut`], which may modify the nodes
+///
+/// Nodes are visited before their children, so changes to the children of a
+/// node, such as inserted selections, are visited too. All methods do
+/// nothing and return [`VisitControl::Continue`] by default.
+pub trait VisitorMut<'a, T: Text<'a>> {
+    fn visit_operation(&mut self, _operation: &mut OperationDefinition<'a, T>) -> VisitControl {
+        VisitControl::Continue
+    }
+    fn visit_fragment_definition(
+        &mut self,
+        _fragment: &mut FragmentDefinition<'a, T>,
+    ) -> VisitControl {
+        VisitControl::Continue
+    }
+    /// Called before the selections of the set are visited
+    fn visit_selection_set(&mut self, _selection_set: &mut SelectionSet<'a, T>) {}
+    /// Called after the selections of the set are visited, and removed
+    /// ones are gone
+    fn leave_selection_set(&mut self, _selection_set: &mut SelectionSet<'a, T>) {}
+    fn visit_field(&mut self, _field: &mut Field<'a, T>) -> VisitControl {
+        VisitControl::Continue
+    }
+    fn visit_inline_fragment(&mut self, _fragment: &mut InlineFragment<'a, T>) -> VisitControl {
+        VisitControl::Continue
+    }
+    fn visit_fragment_spread(&mut self, _spread: &mut FragmentSpread<'a, T>) -> VisitControl {
+        VisitControl::Continue
+    }
+}
+
+/// Visits every definition of `doc` in document order, allowing the visitor
+/// to modify or remove nodes
+///
+/// As with [`walk_document`], fragment spreads are not followed.
+pub fn walk_document_mut<'a, T, V>(visitor: &mut V, doc: &mut Document<'a, T>)
+where
+    T: Text<'a>,
+    V: VisitorMut<'a, T> + ?Sized,
+{
+    let mut idx = 0;
+    while idx < doc.definitions.len() {
+        let control = match &mut doc.definitions[idx] {
+            Definition::Operation(op) => {
+                let control = visitor.visit_operation(op);
+                if control == VisitControl::Continue {
+                    let set = match op {
+                        OperationDefinition::SelectionSet(s) => s,
+                        OperationDefinition::Query(q) => &mut q.selection_set,
+                        OperationDefinition::Mutation(m) => &mut m.selection_set,
+                        OperationDefinition::Subscription(s) => &mut s.selection_set,
+                    };
+                    walk_selection_set_mut(visitor, set);
+                }
+                control
+            }
+            Definition::Fragment(frag) => {
+                let control = visitor.visit_fragment_definition(frag);
+                if control == VisitControl::Continue {
+                    walk_selection_set_mut(visitor, &mut frag.selection_set);
+                }
+                control
+            }
+        };
+        if control == VisitControl::Remove {
+            doc.definitions.remove(idx);
+        } else {
+            idx += 1;
+        }
+    }
+}
+
+/// Visits `set` and all selections in it recursively, allowing the visitor
+/// to modify or remove them
+pub fn walk_selection_set_mut<'a, T, V>(visitor: &mut V, set: &mut SelectionSet<'a, T>)
+where
+    T: Text<'a>,
+    V: VisitorMut<'a, T> + ?Sized,
+{
+    visitor.visit_selection_set(set);
+    let mut idx = 0;
+    while idx < set.items.len() {
+        let control = match &mut set.items[idx] {
+            Selection::Field(field) => {
+                let control = visitor.visit_field(field);
+                if control == VisitControl::Continue && !field.selection_set.items.is_empty() {
+                    walk_selection_set_mut(visitor, &mut field.selection_set);
+                }
+                control
+            }
+            Selection::InlineFragment(frag) => {
+                let control = visitor.visit_inline_fragment(frag);
+                if control == VisitControl::Continue {
+                    walk_selection_set_mut(visitor, &mut frag.selection_set);
+                }
+                control
+            }
+            Selection::FragmentSpread(spread) => visitor.visit_fragment_spread(spread),
+        };
+        if control == VisitControl::Remove {
+            set.items.remove(idx);
+        } else {
+            idx += 1;
+        }
+    }
+    visitor.leave_selection_set(set);
+}
+
+#[cfg(test)]
+mod test {
+    use super::{walk_document, walk_document_mut, VisitControl, Visitor, VisitorMut};
+    use crate::common::Directive;
+    use crate::position::Pos;
+    use crate::query::*;
+
+    #[derive(Default)]
+    struct Paths {
+        path: Vec<String>,
+        paths: Vec<String>,
+    }
+
+    impl<'a> Visitor<'a, &'a str> for Paths {
+        fn enter_operation(&mut self, op: &OperationDefinition<'a, &'a str>) {
+            self.path
+                .push(op.name().map_or("<anonymous>", |n| *n).to_string());
+        }
+        fn leave_operation(&mut self, _: &OperationDefinition<'a, &'a str>) {
+            self.path.pop();
+        }
+        fn enter_fragment_definition(&mut self, frag: &FragmentDefinition<'a, &'a str>) {
+            self.path.push(frag.name.to_string());
+        }
+        fn leave_fragment_definition(&mut self, _: &FragmentDefinition<'a, &'a str>) {
+            self.path.pop();
+        }
+        fn enter_field(&mut self, field: &Field<'a, &'a str>) {
+            self.path.push(field.name.to_string());
+            self.paths.push(self.path.join("."));
+        }
+        fn leave_field(&mut self, _: &Field<'a, &'a str>) {
+            self.path.pop();
+        }
+        fn enter_inline_fragment(&mut self, frag: &InlineFragment<'a, &'a str>) {
+            let TypeCondition::On(cond, _) = frag.type_condition.as_ref().unwrap();
+            self.path.push(format!("<{}>", cond));
+        }
+        fn leave_inline_fragment(&mut self, _: &InlineFragment<'a, &'a str>) {
+            self.path.pop();
+        }
+        fn visit_fragment_spread(&mut self, spread: &FragmentSpread<'a, &'a str>) {
+            self.paths
+                .push(format!("{}.&{}", self.path.join("."), spread.fragment_name));
+        }
+    }
+
+    #[test]
+    fn paths() {
+        let doc = parse_query::<&str>(
+            "query Q { user { id ... on Admin { level } ...F } }
+             fragment F on User { name }
+             { a }",
+        )
+        .unwrap();
+        let mut visitor = Paths::default();
+        walk_document(&mut visitor, &doc);
+        assert!(visitor.path.is_empty());
+        assert_eq!(
+            visitor.paths,
+            vec![
+                "Q.user",
+                "Q.user.id",
+                "Q.user.<Admin>.level",
+                "Q.user.&F",
+                "F.name",
+                "<anonymous>.a",
+            ]
+        );
+    }
+
+    struct Rewrite;
+
+    impl<'a> VisitorMut<'a, &'a str> for Rewrite {
+        fn visit_fragment_definition(
+            &mut self,
+            frag: &mut FragmentDefinition<'a, &'a str>,
+        ) -> VisitControl {
+            match frag.name {
+                "Removed" => VisitControl::Remove,
+                _ => VisitControl::Continue,
+            }
+        }
+        fn leave_selection_set(&mut self, set: &mut SelectionSet<'a, &'a str>) {
+            if set.items.is_empty() {
+                set.items.push(Selection::Field(Field {
+                    position: Pos::default(),
+                    end: Pos::default(),
+                    alias: None,
+                    name: "__typename",
+                    arguments: Vec::new(),
+                    directives: Vec::new(),
+                    selection_set: SelectionSet {
+                        span: (Pos::default(), Pos::default()),
+                        items: Vec::new(),
+                    },
+                }));
+            }
+        }
+        fn visit_field(&mut self, field: &mut Field<'a, &'a str>) -> VisitControl {
+            match field.name {
+                "__typename" => VisitControl::Remove,
+                "secret" => {
+                    field.directives.push(Directive {
+                        position: Pos::default(),
+                        name: "redact",
+                        arguments: Vec::new(),
+                    });
+                    VisitControl::SkipChildren
+                }
+                _ => VisitControl::Continue,
+            }
+        }
+        fn visit_fragment_spread(
+            &mut self,
+            spread: &mut FragmentSpread<'a, &'a str>,
+        ) -> VisitControl {
+            match spread.fragment_name {
+                "Removed" => VisitControl::Remove,
+                _ => VisitControl::Continue,
+            }
+        }
+    }
+
+    #[test]
+    fn rewrite() {
+        let mut doc = parse_query::<&str>(
+            "query Q { __typename user { __typename id ...Removed secret { __typename } } }
+             fragment Removed on User { name }
+             fragment F on User { ...Removed __typename }",
+        )
+        .unwrap();
+        walk_document_mut(&mut Rewrite, &mut doc);
+        assert_eq!(
+            doc.to_string(),
+            "query Q {
+  user {
+    id
+    secret @redact {
+      __typename
+    }
+  }
+}
+
+fragment F on User {
+  __typename
+}
+"
+        );
+    }
+}
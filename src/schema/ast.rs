@@ -1,11 +1,15 @@
-use std::str::FromStr;
-
-use thiserror::Error;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::str::FromStr;
 
 pub use crate::common::{Directive, Text, Type, Value};
-use crate::position::Pos;
+use crate::common::{StructuralEq, TextMapper};
+use crate::position::{Pos, Span};
 
 #[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Document<'a, T: Text<'a>>
     where
         T: Text<'a>,
@@ -13,6 +17,572 @@ pub struct Document<'a, T: Text<'a>>
     pub definitions: Vec<Definition<'a, T>>,
 }
 
+/// Names of directives defined by the GraphQL specification
+///
+/// These are available in every schema without being declared, so
+/// validators should not report them as undefined.
+pub const BUILTIN_DIRECTIVES: &[&str] = &["skip", "include", "deprecated", "specifiedBy", "oneOf"];
+
+pub(crate) const BUILTIN_SCALARS: &[&str] = &["Int", "Float", "String", "Boolean", "ID"];
+
+impl<'a, T: Text<'a>> Document<'a, T> {
+    /// Returns a hash of the document's content, suitable as a cache key
+    ///
+    /// See [`query::Document::content_hash`](crate::query::Document::content_hash).
+    /// Descriptions are part of the content, comments are not.
+    pub fn content_hash(&self) -> u64 {
+        crate::hash::content_hash(self)
+    }
+
+    /// Returns directives applied to the schema
+    ///
+    /// Directives of the schema definition come first, followed by ones
+    /// of every schema extension in document order.
+    pub fn schema_directives(&self) -> Vec<&Directive<'a, T>> {
+        let mut result = Vec::new();
+        for def in &self.definitions {
+            if let Definition::SchemaDefinition(s) = def {
+                result.extend(&s.directives);
+            }
+        }
+        for def in &self.definitions {
+            if let Definition::SchemaExtension(s) = def {
+                result.extend(&s.directives);
+            }
+        }
+        result
+    }
+
+    /// Returns every directive applied in the document, in source order
+    fn applied_directives(&self) -> Vec<&Directive<'a, T>> {
+        fn values<'d, 'a, T: Text<'a>>(
+            result: &mut Vec<&'d Directive<'a, T>>,
+            values: &'d [InputValue<'a, T>],
+        ) {
+            for value in values {
+                result.extend(&value.directives);
+            }
+        }
+        fn fields<'d, 'a, T: Text<'a>>(
+            result: &mut Vec<&'d Directive<'a, T>>,
+            fields: &'d [Field<'a, T>],
+        ) {
+            for field in fields {
+                values(result, &field.arguments);
+                result.extend(&field.directives);
+            }
+        }
+
+        let mut result = Vec::new();
+        for def in &self.definitions {
+            match def {
+                Definition::SchemaDefinition(s) => result.extend(&s.directives),
+                Definition::SchemaExtension(s) => result.extend(&s.directives),
+                Definition::TypeDefinition(TypeDefinition::Scalar(s)) => {
+                    result.extend(&s.directives)
+                }
+                Definition::TypeDefinition(TypeDefinition::Object(o)) => {
+                    result.extend(&o.directives);
+                    fields(&mut result, &o.fields);
+                }
+                Definition::TypeDefinition(TypeDefinition::Interface(i)) => {
+                    result.extend(&i.directives);
+                    fields(&mut result, &i.fields);
+                }
+                Definition::TypeDefinition(TypeDefinition::Union(u)) => {
+                    result.extend(&u.directives)
+                }
+                Definition::TypeDefinition(TypeDefinition::Enum(e)) => {
+                    result.extend(&e.directives);
+                    for value in &e.values {
+                        result.extend(&value.directives);
+                    }
+                }
+                Definition::TypeDefinition(TypeDefinition::InputObject(i)) => {
+                    result.extend(&i.directives);
+                    values(&mut result, &i.fields);
+                }
+                Definition::TypeExtension(TypeExtension::Scalar(s)) => {
+                    result.extend(&s.directives)
+                }
+                Definition::TypeExtension(TypeExtension::Object(o)) => {
+                    result.extend(&o.directives);
+                    fields(&mut result, &o.fields);
+                }
+                Definition::TypeExtension(TypeExtension::Interface(i)) => {
+                    result.extend(&i.directives);
+                    fields(&mut result, &i.fields);
+                }
+                Definition::TypeExtension(TypeExtension::Union(u)) => {
+                    result.extend(&u.directives)
+                }
+                Definition::TypeExtension(TypeExtension::Enum(e)) => {
+                    result.extend(&e.directives);
+                    for value in &e.values {
+                        result.extend(&value.directives);
+                    }
+                }
+                Definition::TypeExtension(TypeExtension::InputObject(i)) => {
+                    result.extend(&i.directives);
+                    values(&mut result, &i.fields);
+                }
+                Definition::DirectiveDefinition(d) => values(&mut result, &d.arguments),
+            }
+        }
+        result
+    }
+
+    /// Returns applications of directives which are neither defined in the
+    /// document nor [built in](BUILTIN_DIRECTIVES)
+    ///
+    /// Every application is listed with its position in source order, so a
+    /// directive used twice is listed twice.
+    pub fn undefined_directives(&self) -> Vec<(T, Pos)> {
+        let defined: Vec<&str> = self
+            .definitions
+            .iter()
+            .filter_map(|def| match def {
+                Definition::DirectiveDefinition(d) => Some(d.name.as_ref()),
+                _ => None,
+            })
+            .collect();
+        self.applied_directives()
+            .into_iter()
+            .filter(|d| {
+                let name = d.name.as_ref();
+                !defined.contains(&name) && !BUILTIN_DIRECTIVES.contains(&name)
+            })
+            .map(|d| (d.name.clone(), d.position))
+            .collect()
+    }
+
+    /// Returns mutable references to the types of all fields, arguments
+    /// and input fields, in source order
+    ///
+    /// Only the outermost type at each position is returned, list and
+    /// non-null wrappers contain the rest. Types added by extensions and
+    /// arguments of directive definitions are included.
+    pub fn types_mut(&mut self) -> impl Iterator<Item = &mut Type<'a, T>> {
+        fn fields<'d, 'a, T: Text<'a>>(
+            result: &mut Vec<&'d mut Type<'a, T>>,
+            fields: &'d mut [Field<'a, T>],
+        ) {
+            for field in fields {
+                result.extend(field.arguments.iter_mut().map(|a| &mut a.value_type));
+                result.push(&mut field.field_type);
+            }
+        }
+
+        let mut result = Vec::new();
+        for def in &mut self.definitions {
+            match def {
+                Definition::TypeDefinition(TypeDefinition::Object(o)) => {
+                    fields(&mut result, &mut o.fields)
+                }
+                Definition::TypeDefinition(TypeDefinition::Interface(i)) => {
+                    fields(&mut result, &mut i.fields)
+                }
+                Definition::TypeDefinition(TypeDefinition::InputObject(i)) => {
+                    result.extend(i.fields.iter_mut().map(|f| &mut f.value_type))
+                }
+                Definition::TypeExtension(TypeExtension::Object(o)) => {
+                    fields(&mut result, &mut o.fields)
+                }
+                Definition::TypeExtension(TypeExtension::Interface(i)) => {
+                    fields(&mut result, &mut i.fields)
+                }
+                Definition::TypeExtension(TypeExtension::InputObject(i)) => {
+                    result.extend(i.fields.iter_mut().map(|f| &mut f.value_type))
+                }
+                Definition::DirectiveDefinition(d) => {
+                    result.extend(d.arguments.iter_mut().map(|a| &mut a.value_type))
+                }
+                _ => {}
+            }
+        }
+        result.into_iter()
+    }
+
+    /// Returns all directives applied anywhere in the document
+    ///
+    /// Directives of the schema, types, fields, arguments, input fields and
+    /// enum values are included, from both definitions and extensions.
+    /// Nodes are visited in source order, but directives of each node come
+    /// before the ones of the nodes it contains, so directives of a field
+    /// precede the ones of its arguments.
+    pub fn all_directives(&self) -> impl Iterator<Item = &Directive<'a, T>> {
+        fn inputs<'d, 'a, T: Text<'a>>(
+            result: &mut Vec<&'d Directive<'a, T>>,
+            inputs: &'d [InputValue<'a, T>],
+        ) {
+            for input in inputs {
+                result.extend(&input.directives);
+            }
+        }
+        fn fields<'d, 'a, T: Text<'a>>(
+            result: &mut Vec<&'d Directive<'a, T>>,
+            fields: &'d [Field<'a, T>],
+        ) {
+            for field in fields {
+                result.extend(&field.directives);
+                inputs(result, &field.arguments);
+            }
+        }
+        fn values<'d, 'a, T: Text<'a>>(
+            result: &mut Vec<&'d Directive<'a, T>>,
+            values: &'d [EnumValue<'a, T>],
+        ) {
+            for value in values {
+                result.extend(&value.directives);
+            }
+        }
+
+        let mut result = Vec::new();
+        for def in &self.definitions {
+            match def {
+                Definition::SchemaDefinition(s) => result.extend(&s.directives),
+                Definition::SchemaExtension(s) => result.extend(&s.directives),
+                Definition::TypeDefinition(t) => match t {
+                    TypeDefinition::Scalar(s) => result.extend(&s.directives),
+                    TypeDefinition::Object(o) => {
+                        result.extend(&o.directives);
+                        fields(&mut result, &o.fields);
+                    }
+                    TypeDefinition::Interface(i) => {
+                        result.extend(&i.directives);
+                        fields(&mut result, &i.fields);
+                    }
+                    TypeDefinition::Union(u) => result.extend(&u.directives),
+                    TypeDefinition::Enum(e) => {
+                        result.extend(&e.directives);
+                        values(&mut result, &e.values);
+                    }
+                    TypeDefinition::InputObject(i) => {
+                        result.extend(&i.directives);
+                        inputs(&mut result, &i.fields);
+                    }
+                },
+                Definition::TypeExtension(t) => match t {
+                    TypeExtension::Scalar(s) => result.extend(&s.directives),
+                    TypeExtension::Object(o) => {
+                        result.extend(&o.directives);
+                        fields(&mut result, &o.fields);
+                    }
+                    TypeExtension::Interface(i) => {
+                        result.extend(&i.directives);
+                        fields(&mut result, &i.fields);
+                    }
+                    TypeExtension::Union(u) => result.extend(&u.directives),
+                    TypeExtension::Enum(e) => {
+                        result.extend(&e.directives);
+                        values(&mut result, &e.values);
+                    }
+                    TypeExtension::InputObject(i) => {
+                        result.extend(&i.directives);
+                        inputs(&mut result, &i.fields);
+                    }
+                },
+                Definition::DirectiveDefinition(d) => inputs(&mut result, &d.arguments),
+            }
+        }
+        result.into_iter()
+    }
+
+    /// Moves every position in the document `line_delta` lines and
+    /// `offset_delta` bytes further into the source
+    ///
+    /// See [`query::Document::shift_positions`](crate::query::Document::shift_positions),
+    /// end positions of definitions, fields and input values are moved too.
+    pub fn shift_positions(&mut self, line_delta: usize, offset_delta: usize) {
+        for pos in self.positions_mut() {
+            if *pos != Pos::default() {
+                pos.line += line_delta;
+                pos.offset += offset_delta;
+            }
+        }
+    }
+
+    /// Returns `true` if the documents are equal when positions of the
+    /// nodes are ignored
+    ///
+    /// See [`query::Document::eq_ignore_positions`](crate::query::Document::eq_ignore_positions),
+    /// descriptions must match too.
+    pub fn eq_ignore_positions(&self, other: &Self) -> bool {
+        self.definitions.structural_eq(&other.definitions, false)
+    }
+
+    /// Returns mutable references to all positions in the document
+    fn positions_mut(&mut self) -> Vec<&mut Pos> {
+        fn directives<'d, 'a, T: Text<'a>>(
+            result: &mut Vec<&'d mut Pos>,
+            directives: &'d mut [Directive<'a, T>],
+        ) {
+            result.extend(directives.iter_mut().map(|d| &mut d.position));
+        }
+        fn values<'d, 'a, T: Text<'a>>(
+            result: &mut Vec<&'d mut Pos>,
+            values: &'d mut [InputValue<'a, T>],
+        ) {
+            for value in values {
+                result.push(&mut value.position);
+                result.push(&mut value.end);
+                directives(result, &mut value.directives);
+            }
+        }
+        fn fields<'d, 'a, T: Text<'a>>(
+            result: &mut Vec<&'d mut Pos>,
+            fields: &'d mut [Field<'a, T>],
+        ) {
+            for field in fields {
+                result.push(&mut field.position);
+                result.push(&mut field.end);
+                values(result, &mut field.arguments);
+                directives(result, &mut field.directives);
+            }
+        }
+        fn enum_values<'d, 'a, T: Text<'a>>(
+            result: &mut Vec<&'d mut Pos>,
+            values: &'d mut [EnumValue<'a, T>],
+        ) {
+            for value in values {
+                result.push(&mut value.position);
+                directives(result, &mut value.directives);
+            }
+        }
+
+        let mut positions = Vec::new();
+        for def in &mut self.definitions {
+            let result = &mut positions;
+            match def {
+                Definition::SchemaDefinition(s) => {
+                    result.push(&mut s.position);
+                    directives(result, &mut s.directives);
+                }
+                Definition::SchemaExtension(s) => {
+                    result.push(&mut s.position);
+                    directives(result, &mut s.directives);
+                }
+                Definition::TypeDefinition(TypeDefinition::Scalar(s)) => {
+                    result.extend([&mut s.position, &mut s.end]);
+                    directives(result, &mut s.directives);
+                }
+                Definition::TypeDefinition(TypeDefinition::Object(o)) => {
+                    result.extend([&mut o.position, &mut o.end]);
+                    directives(result, &mut o.directives);
+                    fields(result, &mut o.fields);
+                }
+                Definition::TypeDefinition(TypeDefinition::Interface(i)) => {
+                    result.extend([&mut i.position, &mut i.end]);
+                    directives(result, &mut i.directives);
+                    fields(result, &mut i.fields);
+                }
+                Definition::TypeDefinition(TypeDefinition::Union(u)) => {
+                    result.extend([&mut u.position, &mut u.end]);
+                    directives(result, &mut u.directives);
+                }
+                Definition::TypeDefinition(TypeDefinition::Enum(e)) => {
+                    result.extend([&mut e.position, &mut e.end]);
+                    directives(result, &mut e.directives);
+                    enum_values(result, &mut e.values);
+                }
+                Definition::TypeDefinition(TypeDefinition::InputObject(i)) => {
+                    result.extend([&mut i.position, &mut i.end]);
+                    directives(result, &mut i.directives);
+                    values(result, &mut i.fields);
+                }
+                Definition::TypeExtension(TypeExtension::Scalar(s)) => {
+                    result.push(&mut s.position);
+                    directives(result, &mut s.directives);
+                }
+                Definition::TypeExtension(TypeExtension::Object(o)) => {
+                    result.push(&mut o.position);
+                    directives(result, &mut o.directives);
+                    fields(result, &mut o.fields);
+                }
+                Definition::TypeExtension(TypeExtension::Interface(i)) => {
+                    result.push(&mut i.position);
+                    directives(result, &mut i.directives);
+                    fields(result, &mut i.fields);
+                }
+                Definition::TypeExtension(TypeExtension::Union(u)) => {
+                    result.push(&mut u.position);
+                    directives(result, &mut u.directives);
+                }
+                Definition::TypeExtension(TypeExtension::Enum(e)) => {
+                    result.push(&mut e.position);
+                    directives(result, &mut e.directives);
+                    enum_values(result, &mut e.values);
+                }
+                Definition::TypeExtension(TypeExtension::InputObject(i)) => {
+                    result.push(&mut i.position);
+                    directives(result, &mut i.directives);
+                    values(result, &mut i.fields);
+                }
+                Definition::DirectiveDefinition(d) => {
+                    result.push(&mut d.position);
+                    values(result, &mut d.arguments);
+                }
+            }
+        }
+        positions
+    }
+
+    /// Sorts definitions by a key extracted from each of them
+    ///
+    /// The sort is stable, so definitions with equal keys keep their
+    /// relative order. The key function is called once per definition.
+    pub fn sort_definitions_by<K, F>(&mut self, key: F)
+    where
+        K: Ord,
+        F: FnMut(&Definition<'a, T>) -> K,
+    {
+        self.definitions.sort_by_cached_key(key);
+    }
+
+    /// Converts the document to another text type by calling `f` on every
+    /// name in it
+    ///
+    /// See [`query::Document::map_text`](crate::query::Document::map_text),
+    /// descriptions are kept as is like string values.
+    pub fn map_text<'b, U, F>(self, f: F) -> Document<'b, U>
+    where
+        U: Text<'b>,
+        F: FnMut(T) -> U,
+    {
+        let mut mapper = TextMapper::new(f);
+        Document {
+            definitions: self
+                .definitions
+                .into_iter()
+                .map(|def| mapper.definition(def))
+                .collect(),
+        }
+    }
+
+    /// Copies every name into a `String`, making the document independent
+    /// of the source it was parsed from
+    ///
+    /// Unlike [`Document::into_static`], this works for any text type.
+    pub fn into_owned(self) -> Document<'static, String> {
+        self.map_text(|t| t.as_ref().to_string())
+    }
+
+    /// Finds the definition of type `name` along with all its extensions
+    ///
+    /// Returns `None` if the type is neither defined nor extended.
+    pub fn find_type(&self, name: &str) -> Option<TypeView<'_, 'a, T>> {
+        let mut view = TypeView {
+            definition: None,
+            extensions: Vec::new(),
+        };
+        for def in &self.definitions {
+            match def {
+                Definition::TypeDefinition(t) if t.name().as_ref() == name => {
+                    view.definition.get_or_insert(t);
+                }
+                Definition::TypeExtension(e) if e.name().as_ref() == name => {
+                    view.extensions.push(e)
+                }
+                _ => {}
+            }
+        }
+        if view.definition.is_none() && view.extensions.is_empty() {
+            return None;
+        }
+        Some(view)
+    }
+
+    /// Returns the name of the root type for operations of kind `kind`
+    ///
+    /// If the document has no schema definition, types named `Query`,
+    /// `Mutation` and `Subscription` are the root types, provided they are
+    /// defined.
+    pub fn root_type_name(&self, kind: OperationType) -> Option<&str> {
+        let mut has_schema = false;
+        for def in &self.definitions {
+            let (query, mutation, subscription) = match def {
+                Definition::SchemaDefinition(s) => (&s.query, &s.mutation, &s.subscription),
+                Definition::SchemaExtension(s) => (&s.query, &s.mutation, &s.subscription),
+                _ => continue,
+            };
+            has_schema = true;
+            let name = match kind {
+                OperationType::Query => query,
+                OperationType::Mutation => mutation,
+                OperationType::Subscription => subscription,
+            };
+            if let Some(name) = name {
+                return Some(name.as_ref());
+            }
+        }
+        let default = match kind {
+            OperationType::Query => "Query",
+            OperationType::Mutation => "Mutation",
+            OperationType::Subscription => "Subscription",
+        };
+        if has_schema || self.find_type(default).is_none() {
+            return None;
+        }
+        Some(default)
+    }
+
+    /// Returns the interfaces implemented by the object or interface type
+    /// `type_name`, including ones implemented through other interfaces
+    ///
+    /// Interfaces declared directly, in the definition or any extension,
+    /// come first, followed by interfaces they implement, breadth first.
+    /// Each interface is listed once, and cycles between interfaces are
+    /// tolerated. The type itself is never included.
+    pub fn all_interfaces(&self, type_name: &str) -> Vec<&T> {
+        let mut result: Vec<&T> = Vec::new();
+        let mut next = 0;
+        let mut current = type_name;
+        loop {
+            for def in &self.definitions {
+                let (name, interfaces) = match def {
+                    Definition::TypeDefinition(TypeDefinition::Object(o)) => {
+                        (&o.name, &o.implements_interfaces)
+                    }
+                    Definition::TypeDefinition(TypeDefinition::Interface(i)) => {
+                        (&i.name, &i.implements_interfaces)
+                    }
+                    Definition::TypeExtension(TypeExtension::Object(o)) => {
+                        (&o.name, &o.implements_interfaces)
+                    }
+                    Definition::TypeExtension(TypeExtension::Interface(i)) => {
+                        (&i.name, &i.implements_interfaces)
+                    }
+                    _ => continue,
+                };
+                if name.as_ref() != current {
+                    continue;
+                }
+                for iface in interfaces {
+                    if iface.as_ref() != type_name
+                        && !result.iter().any(|r| r.as_ref() == iface.as_ref())
+                    {
+                        result.push(iface);
+                    }
+                }
+            }
+            match result.get(next) {
+                Some(&iface) => current = iface.as_ref(),
+                None => return result,
+            }
+            next += 1;
+        }
+    }
+}
+
+/// A type as described by its definition and extensions in a document
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeView<'d, 'a, T: Text<'a>> {
+    /// The base definition, or the first one if the type is defined twice
+    pub definition: Option<&'d TypeDefinition<'a, T>>,
+    /// Extensions of the type in document order
+    pub extensions: Vec<&'d TypeExtension<'a, T>>,
+}
+
 impl<'a> Document<'a, String> {
     pub fn into_static(self) -> Document<'static, String> {
         // To support both reference and owned values in the AST,
@@ -28,11 +598,12 @@ impl<'a> Document<'a, String> {
         //
         // This is safe because no references are present.
         // Just the PhantomData lifetime reference is transmuted away.
-        unsafe { std::mem::transmute::<_, Document<'static, String>>(self) }
+        unsafe { core::mem::transmute::<_, Document<'static, String>>(self) }
     }
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Definition<'a, T: Text<'a>> {
     SchemaDefinition(SchemaDefinition<'a, T>),
     SchemaExtension(SchemaExtension<'a, T>),
@@ -79,15 +650,35 @@ impl<'a, T: Text<'a>> Definition<'a, T> {
 }
 
 #[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SchemaDefinition<'a, T: Text<'a>> {
     pub position: Pos,
     pub directives: Vec<Directive<'a, T>>,
     pub query: Option<T>,
     pub mutation: Option<T>,
     pub subscription: Option<T>,
+    /// Order in which root operation types were declared in the source
+    ///
+    /// Operations which are set but not listed here are formatted after
+    /// the listed ones, in the canonical `query`, `mutation`,
+    /// `subscription` order.
+    pub operation_order: Vec<OperationType>,
+}
+
+impl<'a, T: Text<'a>> SchemaDefinition<'a, T> {
+    /// Returns root operation types in the order they should be printed
+    pub fn root_operations(&self) -> Vec<(OperationType, &T)> {
+        root_operations(
+            &self.operation_order,
+            &self.query,
+            &self.mutation,
+            &self.subscription,
+        )
+    }
 }
 
 #[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SchemaExtension<'a, T: Text<'a>> {
     pub position: Pos,
     pub directives: Vec<Directive<'a, T>>,
@@ -95,9 +686,68 @@ pub struct SchemaExtension<'a, T: Text<'a>> {
     pub query: Option<T>,
     pub mutation: Option<T>,
     pub subscription: Option<T>,
+    /// Order in which root operation types were declared in the source
+    pub operation_order: Vec<OperationType>,
+}
+
+impl<'a, T: Text<'a>> SchemaExtension<'a, T> {
+    /// Returns root operation types in the order they should be printed
+    pub fn root_operations(&self) -> Vec<(OperationType, &T)> {
+        root_operations(
+            &self.operation_order,
+            &self.query,
+            &self.mutation,
+            &self.subscription,
+        )
+    }
+}
+
+/// Kind of a root operation type in a `schema` block
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OperationType {
+    Query,
+    Mutation,
+    Subscription,
+}
+
+impl OperationType {
+    /// Returns GraphQL syntax compatible name of the operation type
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            OperationType::Query => "query",
+            OperationType::Mutation => "mutation",
+            OperationType::Subscription => "subscription",
+        }
+    }
+}
+
+fn root_operations<'s, T>(
+    order: &[OperationType],
+    query: &'s Option<T>,
+    mutation: &'s Option<T>,
+    subscription: &'s Option<T>,
+) -> Vec<(OperationType, &'s T)> {
+    use self::OperationType::*;
+    let lookup = |kind| match kind {
+        Query => query.as_ref(),
+        Mutation => mutation.as_ref(),
+        Subscription => subscription.as_ref(),
+    };
+    let mut result = Vec::with_capacity(3);
+    for &kind in order.iter().chain(&[Query, Mutation, Subscription]) {
+        if result.iter().any(|&(k, _)| k == kind) {
+            continue;
+        }
+        if let Some(name) = lookup(kind) {
+            result.push((kind, name));
+        }
+    }
+    result
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TypeDefinition<'a, T: Text<'a>> {
     Scalar(ScalarType<'a, T>),
     Object(ObjectType<'a, T>),
@@ -118,6 +768,17 @@ impl<'a, T: Text<'a>> TypeDefinition<'a, T> {
             TypeDefinition::InputObject(i) => &i.name,
         }
     }
+    /// Returns the [`Span`] the definition was parsed from
+    pub fn span(&self) -> Span {
+        match self {
+            TypeDefinition::Scalar(s) => s.span(),
+            TypeDefinition::Object(o) => o.span(),
+            TypeDefinition::Interface(i) => i.span(),
+            TypeDefinition::Union(u) => u.span(),
+            TypeDefinition::Enum(e) => e.span(),
+            TypeDefinition::InputObject(i) => i.span(),
+        }
+    }
     pub fn as_scalar(&self) -> Option<&ScalarType<'a, T>> {
         match self {
             Self::Scalar(s) => Some(s),
@@ -202,6 +863,7 @@ impl<'a, T: Text<'a>> TypeDefinition<'a, T> {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TypeExtension<'a, T: Text<'a>> {
     Scalar(ScalarTypeExtension<'a, T>),
     Object(ObjectTypeExtension<'a, T>),
@@ -211,9 +873,36 @@ pub enum TypeExtension<'a, T: Text<'a>> {
     InputObject(InputObjectTypeExtension<'a, T>),
 }
 
+impl<'a, T: Text<'a>> TypeExtension<'a, T> {
+    pub fn name(&self) -> &T {
+        match self {
+            TypeExtension::Scalar(s) => &s.name,
+            TypeExtension::Object(o) => &o.name,
+            TypeExtension::Interface(i) => &i.name,
+            TypeExtension::Union(u) => &u.name,
+            TypeExtension::Enum(e) => &e.name,
+            TypeExtension::InputObject(i) => &i.name,
+        }
+    }
+
+    pub fn position(&self) -> Pos {
+        match self {
+            TypeExtension::Scalar(s) => s.position,
+            TypeExtension::Object(o) => o.position,
+            TypeExtension::Interface(i) => i.position,
+            TypeExtension::Union(u) => u.position,
+            TypeExtension::Enum(e) => e.position,
+            TypeExtension::InputObject(i) => i.position,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ScalarType<'a, T: Text<'a>> {
     pub position: Pos,
+    /// Position right past the last token, see [`Span`]
+    pub end: Pos,
     pub description: Option<String>,
     pub name: T,
     pub directives: Vec<Directive<'a, T>>,
@@ -226,14 +915,21 @@ impl<'a, T> ScalarType<'a, T>
     pub fn new(name: T) -> Self {
         Self {
             position: Pos::default(),
+            end: Pos::default(),
             description: None,
             name,
             directives: vec![],
         }
     }
+
+    /// Returns the [`Span`] the definition was parsed from
+    pub fn span(&self) -> Span {
+        Span::new(self.position, self.end)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ScalarTypeExtension<'a, T: Text<'a>> {
     pub position: Pos,
     pub name: T,
@@ -254,8 +950,11 @@ impl<'a, T> ScalarTypeExtension<'a, T>
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ObjectType<'a, T: Text<'a>> {
     pub position: Pos,
+    /// Position right past the last token, see [`Span`]
+    pub end: Pos,
     pub description: Option<String>,
     pub name: T,
     pub implements_interfaces: Vec<T>,
@@ -270,6 +969,7 @@ impl<'a, T> ObjectType<'a, T>
     pub fn new(name: T) -> Self {
         Self {
             position: Pos::default(),
+            end: Pos::default(),
             description: None,
             name,
             implements_interfaces: vec![],
@@ -277,9 +977,25 @@ impl<'a, T> ObjectType<'a, T>
             fields: vec![],
         }
     }
+
+    /// Returns the [`Span`] the definition was parsed from
+    pub fn span(&self) -> Span {
+        Span::new(self.position, self.end)
+    }
+
+    /// Returns the field `name`
+    pub fn field(&self, name: &str) -> Option<&Field<'a, T>> {
+        self.fields.iter().find(|f| f.name.as_ref() == name)
+    }
+
+    /// Returns the field `name` for modification
+    pub fn field_mut(&mut self, name: &str) -> Option<&mut Field<'a, T>> {
+        self.fields.iter_mut().find(|f| f.name.as_ref() == name)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ObjectTypeExtension<'a, T: Text<'a>> {
     pub position: Pos,
     pub name: T,
@@ -304,8 +1020,11 @@ impl<'a, T> ObjectTypeExtension<'a, T>
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Field<'a, T: Text<'a>> {
     pub position: Pos,
+    /// Position right past the last token, see [`Span`]
+    pub end: Pos,
     pub description: Option<String>,
     pub name: T,
     pub arguments: Vec<InputValue<'a, T>>,
@@ -313,9 +1032,27 @@ pub struct Field<'a, T: Text<'a>> {
     pub directives: Vec<Directive<'a, T>>,
 }
 
+impl<'a, T> Field<'a, T>
+    where
+        T: Text<'a>,
+{
+    /// Returns the definition of the argument `name`
+    pub fn argument(&self, name: &str) -> Option<&InputValue<'a, T>> {
+        self.arguments.iter().find(|arg| arg.name.as_ref() == name)
+    }
+
+    /// Returns the [`Span`] the field was parsed from
+    pub fn span(&self) -> Span {
+        Span::new(self.position, self.end)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct InputValue<'a, T: Text<'a>> {
     pub position: Pos,
+    /// Position right past the last token, see [`Span`]
+    pub end: Pos,
     pub description: Option<String>,
     pub name: T,
     pub value_type: Type<'a, T>,
@@ -323,9 +1060,22 @@ pub struct InputValue<'a, T: Text<'a>> {
     pub directives: Vec<Directive<'a, T>>,
 }
 
+impl<'a, T> InputValue<'a, T>
+    where
+        T: Text<'a>,
+{
+    /// Returns the [`Span`] the value was parsed from
+    pub fn span(&self) -> Span {
+        Span::new(self.position, self.end)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct InterfaceType<'a, T: Text<'a>> {
     pub position: Pos,
+    /// Position right past the last token, see [`Span`]
+    pub end: Pos,
     pub description: Option<String>,
     pub name: T,
     pub implements_interfaces: Vec<T>,
@@ -340,6 +1090,7 @@ impl<'a, T> InterfaceType<'a, T>
     pub fn new(name: T) -> Self {
         Self {
             position: Pos::default(),
+            end: Pos::default(),
             description: None,
             name,
             implements_interfaces: vec![],
@@ -347,9 +1098,25 @@ impl<'a, T> InterfaceType<'a, T>
             fields: vec![],
         }
     }
+
+    /// Returns the [`Span`] the definition was parsed from
+    pub fn span(&self) -> Span {
+        Span::new(self.position, self.end)
+    }
+
+    /// Returns the field `name`
+    pub fn field(&self, name: &str) -> Option<&Field<'a, T>> {
+        self.fields.iter().find(|f| f.name.as_ref() == name)
+    }
+
+    /// Returns the field `name` for modification
+    pub fn field_mut(&mut self, name: &str) -> Option<&mut Field<'a, T>> {
+        self.fields.iter_mut().find(|f| f.name.as_ref() == name)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct InterfaceTypeExtension<'a, T: Text<'a>> {
     pub position: Pos,
     pub name: T,
@@ -374,8 +1141,11 @@ impl<'a, T> InterfaceTypeExtension<'a, T>
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct UnionType<'a, T: Text<'a>> {
     pub position: Pos,
+    /// Position right past the last token, see [`Span`]
+    pub end: Pos,
     pub description: Option<String>,
     pub name: T,
     pub directives: Vec<Directive<'a, T>>,
@@ -389,15 +1159,22 @@ impl<'a, T> UnionType<'a, T>
     pub fn new(name: T) -> Self {
         Self {
             position: Pos::default(),
+            end: Pos::default(),
             description: None,
             name,
             directives: vec![],
             types: vec![],
         }
     }
+
+    /// Returns the [`Span`] the definition was parsed from
+    pub fn span(&self) -> Span {
+        Span::new(self.position, self.end)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct UnionTypeExtension<'a, T: Text<'a>> {
     pub position: Pos,
     pub name: T,
@@ -420,8 +1197,11 @@ impl<'a, T> UnionTypeExtension<'a, T>
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EnumType<'a, T: Text<'a>> {
     pub position: Pos,
+    /// Position right past the last token, see [`Span`]
+    pub end: Pos,
     pub description: Option<String>,
     pub name: T,
     pub directives: Vec<Directive<'a, T>>,
@@ -435,15 +1215,32 @@ impl<'a, T> EnumType<'a, T>
     pub fn new(name: T) -> Self {
         Self {
             position: Pos::default(),
+            end: Pos::default(),
             description: None,
             name,
             directives: vec![],
             values: vec![],
         }
     }
+
+    /// Returns the [`Span`] the definition was parsed from
+    pub fn span(&self) -> Span {
+        Span::new(self.position, self.end)
+    }
+
+    /// Returns the enum value `name`
+    pub fn value(&self, name: &str) -> Option<&EnumValue<'a, T>> {
+        self.values.iter().find(|v| v.name.as_ref() == name)
+    }
+
+    /// Returns the enum value `name` for modification
+    pub fn value_mut(&mut self, name: &str) -> Option<&mut EnumValue<'a, T>> {
+        self.values.iter_mut().find(|v| v.name.as_ref() == name)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EnumValue<'a, T: Text<'a>> {
     pub position: Pos,
     pub description: Option<String>,
@@ -466,6 +1263,7 @@ impl<'a, T> EnumValue<'a, T>
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EnumTypeExtension<'a, T: Text<'a>> {
     pub position: Pos,
     pub name: T,
@@ -488,8 +1286,11 @@ impl<'a, T> EnumTypeExtension<'a, T>
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct InputObjectType<'a, T: Text<'a>> {
     pub position: Pos,
+    /// Position right past the last token, see [`Span`]
+    pub end: Pos,
     pub description: Option<String>,
     pub name: T,
     pub directives: Vec<Directive<'a, T>>,
@@ -503,15 +1304,32 @@ impl<'a, T> InputObjectType<'a, T>
     pub fn new(name: T) -> Self {
         Self {
             position: Pos::default(),
+            end: Pos::default(),
             description: None,
             name,
             directives: vec![],
             fields: vec![],
         }
     }
+
+    /// Returns the [`Span`] the definition was parsed from
+    pub fn span(&self) -> Span {
+        Span::new(self.position, self.end)
+    }
+
+    /// Returns the input field `name`
+    pub fn field(&self, name: &str) -> Option<&InputValue<'a, T>> {
+        self.fields.iter().find(|f| f.name.as_ref() == name)
+    }
+
+    /// Returns the input field `name` for modification
+    pub fn field_mut(&mut self, name: &str) -> Option<&mut InputValue<'a, T>> {
+        self.fields.iter_mut().find(|f| f.name.as_ref() == name)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct InputObjectTypeExtension<'a, T: Text<'a>> {
     pub position: Pos,
     pub name: T,
@@ -534,6 +1352,7 @@ impl<'a, T> InputObjectTypeExtension<'a, T>
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DirectiveLocation {
     // executable
     Query,
@@ -560,6 +1379,7 @@ pub enum DirectiveLocation {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DirectiveDefinition<'a, T: Text<'a>> {
     pub position: Pos,
     pub description: Option<String>,
@@ -630,10 +1450,18 @@ impl DirectiveLocation {
     }
 }
 
-#[derive(Debug, Error)]
-#[error("invalid directive location")]
+#[derive(Debug)]
 pub struct InvalidDirectiveLocation;
 
+impl fmt::Display for InvalidDirectiveLocation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("invalid directive location")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for InvalidDirectiveLocation {}
+
 impl FromStr for DirectiveLocation {
     type Err = InvalidDirectiveLocation;
     fn from_str(s: &str) -> Result<DirectiveLocation, InvalidDirectiveLocation> {
@@ -664,3 +1492,197 @@ impl FromStr for DirectiveLocation {
         Ok(val)
     }
 }
+
+/// Parses a `|`-separated list of directive locations
+///
+/// This accepts the same syntax as the `on` clause of a directive
+/// definition, e.g. `FIELD | OBJECT | ENUM_VALUE`, including an optional
+/// leading `|`. An empty (or whitespace-only) string is an empty list.
+pub fn parse_directive_locations(
+    s: &str,
+) -> Result<Vec<DirectiveLocation>, InvalidDirectiveLocation> {
+    let s = s.trim();
+    if s.is_empty() {
+        return Ok(Vec::new());
+    }
+    let s = s.strip_prefix('|').unwrap_or(s);
+    s.split('|').map(|loc| loc.trim().parse()).collect()
+}
+
+impl<'a, 'b, T, U, F> TextMapper<'a, 'b, T, U, F>
+where
+    T: Text<'a>,
+    U: Text<'b>,
+    F: FnMut(T) -> U,
+{
+    fn definition(&mut self, def: Definition<'a, T>) -> Definition<'b, U> {
+        match def {
+            Definition::SchemaDefinition(s) => Definition::SchemaDefinition(SchemaDefinition {
+                position: s.position,
+                directives: self.directives(s.directives),
+                query: s.query.map(|n| self.text(n)),
+                mutation: s.mutation.map(|n| self.text(n)),
+                subscription: s.subscription.map(|n| self.text(n)),
+                operation_order: s.operation_order,
+            }),
+            Definition::SchemaExtension(s) => Definition::SchemaExtension(SchemaExtension {
+                position: s.position,
+                directives: self.directives(s.directives),
+                query: s.query.map(|n| self.text(n)),
+                mutation: s.mutation.map(|n| self.text(n)),
+                subscription: s.subscription.map(|n| self.text(n)),
+                operation_order: s.operation_order,
+            }),
+            Definition::TypeDefinition(t) => Definition::TypeDefinition(self.type_definition(t)),
+            Definition::TypeExtension(t) => Definition::TypeExtension(self.type_extension(t)),
+            Definition::DirectiveDefinition(d) => {
+                Definition::DirectiveDefinition(DirectiveDefinition {
+                    position: d.position,
+                    description: d.description,
+                    name: self.text(d.name),
+                    arguments: self.input_values(d.arguments),
+                    repeatable: d.repeatable,
+                    locations: d.locations,
+                })
+            }
+        }
+    }
+
+    fn type_definition(&mut self, def: TypeDefinition<'a, T>) -> TypeDefinition<'b, U> {
+        match def {
+            TypeDefinition::Scalar(s) => TypeDefinition::Scalar(ScalarType {
+                position: s.position,
+                end: s.end,
+                description: s.description,
+                name: self.text(s.name),
+                directives: self.directives(s.directives),
+            }),
+            TypeDefinition::Object(o) => TypeDefinition::Object(ObjectType {
+                position: o.position,
+                end: o.end,
+                description: o.description,
+                name: self.text(o.name),
+                implements_interfaces: self.texts(o.implements_interfaces),
+                directives: self.directives(o.directives),
+                fields: self.fields(o.fields),
+            }),
+            TypeDefinition::Interface(i) => TypeDefinition::Interface(InterfaceType {
+                position: i.position,
+                end: i.end,
+                description: i.description,
+                name: self.text(i.name),
+                implements_interfaces: self.texts(i.implements_interfaces),
+                directives: self.directives(i.directives),
+                fields: self.fields(i.fields),
+            }),
+            TypeDefinition::Union(u) => TypeDefinition::Union(UnionType {
+                position: u.position,
+                end: u.end,
+                description: u.description,
+                name: self.text(u.name),
+                directives: self.directives(u.directives),
+                types: self.texts(u.types),
+            }),
+            TypeDefinition::Enum(e) => TypeDefinition::Enum(EnumType {
+                position: e.position,
+                end: e.end,
+                description: e.description,
+                name: self.text(e.name),
+                directives: self.directives(e.directives),
+                values: self.enum_values(e.values),
+            }),
+            TypeDefinition::InputObject(i) => TypeDefinition::InputObject(InputObjectType {
+                position: i.position,
+                end: i.end,
+                description: i.description,
+                name: self.text(i.name),
+                directives: self.directives(i.directives),
+                fields: self.input_values(i.fields),
+            }),
+        }
+    }
+
+    fn type_extension(&mut self, ext: TypeExtension<'a, T>) -> TypeExtension<'b, U> {
+        match ext {
+            TypeExtension::Scalar(s) => TypeExtension::Scalar(ScalarTypeExtension {
+                position: s.position,
+                name: self.text(s.name),
+                directives: self.directives(s.directives),
+            }),
+            TypeExtension::Object(o) => TypeExtension::Object(ObjectTypeExtension {
+                position: o.position,
+                name: self.text(o.name),
+                implements_interfaces: self.texts(o.implements_interfaces),
+                directives: self.directives(o.directives),
+                fields: self.fields(o.fields),
+            }),
+            TypeExtension::Interface(i) => TypeExtension::Interface(InterfaceTypeExtension {
+                position: i.position,
+                name: self.text(i.name),
+                implements_interfaces: self.texts(i.implements_interfaces),
+                directives: self.directives(i.directives),
+                fields: self.fields(i.fields),
+            }),
+            TypeExtension::Union(u) => TypeExtension::Union(UnionTypeExtension {
+                position: u.position,
+                name: self.text(u.name),
+                directives: self.directives(u.directives),
+                types: self.texts(u.types),
+            }),
+            TypeExtension::Enum(e) => TypeExtension::Enum(EnumTypeExtension {
+                position: e.position,
+                name: self.text(e.name),
+                directives: self.directives(e.directives),
+                values: self.enum_values(e.values),
+            }),
+            TypeExtension::InputObject(i) => TypeExtension::InputObject(InputObjectTypeExtension {
+                position: i.position,
+                name: self.text(i.name),
+                directives: self.directives(i.directives),
+                fields: self.input_values(i.fields),
+            }),
+        }
+    }
+
+    fn fields(&mut self, fields: Vec<Field<'a, T>>) -> Vec<Field<'b, U>> {
+        fields
+            .into_iter()
+            .map(|f| Field {
+                position: f.position,
+                end: f.end,
+                description: f.description,
+                name: self.text(f.name),
+                arguments: self.input_values(f.arguments),
+                field_type: self.typ(f.field_type),
+                directives: self.directives(f.directives),
+            })
+            .collect()
+    }
+
+    fn input_values(&mut self, values: Vec<InputValue<'a, T>>) -> Vec<InputValue<'b, U>> {
+        values
+            .into_iter()
+            .map(|v| InputValue {
+                position: v.position,
+                end: v.end,
+                description: v.description,
+                name: self.text(v.name),
+                value_type: self.typ(v.value_type),
+                default_value: v.default_value.map(|d| self.value(d)),
+                directives: self.directives(v.directives),
+            })
+            .collect()
+    }
+
+    fn enum_values(&mut self, values: Vec<EnumValue<'a, T>>) -> Vec<EnumValue<'b, U>> {
+        values
+            .into_iter()
+            .map(|v| EnumValue {
+                position: v.position,
+                description: v.description,
+                name: self.text(v.name),
+                directives: self.directives(v.directives),
+            })
+            .collect()
+    }
+}
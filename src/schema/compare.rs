@@ -0,0 +1,228 @@
+//! Comparing schema definitions by structure only
+//!
+use alloc::string::String;
+
+use crate::common::{StructuralEq, Text};
+use crate::schema::ast::*;
+
+/// Descriptions only count when comparing strictly
+fn description_eq(a: &Option<String>, b: &Option<String>, loose: bool) -> bool {
+    loose || a == b
+}
+
+impl<'a, T: Text<'a>> StructuralEq for InputValue<'a, T> {
+    fn structural_eq(&self, other: &Self, loose: bool) -> bool {
+        description_eq(&self.description, &other.description, loose)
+            && self.name == other.name
+            && self.value_type == other.value_type
+            && self.default_value == other.default_value
+            && self.directives.structural_eq(&other.directives, loose)
+    }
+}
+
+impl<'a, T: Text<'a>> StructuralEq for Field<'a, T> {
+    fn structural_eq(&self, other: &Self, loose: bool) -> bool {
+        description_eq(&self.description, &other.description, loose)
+            && self.name == other.name
+            && self.arguments.structural_eq(&other.arguments, loose)
+            && self.field_type == other.field_type
+            && self.directives.structural_eq(&other.directives, loose)
+    }
+}
+
+impl<'a, T: Text<'a>> StructuralEq for EnumValue<'a, T> {
+    fn structural_eq(&self, other: &Self, loose: bool) -> bool {
+        description_eq(&self.description, &other.description, loose)
+            && self.name == other.name
+            && self.directives.structural_eq(&other.directives, loose)
+    }
+}
+
+impl<'a, T: Text<'a>> StructuralEq for TypeDefinition<'a, T> {
+    fn structural_eq(&self, other: &Self, loose: bool) -> bool {
+        use crate::schema::TypeDefinition::*;
+        match (self, other) {
+            (Scalar(a), Scalar(b)) => {
+                description_eq(&a.description, &b.description, loose)
+                    && a.name == b.name
+                    && a.directives.structural_eq(&b.directives, loose)
+            }
+            (Object(a), Object(b)) => {
+                description_eq(&a.description, &b.description, loose)
+                    && a.name == b.name
+                    && a.implements_interfaces == b.implements_interfaces
+                    && a.directives.structural_eq(&b.directives, loose)
+                    && a.fields.structural_eq(&b.fields, loose)
+            }
+            (Interface(a), Interface(b)) => {
+                description_eq(&a.description, &b.description, loose)
+                    && a.name == b.name
+                    && a.implements_interfaces == b.implements_interfaces
+                    && a.directives.structural_eq(&b.directives, loose)
+                    && a.fields.structural_eq(&b.fields, loose)
+            }
+            (Union(a), Union(b)) => {
+                description_eq(&a.description, &b.description, loose)
+                    && a.name == b.name
+                    && a.directives.structural_eq(&b.directives, loose)
+                    && a.types == b.types
+            }
+            (Enum(a), Enum(b)) => {
+                description_eq(&a.description, &b.description, loose)
+                    && a.name == b.name
+                    && a.directives.structural_eq(&b.directives, loose)
+                    && a.values.structural_eq(&b.values, loose)
+            }
+            (InputObject(a), InputObject(b)) => {
+                description_eq(&a.description, &b.description, loose)
+                    && a.name == b.name
+                    && a.directives.structural_eq(&b.directives, loose)
+                    && a.fields.structural_eq(&b.fields, loose)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> StructuralEq for TypeExtension<'a, T> {
+    fn structural_eq(&self, other: &Self, loose: bool) -> bool {
+        use crate::schema::TypeExtension::*;
+        match (self, other) {
+            (Scalar(a), Scalar(b)) => {
+                a.name == b.name && a.directives.structural_eq(&b.directives, loose)
+            }
+            (Object(a), Object(b)) => {
+                a.name == b.name
+                    && a.implements_interfaces == b.implements_interfaces
+                    && a.directives.structural_eq(&b.directives, loose)
+                    && a.fields.structural_eq(&b.fields, loose)
+            }
+            (Interface(a), Interface(b)) => {
+                a.name == b.name
+                    && a.implements_interfaces == b.implements_interfaces
+                    && a.directives.structural_eq(&b.directives, loose)
+                    && a.fields.structural_eq(&b.fields, loose)
+            }
+            (Union(a), Union(b)) => {
+                a.name == b.name
+                    && a.directives.structural_eq(&b.directives, loose)
+                    && a.types == b.types
+            }
+            (Enum(a), Enum(b)) => {
+                a.name == b.name
+                    && a.directives.structural_eq(&b.directives, loose)
+                    && a.values.structural_eq(&b.values, loose)
+            }
+            (InputObject(a), InputObject(b)) => {
+                a.name == b.name
+                    && a.directives.structural_eq(&b.directives, loose)
+                    && a.fields.structural_eq(&b.fields, loose)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> StructuralEq for Definition<'a, T> {
+    fn structural_eq(&self, other: &Self, loose: bool) -> bool {
+        use crate::schema::Definition::*;
+        match (self, other) {
+            (SchemaDefinition(a), SchemaDefinition(b)) => {
+                a.directives.structural_eq(&b.directives, loose)
+                    && a.query == b.query
+                    && a.mutation == b.mutation
+                    && a.subscription == b.subscription
+                    && (loose || a.operation_order == b.operation_order)
+            }
+            (SchemaExtension(a), SchemaExtension(b)) => {
+                a.directives.structural_eq(&b.directives, loose)
+                    && a.query == b.query
+                    && a.mutation == b.mutation
+                    && a.subscription == b.subscription
+                    && (loose || a.operation_order == b.operation_order)
+            }
+            (TypeDefinition(a), TypeDefinition(b)) => a.structural_eq(b, loose),
+            (TypeExtension(a), TypeExtension(b)) => a.structural_eq(b, loose),
+            (DirectiveDefinition(a), DirectiveDefinition(b)) => {
+                description_eq(&a.description, &b.description, loose)
+                    && a.name == b.name
+                    && a.arguments.structural_eq(&b.arguments, loose)
+                    && a.repeatable == b.repeatable
+                    && a.locations == b.locations
+            }
+            _ => false,
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> TypeDefinition<'a, T> {
+    /// Compares two type definitions ignoring descriptions
+    ///
+    /// Source positions are ignored as well, since changing a description
+    /// usually shifts everything after it.
+    pub fn eq_ignoring_descriptions(&self, other: &Self) -> bool {
+        self.structural_eq(other, true)
+    }
+}
+
+impl<'a, T: Text<'a>> Document<'a, T> {
+    /// Compares two documents ignoring descriptions
+    ///
+    /// Definitions are compared in order. As with
+    /// [`TypeDefinition::eq_ignoring_descriptions`], source positions are
+    /// ignored, and so is the order root operations are listed in the
+    /// schema definition.
+    pub fn eq_ignoring_descriptions(&self, other: &Self) -> bool {
+        self.definitions.structural_eq(&other.definitions, true)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::schema::{parse_schema, Definition, Document, TypeDefinition};
+
+    #[test]
+    fn descriptions_ignored() {
+        let a = parse_schema::<&str>(
+            "schema { query: Q mutation: M }
+             \"Query\" type Q { \"field\" f(\"arg\" a: Int = 1 @d): E }
+             enum E { \"value\" A }
+             \"dir\" directive @d(x: Int) on ARGUMENT_DEFINITION",
+        )
+        .unwrap();
+        let b = parse_schema::<&str>(
+            "schema { mutation: M query: Q }
+             type Q {
+               \"\"\"
+               New docs
+               \"\"\"
+               f(a: Int = 1 @d): E
+             }
+             enum E { A }
+             directive @d(x: Int) on ARGUMENT_DEFINITION",
+        )
+        .unwrap();
+        assert!(a.eq_ignoring_descriptions(&b));
+        assert_ne!(a, b);
+
+        let c = parse_schema::<&str>(
+            "schema { query: Q mutation: M }
+             type Q { f(a: Int = 2 @d): E }
+             enum E { A }
+             directive @d(x: Int) on ARGUMENT_DEFINITION",
+        )
+        .unwrap();
+        assert!(!a.eq_ignoring_descriptions(&c));
+        fn type_def<'d, 'a>(
+            doc: &'d Document<'a, &'a str>,
+            idx: usize,
+        ) -> &'d TypeDefinition<'a, &'a str> {
+            match doc.definitions[idx] {
+                Definition::TypeDefinition(ref t) => t,
+                _ => unreachable!(),
+            }
+        }
+        assert!(type_def(&a, 2).eq_ignoring_descriptions(type_def(&c, 2)));
+        assert!(!type_def(&a, 1).eq_ignoring_descriptions(type_def(&c, 1)));
+    }
+}
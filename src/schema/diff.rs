@@ -0,0 +1,564 @@
+//! Finding changes between two versions of a schema
+//!
+use std::ptr;
+
+use crate::common::{Text, Type};
+use crate::schema::ast::*;
+use crate::schema::extend::apply_extensions;
+use crate::schema::index::SchemaIndex;
+
+/// Change between two versions of a schema, see [`diff`]
+///
+/// `breaking` is set if queries or variables valid for the old schema may
+/// be invalid for the new one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaChange {
+    TypeAdded {
+        type_name: String,
+        breaking: bool,
+    },
+    TypeRemoved {
+        type_name: String,
+        breaking: bool,
+    },
+    /// Type was redefined as a different kind, e.g. an object as an interface
+    TypeKindChanged {
+        type_name: String,
+        breaking: bool,
+    },
+    /// Field was added to an object, interface or input object type
+    FieldAdded {
+        type_name: String,
+        field_name: String,
+        breaking: bool,
+    },
+    FieldRemoved {
+        type_name: String,
+        field_name: String,
+        breaking: bool,
+    },
+    /// Type of a field changed, types are formatted as in the schema
+    FieldTypeChanged {
+        type_name: String,
+        field_name: String,
+        old_type: String,
+        new_type: String,
+        breaking: bool,
+    },
+    ArgumentAdded {
+        type_name: String,
+        field_name: String,
+        argument_name: String,
+        breaking: bool,
+    },
+    ArgumentRemoved {
+        type_name: String,
+        field_name: String,
+        argument_name: String,
+        breaking: bool,
+    },
+    ArgumentTypeChanged {
+        type_name: String,
+        field_name: String,
+        argument_name: String,
+        old_type: String,
+        new_type: String,
+        breaking: bool,
+    },
+    EnumValueAdded {
+        type_name: String,
+        value_name: String,
+        breaking: bool,
+    },
+    EnumValueRemoved {
+        type_name: String,
+        value_name: String,
+        breaking: bool,
+    },
+    UnionMemberAdded {
+        type_name: String,
+        member_name: String,
+        breaking: bool,
+    },
+    UnionMemberRemoved {
+        type_name: String,
+        member_name: String,
+        breaking: bool,
+    },
+    InterfaceAdded {
+        type_name: String,
+        interface_name: String,
+        breaking: bool,
+    },
+    InterfaceRemoved {
+        type_name: String,
+        interface_name: String,
+        breaking: bool,
+    },
+}
+
+impl SchemaChange {
+    /// Returns the `breaking` flag of the change
+    pub fn is_breaking(&self) -> bool {
+        match self {
+            SchemaChange::TypeAdded { breaking, .. }
+            | SchemaChange::TypeRemoved { breaking, .. }
+            | SchemaChange::TypeKindChanged { breaking, .. }
+            | SchemaChange::FieldAdded { breaking, .. }
+            | SchemaChange::FieldRemoved { breaking, .. }
+            | SchemaChange::FieldTypeChanged { breaking, .. }
+            | SchemaChange::ArgumentAdded { breaking, .. }
+            | SchemaChange::ArgumentRemoved { breaking, .. }
+            | SchemaChange::ArgumentTypeChanged { breaking, .. }
+            | SchemaChange::EnumValueAdded { breaking, .. }
+            | SchemaChange::EnumValueRemoved { breaking, .. }
+            | SchemaChange::UnionMemberAdded { breaking, .. }
+            | SchemaChange::UnionMemberRemoved { breaking, .. }
+            | SchemaChange::InterfaceAdded { breaking, .. }
+            | SchemaChange::InterfaceRemoved { breaking, .. } => *breaking,
+        }
+    }
+}
+
+/// Returns `true` if a value of type `old` is always a value of type `new`
+///
+/// That is the case if `new` only drops non-null wrappers of `old`. Output
+/// types are compatible the other way around.
+fn accepts<'a, T: Text<'a>>(old: &Type<'a, T>, new: &Type<'a, T>) -> bool {
+    match (old, new) {
+        (Type::NamedType(a), Type::NamedType(b)) => a == b,
+        (Type::ListType(a), Type::ListType(b)) => accepts(a, b),
+        (Type::NonNullType(a, _), Type::NonNullType(b, _)) => accepts(a, b),
+        (Type::NonNullType(a, _), b) => accepts(a, b),
+        _ => false,
+    }
+}
+
+fn is_required<'a, T: Text<'a>>(value: &InputValue<'a, T>) -> bool {
+    matches!(value.value_type, Type::NonNullType(..)) && value.default_value.is_none()
+}
+
+fn names<'a, T: Text<'a>>(
+    changes: &mut Vec<SchemaChange>,
+    old: &[T],
+    new: &[T],
+    removed: impl Fn(String) -> SchemaChange,
+    added: impl Fn(String) -> SchemaChange,
+) {
+    for name in old.iter().filter(|name| !new.contains(name)) {
+        changes.push(removed(name.as_ref().to_string()));
+    }
+    for name in new.iter().filter(|name| !old.contains(name)) {
+        changes.push(added(name.as_ref().to_string()));
+    }
+}
+
+fn arguments<'a, T: Text<'a>>(
+    changes: &mut Vec<SchemaChange>,
+    type_name: &str,
+    field_name: &str,
+    old: &[InputValue<'a, T>],
+    new: &[InputValue<'a, T>],
+) {
+    for o in old {
+        let argument_name = o.name.as_ref().to_string();
+        match new.iter().find(|n| n.name == o.name) {
+            None => changes.push(SchemaChange::ArgumentRemoved {
+                type_name: type_name.to_string(),
+                field_name: field_name.to_string(),
+                argument_name,
+                breaking: true,
+            }),
+            Some(n) if n.value_type != o.value_type => {
+                changes.push(SchemaChange::ArgumentTypeChanged {
+                    type_name: type_name.to_string(),
+                    field_name: field_name.to_string(),
+                    argument_name,
+                    old_type: o.value_type.to_string(),
+                    new_type: n.value_type.to_string(),
+                    breaking: !accepts(&o.value_type, &n.value_type),
+                })
+            }
+            Some(_) => {}
+        }
+    }
+    for n in new.iter().filter(|n| old.iter().all(|o| o.name != n.name)) {
+        changes.push(SchemaChange::ArgumentAdded {
+            type_name: type_name.to_string(),
+            field_name: field_name.to_string(),
+            argument_name: n.name.as_ref().to_string(),
+            breaking: is_required(n),
+        });
+    }
+}
+
+fn output_fields<'a, T: Text<'a>>(
+    changes: &mut Vec<SchemaChange>,
+    type_name: &str,
+    old: &[Field<'a, T>],
+    new: &[Field<'a, T>],
+) {
+    for o in old {
+        let field_name = o.name.as_ref().to_string();
+        let n = match new.iter().find(|n| n.name == o.name) {
+            Some(n) => n,
+            None => {
+                changes.push(SchemaChange::FieldRemoved {
+                    type_name: type_name.to_string(),
+                    field_name,
+                    breaking: true,
+                });
+                continue;
+            }
+        };
+        if n.field_type != o.field_type {
+            changes.push(SchemaChange::FieldTypeChanged {
+                type_name: type_name.to_string(),
+                field_name: field_name.clone(),
+                old_type: o.field_type.to_string(),
+                new_type: n.field_type.to_string(),
+                breaking: !accepts(&n.field_type, &o.field_type),
+            });
+        }
+        arguments(changes, type_name, &field_name, &o.arguments, &n.arguments);
+    }
+    for n in new.iter().filter(|n| old.iter().all(|o| o.name != n.name)) {
+        changes.push(SchemaChange::FieldAdded {
+            type_name: type_name.to_string(),
+            field_name: n.name.as_ref().to_string(),
+            breaking: false,
+        });
+    }
+}
+
+fn input_fields<'a, T: Text<'a>>(
+    changes: &mut Vec<SchemaChange>,
+    type_name: &str,
+    old: &[InputValue<'a, T>],
+    new: &[InputValue<'a, T>],
+) {
+    for o in old {
+        let field_name = o.name.as_ref().to_string();
+        match new.iter().find(|n| n.name == o.name) {
+            None => changes.push(SchemaChange::FieldRemoved {
+                type_name: type_name.to_string(),
+                field_name,
+                breaking: true,
+            }),
+            Some(n) if n.value_type != o.value_type => {
+                changes.push(SchemaChange::FieldTypeChanged {
+                    type_name: type_name.to_string(),
+                    field_name,
+                    old_type: o.value_type.to_string(),
+                    new_type: n.value_type.to_string(),
+                    breaking: !accepts(&o.value_type, &n.value_type),
+                })
+            }
+            Some(_) => {}
+        }
+    }
+    for n in new.iter().filter(|n| old.iter().all(|o| o.name != n.name)) {
+        changes.push(SchemaChange::FieldAdded {
+            type_name: type_name.to_string(),
+            field_name: n.name.as_ref().to_string(),
+            breaking: is_required(n),
+        });
+    }
+}
+
+fn interfaces<'a, T: Text<'a>>(
+    changes: &mut Vec<SchemaChange>,
+    type_name: &str,
+    old: &[T],
+    new: &[T],
+) {
+    names(
+        changes,
+        old,
+        new,
+        |interface_name| SchemaChange::InterfaceRemoved {
+            type_name: type_name.to_string(),
+            interface_name,
+            breaking: true,
+        },
+        |interface_name| SchemaChange::InterfaceAdded {
+            type_name: type_name.to_string(),
+            interface_name,
+            breaking: false,
+        },
+    );
+}
+
+fn type_definition<'a, T: Text<'a>>(
+    changes: &mut Vec<SchemaChange>,
+    old: &TypeDefinition<'a, T>,
+    new: &TypeDefinition<'a, T>,
+) {
+    let type_name = old.name().as_ref();
+    match (old, new) {
+        (TypeDefinition::Object(o), TypeDefinition::Object(n)) => {
+            interfaces(
+                changes,
+                type_name,
+                &o.implements_interfaces,
+                &n.implements_interfaces,
+            );
+            output_fields(changes, type_name, &o.fields, &n.fields);
+        }
+        (TypeDefinition::Interface(o), TypeDefinition::Interface(n)) => {
+            interfaces(
+                changes,
+                type_name,
+                &o.implements_interfaces,
+                &n.implements_interfaces,
+            );
+            output_fields(changes, type_name, &o.fields, &n.fields);
+        }
+        (TypeDefinition::InputObject(o), TypeDefinition::InputObject(n)) => {
+            input_fields(changes, type_name, &o.fields, &n.fields);
+        }
+        (TypeDefinition::Union(o), TypeDefinition::Union(n)) => names(
+            changes,
+            &o.types,
+            &n.types,
+            |member_name| SchemaChange::UnionMemberRemoved {
+                type_name: type_name.to_string(),
+                member_name,
+                breaking: true,
+            },
+            |member_name| SchemaChange::UnionMemberAdded {
+                type_name: type_name.to_string(),
+                member_name,
+                breaking: false,
+            },
+        ),
+        (TypeDefinition::Enum(o), TypeDefinition::Enum(n)) => {
+            let old_values = o.values.iter().map(|v| v.name.clone()).collect::<Vec<_>>();
+            let new_values = n.values.iter().map(|v| v.name.clone()).collect::<Vec<_>>();
+            names(
+                changes,
+                &old_values,
+                &new_values,
+                |value_name| SchemaChange::EnumValueRemoved {
+                    type_name: type_name.to_string(),
+                    value_name,
+                    breaking: true,
+                },
+                |value_name| SchemaChange::EnumValueAdded {
+                    type_name: type_name.to_string(),
+                    value_name,
+                    breaking: false,
+                },
+            );
+        }
+        (TypeDefinition::Scalar(_), TypeDefinition::Scalar(_)) => {}
+        _ => changes.push(SchemaChange::TypeKindChanged {
+            type_name: type_name.to_string(),
+            breaking: true,
+        }),
+    }
+}
+
+/// Returns type definitions of `doc`, skipping all but the first
+/// definition of each type
+fn first_definitions<'d, 'a, T: Text<'a>>(
+    doc: &'d Document<'a, T>,
+    index: &'d SchemaIndex<'d, 'a, T>,
+) -> impl Iterator<Item = &'d TypeDefinition<'a, T>> {
+    doc.definitions.iter().filter_map(move |def| match def {
+        Definition::TypeDefinition(t) => index
+            .type_def(t.name().as_ref())
+            .filter(|first| ptr::eq(*first, t)),
+        _ => None,
+    })
+}
+
+/// Lists the changes between two versions of a schema
+///
+/// Type extensions of both documents are merged first, as by
+/// [`apply_extensions`]. If that fails for a document, its extensions are
+/// ignored. Types are matched by name, the first definition is used if a
+/// type is defined more than once. Changes of types in `old` come first, in
+/// the order of `old`, followed by types added in `new`.
+///
+/// Removing a type, field, argument, enum value, union member or interface
+/// is breaking, and so is changing the kind of a type. Adding a field or an
+/// argument is breaking only if it's a non-null input without a default.
+/// A changed field type is breaking unless it only adds non-null wrappers
+/// to an output field, or only drops them from an argument or input field.
+///
+/// Descriptions, directives, default values, directive definitions and the
+/// schema definition aren't compared.
+pub fn diff<'a, T>(old: &Document<'a, T>, new: &Document<'a, T>) -> Vec<SchemaChange>
+where
+    T: Text<'a>,
+{
+    let old = apply_extensions(old.clone()).unwrap_or_else(|_| old.clone());
+    let new = apply_extensions(new.clone()).unwrap_or_else(|_| new.clone());
+    let old_index = SchemaIndex::new(&old);
+    let new_index = SchemaIndex::new(&new);
+    let mut changes = Vec::new();
+    for o in first_definitions(&old, &old_index) {
+        match new_index.type_def(o.name().as_ref()) {
+            Some(n) => type_definition(&mut changes, o, n),
+            None => changes.push(SchemaChange::TypeRemoved {
+                type_name: o.name().as_ref().to_string(),
+                breaking: true,
+            }),
+        }
+    }
+    for n in first_definitions(&new, &new_index) {
+        if old_index.type_def(n.name().as_ref()).is_none() {
+            changes.push(SchemaChange::TypeAdded {
+                type_name: n.name().as_ref().to_string(),
+                breaking: false,
+            });
+        }
+    }
+    changes
+}
+
+#[cfg(test)]
+mod test {
+    use super::{diff, SchemaChange};
+    use crate::schema::parse_schema;
+
+    #[test]
+    fn changes() {
+        let old = parse_schema::<&str>(
+            "type Query implements Node { a: Int, b(x: Int, y: Int!): String!, c: [Int] }
+             interface Node { id: ID }
+             input Filter { a: Int!, b: String }
+             enum Color { RED GREEN }
+             union Item = Query | Other
+             type Other { a: Int }
+             scalar Date",
+        )
+        .unwrap();
+        let new = parse_schema::<&str>(
+            "type Query implements Node & Named { a: Int!, b(y: Int, z: Int): String, c: [Int]! }
+             extend type Query { d: Int }
+             input Filter { a: Int, c: Int! }
+             enum Color { RED BLUE }
+             union Item = Query
+             interface Other { a: Int }
+             scalar Date
+             scalar Time",
+        )
+        .unwrap();
+        let changes = diff(&old, &new);
+        let s = |s: &str| s.to_string();
+        assert_eq!(
+            changes,
+            vec![
+                SchemaChange::InterfaceAdded {
+                    type_name: s("Query"),
+                    interface_name: s("Named"),
+                    breaking: false,
+                },
+                SchemaChange::FieldTypeChanged {
+                    type_name: s("Query"),
+                    field_name: s("a"),
+                    old_type: s("Int"),
+                    new_type: s("Int!"),
+                    breaking: false,
+                },
+                SchemaChange::FieldTypeChanged {
+                    type_name: s("Query"),
+                    field_name: s("b"),
+                    old_type: s("String!"),
+                    new_type: s("String"),
+                    breaking: true,
+                },
+                SchemaChange::ArgumentRemoved {
+                    type_name: s("Query"),
+                    field_name: s("b"),
+                    argument_name: s("x"),
+                    breaking: true,
+                },
+                SchemaChange::ArgumentTypeChanged {
+                    type_name: s("Query"),
+                    field_name: s("b"),
+                    argument_name: s("y"),
+                    old_type: s("Int!"),
+                    new_type: s("Int"),
+                    breaking: false,
+                },
+                SchemaChange::ArgumentAdded {
+                    type_name: s("Query"),
+                    field_name: s("b"),
+                    argument_name: s("z"),
+                    breaking: false,
+                },
+                SchemaChange::FieldTypeChanged {
+                    type_name: s("Query"),
+                    field_name: s("c"),
+                    old_type: s("[Int]"),
+                    new_type: s("[Int]!"),
+                    breaking: false,
+                },
+                SchemaChange::FieldAdded {
+                    type_name: s("Query"),
+                    field_name: s("d"),
+                    breaking: false,
+                },
+                SchemaChange::TypeRemoved {
+                    type_name: s("Node"),
+                    breaking: true,
+                },
+                SchemaChange::FieldTypeChanged {
+                    type_name: s("Filter"),
+                    field_name: s("a"),
+                    old_type: s("Int!"),
+                    new_type: s("Int"),
+                    breaking: false,
+                },
+                SchemaChange::FieldRemoved {
+                    type_name: s("Filter"),
+                    field_name: s("b"),
+                    breaking: true,
+                },
+                SchemaChange::FieldAdded {
+                    type_name: s("Filter"),
+                    field_name: s("c"),
+                    breaking: true,
+                },
+                SchemaChange::EnumValueRemoved {
+                    type_name: s("Color"),
+                    value_name: s("GREEN"),
+                    breaking: true,
+                },
+                SchemaChange::EnumValueAdded {
+                    type_name: s("Color"),
+                    value_name: s("BLUE"),
+                    breaking: false,
+                },
+                SchemaChange::UnionMemberRemoved {
+                    type_name: s("Item"),
+                    member_name: s("Other"),
+                    breaking: true,
+                },
+                SchemaChange::TypeKindChanged {
+                    type_name: s("Other"),
+                    breaking: true,
+                },
+                SchemaChange::TypeAdded {
+                    type_name: s("Time"),
+                    breaking: false,
+                },
+            ]
+        );
+        assert_eq!(changes.iter().filter(|c| c.is_breaking()).count(), 8);
+    }
+
+    #[test]
+    fn unchanged() {
+        let old = parse_schema::<&str>(
+            "\"Root\" type Query @tag { a(x: Int = 1): Int }
+             type Query { b: Int }",
+        )
+        .unwrap();
+        let new = parse_schema::<&str>("type Query { a(x: Int = 2): Int }").unwrap();
+        assert_eq!(diff(&old, &new), vec![]);
+    }
+}
@@ -0,0 +1,632 @@
+//! Applying structural edits to schema source text
+//!
+//! Edits only touch the text of the affected definitions, so comments and
+//! formatting of the rest of the document are preserved as is.
+use combine::StreamOnce;
+use thiserror::Error;
+
+use crate::schema::{parse_schema, ParseError};
+use crate::tokenizer::{Kind, TokenStream};
+
+const TYPE_KEYWORDS: &[&str] = &["scalar", "type", "interface", "union", "enum", "input"];
+const KEYWORDS: &[&str] = &[
+    "scalar",
+    "type",
+    "interface",
+    "union",
+    "enum",
+    "input",
+    "schema",
+    "directive",
+    "extend",
+];
+
+/// Change to apply to a schema with [`apply_edit`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaEdit<'e> {
+    /// Appends a field, e.g. `"email: String"`, to the definition of a type
+    ///
+    /// A fields block written on a single line is re-emitted with one field
+    /// per line.
+    AddField { type_name: &'e str, field: &'e str },
+    /// Removes a field from a type, along with its description
+    RemoveField {
+        type_name: &'e str,
+        field_name: &'e str,
+    },
+    /// Appends a directive, e.g. `"@key(fields: \"id\")"`, to the
+    /// definition of a type
+    AddDirective {
+        type_name: &'e str,
+        directive: &'e str,
+    },
+    /// Renames a type, including its extensions and all references to it
+    Rename { from: &'e str, to: &'e str },
+}
+
+/// Error applying a [`SchemaEdit`]
+#[derive(Error, Debug)]
+pub enum EditError {
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+    #[error("type {0:?} is not defined")]
+    UnknownType(String),
+    #[error("type {0:?} is already defined")]
+    DuplicateType(String),
+    #[error("field {field:?} is not defined on type {type_name:?}")]
+    UnknownField { type_name: String, field: String },
+    #[error("type {0:?} can't have fields")]
+    NoFields(String),
+    #[error("edit results in an invalid schema: {0}")]
+    InvalidEdit(ParseError),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Tok<'s> {
+    kind: Kind,
+    value: &'s str,
+    start: usize,
+    end: usize,
+}
+
+/// Location of a field (or an enum value) in the source
+#[derive(Debug)]
+struct FieldOutline<'s> {
+    name: &'s str,
+    /// Includes the description
+    start: usize,
+    end: usize,
+}
+
+/// Location of a type definition or extension in the source
+#[derive(Debug)]
+struct TypeOutline<'s> {
+    extend: bool,
+    keyword: &'s str,
+    name: &'s str,
+    /// End of the last token before the fields block
+    header_end: usize,
+    /// Offsets of the braces around fields
+    block: Option<(usize, usize)>,
+    fields: Vec<FieldOutline<'s>>,
+}
+
+struct Scanner<'s> {
+    toks: Vec<Tok<'s>>,
+    idx: usize,
+    types: Vec<TypeOutline<'s>>,
+    /// Every occurrence of a type name, as byte ranges
+    type_refs: Vec<(usize, usize)>,
+}
+
+impl<'s> Scanner<'s> {
+    fn new(source: &'s str) -> Scanner<'s> {
+        let mut toks = Vec::new();
+        let mut stream = TokenStream::new(source);
+        loop {
+            let start = stream.offset();
+            match stream.uncons() {
+                Ok(tok) => toks.push(Tok {
+                    kind: tok.kind,
+                    value: tok.value,
+                    start,
+                    end: start + tok.value.len(),
+                }),
+                Err(_) => break,
+            }
+        }
+        Scanner {
+            toks,
+            idx: 0,
+            types: Vec::new(),
+            type_refs: Vec::new(),
+        }
+    }
+
+    fn peek(&self) -> Option<Tok<'s>> {
+        self.toks.get(self.idx).cloned()
+    }
+
+    fn is_punct(&self, value: &str) -> bool {
+        matches!(self.peek(), Some(t) if t.kind == Kind::Punctuator && t.value == value)
+    }
+
+    fn is_name(&self) -> bool {
+        matches!(self.peek(), Some(t) if t.kind == Kind::Name)
+    }
+
+    fn is_keyword(&self) -> bool {
+        matches!(self.peek(), Some(t) if t.kind == Kind::Name && KEYWORDS.contains(&t.value))
+    }
+
+    fn is_string(&self) -> bool {
+        matches!(
+            self.peek(),
+            Some(t) if t.kind == Kind::StringValue || t.kind == Kind::BlockString
+        )
+    }
+
+    fn prev_end(&self) -> usize {
+        self.idx
+            .checked_sub(1)
+            .and_then(|i| self.toks.get(i))
+            .map_or(0, |t| t.end)
+    }
+
+    fn type_name(&mut self) -> Option<&'s str> {
+        let tok = self.peek().filter(|t| t.kind == Kind::Name)?;
+        self.type_refs.push((tok.start, tok.end));
+        self.idx += 1;
+        Some(tok.value)
+    }
+
+    fn skip_balanced(&mut self) {
+        let mut depth = 0;
+        while let Some(tok) = self.peek() {
+            self.idx += 1;
+            if tok.kind == Kind::Punctuator {
+                match tok.value {
+                    "(" | "[" | "{" => depth += 1,
+                    ")" | "]" | "}" => depth -= 1,
+                    _ => {}
+                }
+            }
+            if depth <= 0 {
+                return;
+            }
+        }
+    }
+
+    fn document(&mut self) {
+        let mut extend = false;
+        while let Some(tok) = self.peek() {
+            match (tok.kind, tok.value) {
+                (Kind::Name, "extend") => {
+                    extend = true;
+                    self.idx += 1;
+                    continue;
+                }
+                (Kind::Name, kw) if TYPE_KEYWORDS.contains(&kw) => self.type_definition(extend),
+                (Kind::Name, "schema") => {
+                    self.idx += 1;
+                    self.directives();
+                    if self.is_punct("{") {
+                        self.idx += 1;
+                        while self.is_name() {
+                            self.idx += 1;
+                            if self.is_punct(":") {
+                                self.idx += 1;
+                                self.type_name();
+                            }
+                        }
+                        self.idx += 1;
+                    }
+                }
+                (Kind::Name, "directive") => {
+                    // `directive @name`
+                    self.idx += 3;
+                    if self.is_punct("(") {
+                        self.arguments();
+                    }
+                    while !self.is_keyword() && !self.is_string() && self.peek().is_some() {
+                        self.idx += 1;
+                    }
+                }
+                _ => self.idx += 1,
+            }
+            extend = false;
+        }
+    }
+
+    fn type_definition(&mut self, extend: bool) {
+        let keyword = self.toks[self.idx].value;
+        self.idx += 1;
+        let name = match self.type_name() {
+            Some(name) => name,
+            None => return,
+        };
+        if matches!(self.peek(), Some(t) if t.value == "implements") {
+            self.idx += 1;
+            while self.is_punct("&") || (self.is_name() && !self.is_keyword()) {
+                if self.is_punct("&") {
+                    self.idx += 1;
+                } else {
+                    self.type_name();
+                }
+            }
+        }
+        self.directives();
+        if keyword == "union" && self.is_punct("=") {
+            self.idx += 1;
+            if self.is_punct("|") {
+                self.idx += 1;
+            }
+            self.type_name();
+            while self.is_punct("|") {
+                self.idx += 1;
+                self.type_name();
+            }
+        }
+        let mut outline = TypeOutline {
+            extend,
+            keyword,
+            name,
+            header_end: self.prev_end(),
+            block: None,
+            fields: Vec::new(),
+        };
+        if self.is_punct("{") {
+            let open = self.toks[self.idx].start;
+            self.idx += 1;
+            while let Some(tok) = self.peek() {
+                if tok.kind == Kind::Punctuator && tok.value == "}" {
+                    break;
+                }
+                match self.field(keyword == "enum") {
+                    Some(field) => outline.fields.push(field),
+                    None => self.idx += 1,
+                }
+            }
+            if let Some(close) = self.peek() {
+                outline.block = Some((open, close.start));
+                self.idx += 1;
+            }
+        }
+        self.types.push(outline);
+    }
+
+    /// Scans a field definition, an input value or an enum value
+    fn field(&mut self, enum_value: bool) -> Option<FieldOutline<'s>> {
+        let start = self.peek()?.start;
+        if self.is_string() {
+            self.idx += 1;
+        }
+        let name = self.peek().filter(|t| t.kind == Kind::Name)?.value;
+        self.idx += 1;
+        if !enum_value {
+            if self.is_punct("(") {
+                self.arguments();
+            }
+            if self.is_punct(":") {
+                self.idx += 1;
+                self.type_reference();
+            }
+            if self.is_punct("=") {
+                self.idx += 1;
+                self.skip_balanced();
+            }
+        }
+        self.directives();
+        Some(FieldOutline {
+            name,
+            start,
+            end: self.prev_end(),
+        })
+    }
+
+    fn arguments(&mut self) {
+        self.idx += 1;
+        while !self.is_punct(")") && self.peek().is_some() {
+            if self.field(false).is_none() {
+                self.idx += 1;
+            }
+        }
+        self.idx += 1;
+    }
+
+    fn type_reference(&mut self) {
+        while self.is_punct("[") {
+            self.idx += 1;
+        }
+        self.type_name();
+        while self.is_punct("]") || self.is_punct("!") {
+            self.idx += 1;
+        }
+    }
+
+    fn directives(&mut self) {
+        while self.is_punct("@") {
+            self.idx += 2;
+            if self.is_punct("(") {
+                self.skip_balanced();
+            }
+        }
+    }
+}
+
+/// Returns the whitespace the line containing `offset` starts with
+fn line_indent(source: &str, offset: usize) -> &str {
+    let line_start = source[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let line = &source[line_start..];
+    &line[..line.len() - line.trim_start_matches([' ', '\t']).len()]
+}
+
+/// Extends the range of a removed element to whole lines, if the element
+/// occupies them alone
+fn removal_range(source: &str, start: usize, end: usize) -> (usize, usize) {
+    let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+    let rest = &source[end..];
+    let line_end = rest.find('\n').map_or(source.len(), |i| end + i + 1);
+    let tail = source[end..line_end].trim_start_matches([' ', '\t', ',']);
+    let tail_blank = tail.trim().is_empty() || tail.starts_with('#');
+    if source[line_start..start].trim().is_empty() && tail_blank {
+        (line_start, line_end)
+    } else {
+        (start, end)
+    }
+}
+
+/// Applies a structural change to the schema `source`
+///
+/// Only the text affected by the edit is changed, so comments and
+/// formatting elsewhere are preserved. Both the source and the result are
+/// parsed to make sure the edit produces a valid schema.
+pub fn apply_edit(source: &str, edit: SchemaEdit<'_>) -> Result<String, EditError> {
+    parse_schema::<&str>(source)?;
+    let mut scanner = Scanner::new(source);
+    scanner.document();
+    let find_definition = |name: &str| {
+        scanner
+            .types
+            .iter()
+            .find(|t| !t.extend && t.name == name)
+            .ok_or_else(|| EditError::UnknownType(name.to_string()))
+    };
+
+    let mut changes: Vec<(usize, usize, String)> = Vec::new();
+    match edit {
+        SchemaEdit::AddField { type_name, field } => {
+            let def = find_definition(type_name)?;
+            if !["type", "interface", "input"].contains(&def.keyword) {
+                return Err(EditError::NoFields(type_name.to_string()));
+            }
+            match (def.block, def.fields.last()) {
+                (Some((open, close)), Some(_)) if !source[open..close].contains('\n') => {
+                    // Single-line block, put every field on its own line
+                    let indent = line_indent(source, open);
+                    let mut text = String::new();
+                    for existing in &def.fields {
+                        text.push_str(&format!(
+                            "\n{}  {}",
+                            indent,
+                            &source[existing.start..existing.end]
+                        ));
+                    }
+                    text.push_str(&format!("\n{}  {}\n{}", indent, field, indent));
+                    changes.push((open + 1, close, text));
+                }
+                (Some(_), Some(last)) => {
+                    let indent = line_indent(source, last.start);
+                    changes.push((last.end, last.end, format!("\n{}{}", indent, field)));
+                }
+                (Some((open, close)), None) => {
+                    let indent = line_indent(source, open);
+                    let text = format!("\n{}  {}\n{}", indent, field, indent);
+                    changes.push((open + 1, close, text));
+                }
+                (None, _) => {
+                    changes.push((
+                        def.header_end,
+                        def.header_end,
+                        format!(" {{\n  {}\n}}", field),
+                    ));
+                }
+            }
+        }
+        SchemaEdit::RemoveField {
+            type_name,
+            field_name,
+        } => {
+            find_definition(type_name)?;
+            let (def, field) = scanner
+                .types
+                .iter()
+                .filter(|t| t.name == type_name)
+                .find_map(|t| {
+                    t.fields
+                        .iter()
+                        .find(|f| f.name == field_name)
+                        .map(|f| (t, f))
+                })
+                .ok_or_else(|| EditError::UnknownField {
+                    type_name: type_name.to_string(),
+                    field: field_name.to_string(),
+                })?;
+            match def.block {
+                Some((_, close)) if def.fields.len() == 1 => {
+                    changes.push((def.header_end, close + 1, String::new()));
+                }
+                _ => {
+                    let (start, end) = removal_range(source, field.start, field.end);
+                    changes.push((start, end, String::new()));
+                }
+            }
+        }
+        SchemaEdit::AddDirective {
+            type_name,
+            directive,
+        } => {
+            let def = find_definition(type_name)?;
+            changes.push((def.header_end, def.header_end, format!(" {}", directive)));
+        }
+        SchemaEdit::Rename { from, to } => {
+            find_definition(from)?;
+            if scanner.types.iter().any(|t| !t.extend && t.name == to) {
+                return Err(EditError::DuplicateType(to.to_string()));
+            }
+            for &(start, end) in &scanner.type_refs {
+                if &source[start..end] == from {
+                    changes.push((start, end, to.to_string()));
+                }
+            }
+        }
+    }
+
+    changes.sort_by_key(|c| c.0);
+    let mut result = String::with_capacity(source.len());
+    let mut last = 0;
+    for (start, end, text) in changes {
+        result.push_str(&source[last..start]);
+        result.push_str(&text);
+        last = end;
+    }
+    result.push_str(&source[last..]);
+    parse_schema::<&str>(&result).map_err(EditError::InvalidEdit)?;
+    Ok(result)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{apply_edit, EditError, SchemaEdit};
+
+    const SCHEMA: &str = "\
+# People using the system
+type User implements Node @key(fields: \"id\") {
+    id: ID!   # primary key
+    \"\"\"
+    Display name
+    \"\"\"
+    name(format: Format = SHORT): String
+    friends: [User!]
+}
+
+union Search = | User | Post
+
+type Post { author: User }
+extend type User { posts: [Post] }
+";
+
+    fn edit(edit: SchemaEdit<'_>) -> String {
+        apply_edit(SCHEMA, edit).unwrap()
+    }
+
+    #[test]
+    fn add_field() {
+        assert_eq!(
+            edit(SchemaEdit::AddField {
+                type_name: "User",
+                field: "email: String",
+            }),
+            SCHEMA.replace("[User!]\n", "[User!]\n    email: String\n")
+        );
+        assert_eq!(
+            apply_edit(
+                "scalar X\ntype A # comment\n",
+                SchemaEdit::AddField {
+                    type_name: "A",
+                    field: "x: X"
+                }
+            )
+            .unwrap(),
+            "scalar X\ntype A {\n  x: X\n} # comment\n"
+        );
+        assert_eq!(
+            edit(SchemaEdit::AddField {
+                type_name: "Post",
+                field: "title: String",
+            }),
+            SCHEMA.replace(
+                "type Post { author: User }",
+                "type Post {\n  author: User\n  title: String\n}"
+            )
+        );
+        assert_eq!(
+            apply_edit(
+                "  input I { a: Int, b: Int @x }\ndirective @x on INPUT_FIELD_DEFINITION\n",
+                SchemaEdit::AddField {
+                    type_name: "I",
+                    field: "c: Int"
+                }
+            )
+            .unwrap(),
+            "  input I {\n    a: Int\n    b: Int @x\n    c: Int\n  }\n\
+             directive @x on INPUT_FIELD_DEFINITION\n"
+        );
+    }
+
+    #[test]
+    fn remove_field() {
+        assert_eq!(
+            edit(SchemaEdit::RemoveField {
+                type_name: "User",
+                field_name: "id",
+            }),
+            SCHEMA.replace("    id: ID!   # primary key\n", "")
+        );
+        assert_eq!(
+            edit(SchemaEdit::RemoveField {
+                type_name: "User",
+                field_name: "name",
+            }),
+            SCHEMA.replace(
+                "    \"\"\"\n    Display name\n    \"\"\"\n    name(format: Format = SHORT): String\n",
+                ""
+            )
+        );
+        assert_eq!(
+            edit(SchemaEdit::RemoveField {
+                type_name: "Post",
+                field_name: "author",
+            }),
+            SCHEMA.replace("type Post { author: User }", "type Post")
+        );
+    }
+
+    #[test]
+    fn add_directive() {
+        assert_eq!(
+            edit(SchemaEdit::AddDirective {
+                type_name: "Post",
+                directive: "@shareable",
+            }),
+            SCHEMA.replace("type Post {", "type Post @shareable {")
+        );
+    }
+
+    #[test]
+    fn rename() {
+        assert_eq!(
+            edit(SchemaEdit::Rename {
+                from: "User",
+                to: "Account",
+            }),
+            SCHEMA.replace("User", "Account")
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let err = apply_edit(
+            SCHEMA,
+            SchemaEdit::Rename {
+                from: "Nope",
+                to: "X",
+            },
+        );
+        assert!(matches!(err, Err(EditError::UnknownType(_))));
+        let err = apply_edit(
+            SCHEMA,
+            SchemaEdit::Rename {
+                from: "User",
+                to: "Post",
+            },
+        );
+        assert!(matches!(err, Err(EditError::DuplicateType(_))));
+        let err = apply_edit(
+            SCHEMA,
+            SchemaEdit::AddField {
+                type_name: "Search",
+                field: "x: Int",
+            },
+        );
+        assert!(matches!(err, Err(EditError::NoFields(_))));
+        let err = apply_edit(
+            SCHEMA,
+            SchemaEdit::AddField {
+                type_name: "Post",
+                field: "x: ",
+            },
+        );
+        assert!(matches!(err, Err(EditError::InvalidEdit(_))));
+    }
+}
@@ -0,0 +1,185 @@
+//! Merging type extensions into the types they extend
+//!
+use std::collections::HashMap;
+
+use thiserror::Error;
+
+use crate::common::Text;
+use crate::position::Pos;
+use crate::schema::ast::*;
+
+/// Error applying type extensions with [`apply_extensions`]
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ExtensionError {
+    #[error("{position}: extended type {name:?} is not defined")]
+    UndefinedType { name: String, position: Pos },
+    #[error("{position}: extension of {name:?} doesn't match the kind of the type")]
+    KindMismatch { name: String, position: Pos },
+}
+
+fn kind_mismatch<'a, T: Text<'a>>(ext: &TypeExtension<'a, T>) -> ExtensionError {
+    ExtensionError::KindMismatch {
+        name: ext.name().as_ref().to_string(),
+        position: ext.position(),
+    }
+}
+
+fn extend<'a, T>(
+    def: &mut TypeDefinition<'a, T>,
+    ext: TypeExtension<'a, T>,
+) -> Result<(), ExtensionError>
+where
+    T: Text<'a>,
+{
+    match (def, ext) {
+        (TypeDefinition::Scalar(d), TypeExtension::Scalar(e)) => {
+            d.directives.extend(e.directives);
+        }
+        (TypeDefinition::Object(d), TypeExtension::Object(e)) => {
+            d.implements_interfaces.extend(e.implements_interfaces);
+            d.directives.extend(e.directives);
+            d.fields.extend(e.fields);
+        }
+        (TypeDefinition::Interface(d), TypeExtension::Interface(e)) => {
+            d.implements_interfaces.extend(e.implements_interfaces);
+            d.directives.extend(e.directives);
+            d.fields.extend(e.fields);
+        }
+        (TypeDefinition::Union(d), TypeExtension::Union(e)) => {
+            d.directives.extend(e.directives);
+            d.types.extend(e.types);
+        }
+        (TypeDefinition::Enum(d), TypeExtension::Enum(e)) => {
+            d.directives.extend(e.directives);
+            d.values.extend(e.values);
+        }
+        (TypeDefinition::InputObject(d), TypeExtension::InputObject(e)) => {
+            d.directives.extend(e.directives);
+            d.fields.extend(e.fields);
+        }
+        (_, ext) => return Err(kind_mismatch(&ext)),
+    }
+    Ok(())
+}
+
+/// Merges every type extension into the definition of the type it extends
+///
+/// Directives, interfaces, fields, union members and enum values of the
+/// extensions are appended to the ones of the type, in document order, and
+/// the extensions are removed. Nothing is deduplicated, so a field defined
+/// both by a type and its extension appears twice. Extensions may come
+/// before the type they extend. Schema extensions are left unchanged.
+///
+/// Fails if an extension refers to a type that isn't defined in `doc`, or
+/// to a type of a different kind, e.g. `extend enum` of an object type.
+pub fn apply_extensions<'a, T>(doc: Document<'a, T>) -> Result<Document<'a, T>, ExtensionError>
+where
+    T: Text<'a>,
+{
+    let mut definitions = Vec::with_capacity(doc.definitions.len());
+    let mut extensions = Vec::new();
+    for def in doc.definitions {
+        match def {
+            Definition::TypeExtension(ext) => extensions.push(ext),
+            def => definitions.push(def),
+        }
+    }
+    let mut positions = HashMap::new();
+    for (idx, def) in definitions.iter().enumerate() {
+        if let Definition::TypeDefinition(t) = def {
+            positions
+                .entry(t.name().as_ref().to_string())
+                .or_insert(idx);
+        }
+    }
+
+    for ext in extensions {
+        let idx = match positions.get(ext.name().as_ref()) {
+            Some(&idx) => idx,
+            None => {
+                return Err(ExtensionError::UndefinedType {
+                    name: ext.name().as_ref().to_string(),
+                    position: ext.position(),
+                })
+            }
+        };
+        match &mut definitions[idx] {
+            Definition::TypeDefinition(def) => extend(def, ext)?,
+            _ => unreachable!(),
+        }
+    }
+    Ok(Document { definitions })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{apply_extensions, ExtensionError};
+    use crate::position::Pos;
+    use crate::schema::parse_schema;
+
+    #[test]
+    fn merge() {
+        let doc = parse_schema::<&str>(
+            "extend type Query @tag { b: Int }
+             type Query implements Node { a: Int }
+             extend type Query implements Named { a: Int }
+             enum Color { RED }
+             extend enum Color { GREEN }
+             union Item = A
+             extend union Item @tag = B
+             extend input Filter { b: Int }
+             input Filter { a: Int }
+             scalar Date
+             extend scalar Date @tag
+             extend schema @tag",
+        )
+        .unwrap();
+        let merged = apply_extensions(doc).unwrap();
+        assert_eq!(
+            merged.to_string(),
+            "type Query implements Node & Named @tag {
+  a: Int
+  b: Int
+  a: Int
+}
+
+enum Color {
+  RED
+  GREEN
+}
+
+union Item @tag = A | B
+
+input Filter {
+  a: Int
+  b: Int
+}
+
+scalar Date @tag
+
+extend schema @tag
+"
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let doc = parse_schema::<&str>("type A { a: Int }\nextend type B { b: Int }").unwrap();
+        assert_eq!(
+            apply_extensions(doc),
+            Err(ExtensionError::UndefinedType {
+                name: "B".into(),
+                position: Pos {
+                    line: 2,
+                    column: 8,
+                    offset: 25,
+                },
+            })
+        );
+        let doc = parse_schema::<&str>("type A { a: Int }\nextend enum A { B }").unwrap();
+        assert!(matches!(
+            apply_extensions(doc),
+            Err(ExtensionError::KindMismatch { .. })
+        ));
+    }
+}
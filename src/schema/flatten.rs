@@ -0,0 +1,278 @@
+//! Lowering interfaces into the types implementing them
+//!
+use std::collections::{HashMap, HashSet};
+
+use crate::common::Text;
+use crate::schema::ast::*;
+
+struct Interface<'a, T: Text<'a>> {
+    fields: Vec<Field<'a, T>>,
+    implements: Vec<T>,
+}
+
+/// Returns the interfaces `declared` by a type, followed by interfaces they
+/// implement, without duplicates
+fn interface_closure<'a, T>(
+    declared: &[T],
+    interfaces: &HashMap<String, Interface<'a, T>>,
+) -> Vec<String>
+where
+    T: Text<'a>,
+{
+    let mut result: Vec<String> = Vec::new();
+    let mut queue: Vec<String> = declared.iter().map(|i| i.as_ref().to_string()).collect();
+    while !queue.is_empty() {
+        let name = queue.remove(0);
+        if result.contains(&name) {
+            continue;
+        }
+        if let Some(iface) = interfaces.get(&name) {
+            queue.extend(iface.implements.iter().map(|i| i.as_ref().to_string()));
+            result.push(name);
+        }
+    }
+    result
+}
+
+/// Appends the names of the types `fields` return to `result`
+fn return_types<'a, T>(result: &mut Vec<String>, fields: &[Field<'a, T>])
+where
+    T: Text<'a>,
+{
+    result.extend(fields.iter().map(|f| f.field_type.base_name().as_ref().to_string()));
+}
+
+/// Replaces interfaces with the fields and types implementing them
+///
+/// Fields of every interface an object implements, directly or through
+/// other interfaces, are copied into the object unless it already has a
+/// field with the same name. Interfaces are then removed from the
+/// `implements` lists, and each interface definition is replaced by a union
+/// with the same name and description whose members are the implementing
+/// objects, so fields returning the interface stay valid. Interface
+/// directives are dropped, since they may not be allowed on unions.
+///
+/// Interface extensions are removed after their fields are copied. An
+/// interface that no object implements has no members for a union, so it
+/// is removed if nothing refers to it. If fields still return it, it's kept
+/// as an interface instead, with the fields of its extensions and without
+/// the interfaces it implements, which may have become unions.
+pub fn flatten_interfaces<'a, T>(doc: &mut Document<'a, T>)
+where
+    T: Text<'a>,
+{
+    let mut interfaces: HashMap<String, Interface<'a, T>> = HashMap::new();
+    for def in &doc.definitions {
+        let (name, fields, implements) = match def {
+            Definition::TypeDefinition(TypeDefinition::Interface(i)) => {
+                (&i.name, &i.fields, &i.implements_interfaces)
+            }
+            Definition::TypeExtension(TypeExtension::Interface(i)) => {
+                (&i.name, &i.fields, &i.implements_interfaces)
+            }
+            _ => continue,
+        };
+        let entry = interfaces
+            .entry(name.as_ref().to_string())
+            .or_insert_with(|| Interface {
+                fields: Vec::new(),
+                implements: Vec::new(),
+            });
+        entry.fields.extend(fields.iter().cloned());
+        entry.implements.extend(implements.iter().cloned());
+    }
+    if interfaces.is_empty() {
+        return;
+    }
+
+    // Objects in source order, with their declared interfaces and fields
+    let mut objects: Vec<(T, Vec<T>, HashSet<String>)> = Vec::new();
+    for def in &doc.definitions {
+        let (name, implements, fields) = match def {
+            Definition::TypeDefinition(TypeDefinition::Object(o)) => {
+                (&o.name, &o.implements_interfaces, &o.fields)
+            }
+            Definition::TypeExtension(TypeExtension::Object(o)) => {
+                (&o.name, &o.implements_interfaces, &o.fields)
+            }
+            _ => continue,
+        };
+        let idx = match objects.iter().position(|(n, _, _)| n == name) {
+            Some(idx) => idx,
+            None => {
+                objects.push((name.clone(), Vec::new(), HashSet::new()));
+                objects.len() - 1
+            }
+        };
+        objects[idx].1.extend(implements.iter().cloned());
+        objects[idx]
+            .2
+            .extend(fields.iter().map(|f| f.name.as_ref().to_string()));
+    }
+
+    let mut new_fields: HashMap<String, Vec<Field<'a, T>>> = HashMap::new();
+    let mut implementers: HashMap<String, Vec<T>> = HashMap::new();
+    for (name, declared, existing) in &mut objects {
+        let mut added = Vec::new();
+        for iface_name in interface_closure(declared, &interfaces) {
+            let iface = &interfaces[&iface_name];
+            for field in &iface.fields {
+                if existing.insert(field.name.as_ref().to_string()) {
+                    added.push(field.clone());
+                }
+            }
+            implementers
+                .entry(iface_name)
+                .or_default()
+                .push(name.clone());
+        }
+        new_fields.insert(name.as_ref().to_string(), added);
+    }
+
+    // Names of the types fields return, to find interfaces without
+    // implementations which are still in use
+    let mut returned: Vec<String> = Vec::new();
+    for def in &doc.definitions {
+        if let Definition::TypeDefinition(TypeDefinition::Object(o)) = def {
+            return_types(&mut returned, &o.fields);
+        }
+        if let Definition::TypeExtension(TypeExtension::Object(o)) = def {
+            return_types(&mut returned, &o.fields);
+        }
+    }
+    for fields in new_fields.values() {
+        return_types(&mut returned, fields);
+    }
+    let mut kept = HashSet::new();
+    while let Some(name) = returned.pop() {
+        if implementers.contains_key(&name) || !interfaces.contains_key(&name) {
+            continue;
+        }
+        if kept.insert(name.clone()) {
+            return_types(&mut returned, &interfaces[&name].fields);
+        }
+    }
+
+    let definitions = std::mem::take(&mut doc.definitions);
+    for def in definitions {
+        match def {
+            Definition::TypeDefinition(TypeDefinition::Interface(mut i)) => {
+                if kept.contains(i.name.as_ref()) {
+                    i.fields = interfaces[i.name.as_ref()].fields.clone();
+                    i.implements_interfaces.clear();
+                    doc.definitions
+                        .push(Definition::TypeDefinition(TypeDefinition::Interface(i)));
+                } else if let Some(types) = implementers.remove(i.name.as_ref()) {
+                    doc.definitions
+                        .push(Definition::TypeDefinition(TypeDefinition::Union(
+                            UnionType {
+                                position: i.position,
+                                end: i.end,
+                                description: i.description,
+                                name: i.name,
+                                directives: Vec::new(),
+                                types,
+                            },
+                        )));
+                }
+            }
+            Definition::TypeExtension(TypeExtension::Interface(_)) => {}
+            Definition::TypeDefinition(TypeDefinition::Object(mut o)) => {
+                o.implements_interfaces
+                    .retain(|i| !interfaces.contains_key(i.as_ref()));
+                if let Some(fields) = new_fields.remove(o.name.as_ref()) {
+                    o.fields.extend(fields);
+                }
+                doc.definitions
+                    .push(Definition::TypeDefinition(TypeDefinition::Object(o)));
+            }
+            Definition::TypeExtension(TypeExtension::Object(mut o)) => {
+                o.implements_interfaces
+                    .retain(|i| !interfaces.contains_key(i.as_ref()));
+                doc.definitions
+                    .push(Definition::TypeExtension(TypeExtension::Object(o)));
+            }
+            def => doc.definitions.push(def),
+        }
+    }
+
+    // Objects defined only by extensions get the fields in the first one
+    for def in &mut doc.definitions {
+        if let Definition::TypeExtension(TypeExtension::Object(o)) = def {
+            if let Some(fields) = new_fields.remove(o.name.as_ref()) {
+                o.fields.extend(fields);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::flatten_interfaces;
+    use crate::schema::parse_schema;
+
+    #[test]
+    fn flatten() {
+        let mut doc = parse_schema::<&str>(
+            "type Query { node: Node items: [Named] }
+             interface Node { id: ID! }
+             \"Has a name\"
+             interface Named implements Node @tag { id: ID! name: String }
+             extend interface Named { title(full: Boolean): String }
+             type User implements Named & Node @key { name: String! age: Int }
+             type Post implements Node { id: ID! }
+             extend type Tag implements Named
+             interface Unused { a: Int }
+             type Feed { latest: RemovedInterface }
+             interface RemovedInterface implements Node { id: ID! next: [Other!] }
+             extend interface RemovedInterface { title: String }
+             interface Other { a: Int }",
+        )
+        .unwrap();
+        flatten_interfaces(&mut doc);
+        assert_eq!(
+            doc.to_string(),
+            r#"type Query {
+  node: Node
+  items: [Named]
+}
+
+union Node = User | Post | Tag
+
+"Has a name"
+union Named = User | Tag
+
+type User @key {
+  name: String!
+  age: Int
+  id: ID!
+  title(full: Boolean): String
+}
+
+type Post {
+  id: ID!
+}
+
+extend type Tag {
+  id: ID!
+  name: String
+  title(full: Boolean): String
+}
+
+type Feed {
+  latest: RemovedInterface
+}
+
+interface RemovedInterface {
+  id: ID!
+  next: [Other!]
+  title: String
+}
+
+interface Other {
+  a: Int
+}
+"#
+        );
+    }
+}
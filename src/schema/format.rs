@@ -1,4 +1,7 @@
-use std::fmt;
+use alloc::string::String;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::io;
 
 use crate::common::Text;
 use crate::format::{format_directives, Displayable, Formatter, Style};
@@ -17,6 +20,29 @@ where
     }
 }
 
+/// Formats a schema directly into a writer
+///
+/// Definitions are written one by one as they are formatted, so unlike
+/// [`Document::format`] the whole output is never held in memory at once.
+/// The writer is flushed at the end.
+#[cfg(feature = "std")]
+pub fn format_schema_streaming<'a, T, W>(
+    doc: &Document<'a, T>,
+    w: &mut W,
+    style: &Style,
+) -> io::Result<()>
+where
+    T: Text<'a>,
+    W: io::Write,
+{
+    let mut formatter = Formatter::new(style);
+    for item in &doc.definitions {
+        item.display(&mut formatter);
+        formatter.write_to(w)?;
+    }
+    w.flush()
+}
+
 fn to_string<T: Displayable>(v: &T) -> String {
     let style = Style::default();
     let mut formatter = Formatter::new(&style);
@@ -26,9 +52,39 @@ fn to_string<T: Displayable>(v: &T) -> String {
 
 fn description(description: &Option<String>, f: &mut Formatter) {
     if let Some(ref descr) = *description {
-        f.indent();
-        f.write_quoted(descr.as_ref());
-        f.endline();
+        if f.descriptions() {
+            f.indent();
+            f.write_quoted(descr.as_ref());
+            f.endline();
+        }
+    }
+}
+
+/// Writes a description on the same line as the item it describes
+///
+/// Block strings span several lines, so the item starts on a new line
+/// after them, at the indentation of the description.
+fn inline_description(description: &Option<String>, f: &mut Formatter) {
+    if let Some(ref descr) = *description {
+        if f.descriptions() {
+            f.write_quoted(descr.as_ref());
+            if f.is_block_string(descr) {
+                f.endline();
+                f.indent();
+            } else {
+                f.write(" ");
+            }
+        }
+    }
+}
+
+fn is_builtin<'a, T: Text<'a>>(def: &Definition<'a, T>) -> bool {
+    match def {
+        Definition::TypeDefinition(TypeDefinition::Scalar(s)) => {
+            BUILTIN_SCALARS.contains(&s.name.as_ref())
+        }
+        Definition::DirectiveDefinition(d) => BUILTIN_DIRECTIVES.contains(&d.name.as_ref()),
+        _ => false,
     }
 }
 
@@ -48,6 +104,9 @@ where
     T: Text<'a>,
 {
     fn display(&self, f: &mut Formatter) {
+        if !f.builtin_definitions() && is_builtin(self) {
+            return;
+        }
         f.margin();
         match *self {
             Definition::SchemaDefinition(ref s) => s.display(f),
@@ -59,6 +118,19 @@ where
     }
 }
 
+fn format_root_operations<T>(operations: &[(OperationType, &T)], f: &mut Formatter)
+where
+    T: AsRef<str>,
+{
+    for (kind, name) in operations {
+        f.indent();
+        f.write(kind.as_str());
+        f.write(": ");
+        f.write(name.as_ref());
+        f.endline();
+    }
+}
+
 impl<'a, T> Displayable for SchemaDefinition<'a, T>
 where
     T: Text<'a>,
@@ -69,24 +141,7 @@ where
         format_directives(&self.directives, f);
         f.write(" ");
         f.start_block();
-        if let Some(ref q) = self.query {
-            f.indent();
-            f.write("query: ");
-            f.write(q.as_ref());
-            f.endline();
-        }
-        if let Some(ref m) = self.mutation {
-            f.indent();
-            f.write("mutation: ");
-            f.write(m.as_ref());
-            f.endline();
-        }
-        if let Some(ref s) = self.subscription {
-            f.indent();
-            f.write("subscription: ");
-            f.write(s.as_ref());
-            f.endline();
-        }
+        format_root_operations(&self.root_operations(), f);
         f.end_block();
     }
 }
@@ -99,27 +154,15 @@ where
         f.indent();
         f.write("extend schema");
         format_directives(&self.directives, f);
-        f.write(" ");
-        f.start_block();
-        if let Some(ref q) = self.query {
-            f.indent();
-            f.write("query: ");
-            f.write(q.as_ref());
-            f.endline();
-        }
-        if let Some(ref m) = self.mutation {
-            f.indent();
-            f.write("mutation: ");
-            f.write(m.as_ref());
-            f.endline();
-        }
-        if let Some(ref s) = self.subscription {
-            f.indent();
-            f.write("subscription: ");
-            f.write(s.as_ref());
+        let operations = self.root_operations();
+        if !operations.is_empty() {
+            f.write(" ");
+            f.start_block();
+            format_root_operations(&operations, f);
+            f.end_block();
+        } else {
             f.endline();
         }
-        f.end_block();
     }
 }
 
@@ -230,10 +273,7 @@ where
     T: Text<'a>,
 {
     fn display(&self, f: &mut Formatter) {
-        if let Some(ref descr) = self.description {
-            f.write_quoted(descr.as_ref());
-            f.write(" ");
-        }
+        inline_description(&self.description, f);
         f.write(self.name.as_ref());
         f.write(": ");
         self.value_type.display(f);
@@ -249,15 +289,7 @@ fn format_arguments<'a, T>(arguments: &[InputValue<'a, T>], f: &mut Formatter)
 where
     T: Text<'a>,
 {
-    if !arguments.is_empty() {
-        f.write("(");
-        arguments[0].display(f);
-        for arg in &arguments[1..] {
-            f.write(", ");
-            arg.display(f);
-        }
-        f.write(")");
-    }
+    f.write_list(arguments, |arg, f| arg.display(f));
 }
 
 impl<'a, T> Displayable for Field<'a, T>
@@ -377,10 +409,7 @@ where
             f.start_block();
             for val in &self.values {
                 f.indent();
-                if let Some(ref descr) = val.description {
-                    f.write_quoted(descr.as_ref());
-                    f.write(" ");
-                }
+                inline_description(&val.description, f);
                 f.write(val.name.as_ref());
                 format_directives(&val.directives, f);
                 f.endline();
@@ -406,10 +435,7 @@ where
             f.start_block();
             for val in &self.values {
                 f.indent();
-                if let Some(ref descr) = val.description {
-                    f.write_quoted(descr.as_ref());
-                    f.write(" ");
-                }
+                inline_description(&val.description, f);
                 f.write(val.name.as_ref());
                 format_directives(&val.directives, f);
                 f.endline();
@@ -516,6 +542,7 @@ impl_display!(
     Document,
     Definition,
     SchemaDefinition,
+    SchemaExtension,
     TypeDefinition,
     TypeExtension,
     ScalarType,
@@ -1,15 +1,68 @@
+use std::io::Read;
+
 use combine::easy::{Error, Errors};
 use combine::error::StreamError;
 use combine::sep_by1;
-use combine::{choice, eof, many, many1, optional, position};
+use combine::{attempt, choice, eof, many, many1, optional, position};
 use combine::{parser, Parser, StdParseResult};
 
 use crate::common::{default_value, directives, parse_type, string, Text};
-use crate::helpers::{ident, kind, name, punct};
+use crate::helpers::{end_position, ident, kind, name, punct};
+use crate::options::{ParseOptions, ParseWarning};
+use crate::position::{position_at, Pos};
 use crate::schema::ast::*;
 use crate::schema::error::ParseError;
 use crate::tokenizer::{Kind as T, Token, TokenStream};
 
+/// Root operation types collected from a `schema` block
+struct RootOperations<S> {
+    query: Option<S>,
+    mutation: Option<S>,
+    subscription: Option<S>,
+    order: Vec<OperationType>,
+}
+
+fn root_operations<'a, S>(
+    position: Pos,
+    operations: Vec<(Token<'a>, S)>,
+) -> Result<RootOperations<S>, Errors<Token<'a>, Token<'a>, Pos>> {
+    let mut ops = RootOperations {
+        query: None,
+        mutation: None,
+        subscription: None,
+        order: Vec::with_capacity(operations.len()),
+    };
+    let mut err = Errors::empty(position);
+    for (oper, type_name) in operations {
+        let (kind, slot) = match oper.value {
+            "query" => (OperationType::Query, &mut ops.query),
+            "mutation" => (OperationType::Mutation, &mut ops.mutation),
+            "subscription" => (OperationType::Subscription, &mut ops.subscription),
+            _ => {
+                err.add_error(Error::unexpected_token(oper));
+                err.add_error(Error::expected_static_message("query"));
+                err.add_error(Error::expected_static_message("mutation"));
+                err.add_error(Error::expected_static_message("subscription"));
+                continue;
+            }
+        };
+        if slot.is_some() {
+            err.add_error(Error::unexpected_static_message(match kind {
+                OperationType::Query => "duplicate `query` operation",
+                OperationType::Mutation => "duplicate `mutation` operation",
+                OperationType::Subscription => "duplicate `subscription` operation",
+            }));
+            continue;
+        }
+        *slot = Some(type_name);
+        ops.order.push(kind);
+    }
+    if !err.errors.is_empty() {
+        return Err(err);
+    }
+    Ok(ops)
+}
+
 pub fn schema<'a, S>(
     input: &mut TokenStream<'a>,
 ) -> StdParseResult<SchemaDefinition<'a, S>, TokenStream<'a>>
@@ -25,53 +78,14 @@ where
     )
         .flat_map(
             |(position, directives, operations): (_, _, Vec<(Token, _)>)| {
-                let mut query = None;
-                let mut mutation = None;
-                let mut subscription = None;
-                let mut err = Errors::empty(position);
-                for (oper, type_name) in operations {
-                    match oper.value {
-                        "query" if query.is_some() => {
-                            err.add_error(Error::unexpected_static_message(
-                                "duplicate `query` operation",
-                            ));
-                        }
-                        "query" => {
-                            query = Some(type_name);
-                        }
-                        "mutation" if mutation.is_some() => {
-                            err.add_error(Error::unexpected_static_message(
-                                "duplicate `mutation` operation",
-                            ));
-                        }
-                        "mutation" => {
-                            mutation = Some(type_name);
-                        }
-                        "subscription" if subscription.is_some() => {
-                            err.add_error(Error::unexpected_static_message(
-                                "duplicate `subscription` operation",
-                            ));
-                        }
-                        "subscription" => {
-                            subscription = Some(type_name);
-                        }
-                        _ => {
-                            err.add_error(Error::unexpected_token(oper));
-                            err.add_error(Error::expected_static_message("query"));
-                            err.add_error(Error::expected_static_message("mutation"));
-                            err.add_error(Error::expected_static_message("subscription"));
-                        }
-                    }
-                }
-                if !err.errors.is_empty() {
-                    return Err(err);
-                }
+                let ops = root_operations(position, operations)?;
                 Ok(SchemaDefinition {
                     position,
                     directives,
-                    query,
-                    mutation,
-                    subscription,
+                    query: ops.query,
+                    mutation: ops.mutation,
+                    subscription: ops.subscription,
+                    operation_order: ops.order,
                 })
             },
         )
@@ -86,7 +100,9 @@ where
     S: Text<'a>,
 {
     (
-        position().skip((ident("extend"), ident("schema"))),
+        // `extend` is shared with type extensions, so we need to backtrack
+        // if it's not followed by `schema`
+        attempt(position().skip((ident("extend"), ident("schema")))),
         parser(directives),
         optional(
             punct("{")
@@ -96,55 +112,14 @@ where
     )
         .flat_map(
             |(position, directives, operations): (_, _, Option<Vec<(Token, _)>>)| {
-                let mut query = None;
-                let mut mutation = None;
-                let mut subscription = None;
-                let mut err = Errors::empty(position);
-                if let Some(operations) = operations {
-                    for (oper, type_name) in operations {
-                        match oper.value {
-                            "query" if query.is_some() => {
-                                err.add_error(Error::unexpected_static_message(
-                                    "duplicate `query` operation",
-                                ));
-                            }
-                            "query" => {
-                                query = Some(type_name);
-                            }
-                            "mutation" if mutation.is_some() => {
-                                err.add_error(Error::unexpected_static_message(
-                                    "duplicate `mutation` operation",
-                                ));
-                            }
-                            "mutation" => {
-                                mutation = Some(type_name);
-                            }
-                            "subscription" if subscription.is_some() => {
-                                err.add_error(Error::unexpected_static_message(
-                                    "duplicate `subscription` operation",
-                                ));
-                            }
-                            "subscription" => {
-                                subscription = Some(type_name);
-                            }
-                            _ => {
-                                err.add_error(Error::unexpected_token(oper));
-                                err.add_error(Error::expected_static_message("query"));
-                                err.add_error(Error::expected_static_message("mutation"));
-                                err.add_error(Error::expected_static_message("subscription"));
-                            }
-                        }
-                    }
-                }
-                if !err.errors.is_empty() {
-                    return Err(err);
-                }
+                let ops = root_operations(position, operations.unwrap_or_default())?;
                 Ok(SchemaExtension {
                     position,
                     directives,
-                    query,
-                    mutation,
-                    subscription,
+                    query: ops.query,
+                    mutation: ops.mutation,
+                    subscription: ops.subscription,
+                    operation_order: ops.order,
                 })
             },
         )
@@ -162,9 +137,11 @@ where
         position(),
         ident("scalar").with(name::<'a, T>()),
         parser(directives),
+        parser(end_position),
     )
-        .map(|(position, name, directives)| ScalarType {
+        .map(|(position, name, directives, end)| ScalarType {
             position,
+            end,
             description: None,
             name,
             directives,
@@ -232,15 +209,19 @@ where
         punct(":").with(parser(parse_type)),
         optional(punct("=").with(parser(default_value))),
         parser(directives),
+        parser(end_position),
     )
         .map(
-            |(position, description, name, value_type, default_value, directives)| InputValue {
-                position,
-                description,
-                name,
-                value_type,
-                default_value,
-                directives,
+            |(position, description, name, value_type, default_value, directives, end)| {
+                InputValue {
+                    position,
+                    end,
+                    description,
+                    name,
+                    value_type,
+                    default_value,
+                    directives,
+                }
             },
         )
         .parse_stream(input)
@@ -270,10 +251,12 @@ where
         parser(arguments_definition),
         punct(":").with(parser(parse_type)),
         parser(directives),
+        parser(end_position),
     )
         .map(
-            |(position, description, name, arguments, field_type, directives)| Field {
+            |(position, description, name, arguments, field_type, directives, end)| Field {
                 position,
+                end,
                 description,
                 name,
                 arguments,
@@ -309,10 +292,12 @@ where
         parser(implements_interfaces::<S>),
         parser(directives),
         parser(fields),
+        parser(end_position),
     )
-        .map(|(position, name, interfaces, directives, fields)| {
+        .map(|(position, name, interfaces, directives, fields, end)| {
             ObjectType {
                 position,
+                end,
                 name,
                 directives,
                 fields,
@@ -370,10 +355,12 @@ where
         parser(implements_interfaces::<T>),
         parser(directives),
         parser(fields),
+        parser(end_position),
     )
-        .map(|(position, name, interfaces, directives, fields)| {
+        .map(|(position, name, interfaces, directives, fields, end)| {
             InterfaceType {
                 position,
+                end,
                 name,
                 implements_interfaces: interfaces,
                 directives,
@@ -440,10 +427,12 @@ where
         ident("union").with(name::<'a, T>()),
         parser(directives),
         optional(punct("=").with(parser(union_members::<T>))),
+        parser(end_position),
     )
-        .map(|(position, name, directives, types)| {
+        .map(|(position, name, directives, types, end)| {
             UnionType {
                 position,
+                end,
                 name,
                 directives,
                 types: types.unwrap_or_else(Vec::new),
@@ -523,10 +512,12 @@ where
         ident("enum").with(name::<'a, T>()),
         parser(directives),
         optional(parser(enum_values)),
+        parser(end_position),
     )
-        .map(|(position, name, directives, values)| {
+        .map(|(position, name, directives, values, end)| {
             EnumType {
                 position,
+                end,
                 name,
                 directives,
                 values: values.unwrap_or_else(Vec::new),
@@ -592,10 +583,12 @@ where
         ident("input").with(name::<'a, T>()),
         parser(directives),
         parser(input_fields),
+        parser(end_position),
     )
-        .map(|(position, name, directives, fields)| {
+        .map(|(position, name, directives, fields, end)| {
             InputObjectType {
                 position,
+                end,
                 name,
                 directives,
                 fields,
@@ -764,7 +757,30 @@ pub fn parse_schema<'a, T>(s: &'a str) -> Result<Document<'a, T>, ParseError>
 where
     T: Text<'a>,
 {
-    let mut tokens = TokenStream::new(s);
+    parse_schema_with_options(s, &ParseOptions::default())
+}
+
+/// Parses a piece of schema language with the given options
+pub fn parse_schema_with_options<'a, T>(
+    s: &'a str,
+    options: &ParseOptions,
+) -> Result<Document<'a, T>, ParseError>
+where
+    T: Text<'a>,
+{
+    parse_schema_with_warnings(s, options).map(|(doc, _)| doc)
+}
+
+/// Parses a schema with the given options, returning warnings about
+/// input accepted only because of lenient options
+pub fn parse_schema_with_warnings<'a, T>(
+    s: &'a str,
+    options: &ParseOptions,
+) -> Result<(Document<'a, T>, Vec<ParseWarning>), ParseError>
+where
+    T: Text<'a>,
+{
+    let mut tokens = TokenStream::with_options(s, options);
     let (doc, _) = many1(parser(definition))
         .map(|d| Document { definitions: d })
         .skip(eof())
@@ -772,16 +788,41 @@ where
         .into_result()
         .map_err(|e| e.into_inner().error)?;
 
-    Ok(doc)
+    Ok((doc, tokens.take_warnings()))
+}
+
+/// Reads a schema from `reader` and parses it
+///
+/// The parser works on a `&str`, so the whole stream is read into memory
+/// first and the document is parsed once the reader is exhausted. A byte
+/// order mark at the start is skipped, like anywhere else in a document, so
+/// byte offsets of positions are counted from the start of the stream.
+/// Since the input isn't kept around, the document holds owned strings.
+/// I/O errors and invalid UTF-8 are reported as a `ParseError`.
+pub fn parse_schema_from_reader<R: Read>(
+    mut reader: R,
+) -> Result<Document<'static, String>, ParseError> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes).map_err(|e| {
+        ParseError::new(format!("error reading input: {}", e), Pos::default())
+    })?;
+    let source = String::from_utf8(bytes).map_err(|e| {
+        let valid = e.utf8_error().valid_up_to();
+        let source = std::str::from_utf8(&e.as_bytes()[..valid]).unwrap();
+        ParseError::new(
+            format!("invalid UTF-8 at byte {}", valid),
+            position_at(source, valid),
+        )
+    })?;
+    Ok(parse_schema::<String>(&source)?.into_static())
 }
 
 #[cfg(test)]
 mod test {
     use super::parse_schema;
-    use crate::position::Pos;
     use crate::schema::grammar::*;
 
-    fn ast(s: &str) -> Document<String> {
+    fn ast(s: &str) -> Document<'_, String> {
         parse_schema::<String>(s).unwrap().to_owned()
     }
 
@@ -791,13 +832,424 @@ mod test {
             ast("schema { query: Query }"),
             Document {
                 definitions: vec![Definition::SchemaDefinition(SchemaDefinition {
-                    position: Pos { line: 1, column: 1 },
+                    position: Pos { line: 1, column: 1, offset: 0 },
                     directives: vec![],
                     query: Some("Query".into()),
                     mutation: None,
                     subscription: None,
+                    operation_order: vec![OperationType::Query],
                 })],
             }
         );
     }
+
+    #[test]
+    fn operation_order() {
+        let doc = ast("schema { subscription: S query: Q }");
+        let schema = doc.definitions[0].clone().into_schema_definition().unwrap();
+        assert_eq!(
+            schema.operation_order,
+            vec![OperationType::Subscription, OperationType::Query]
+        );
+        assert_eq!(
+            schema.root_operations(),
+            vec![
+                (OperationType::Subscription, &"S".to_string()),
+                (OperationType::Query, &"Q".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn duplicate_operation() {
+        let err = parse_schema::<String>("schema { query: Q query: R }").unwrap_err();
+        assert!(err.to_string().contains("duplicate `query` operation"));
+    }
+
+    #[test]
+    fn schema_directives() {
+        let doc = parse_schema::<&str>(
+            "extend schema @link(url: \"b\")
+             schema @core { query: Q }
+             extend schema @tag { mutation: M }",
+        )
+        .unwrap();
+        let names: Vec<&str> = doc.schema_directives().iter().map(|d| d.name).collect();
+        assert_eq!(names, vec!["core", "link", "tag"]);
+    }
+
+    #[test]
+    fn sort_definitions_by() {
+        let mut doc = parse_schema::<&str>(
+            "type D @category(name: \"b\") { x: Int }
+             scalar C
+             type B @category(name: \"a\") { x: Int }
+             type A @category(name: \"b\") { x: Int }",
+        )
+        .unwrap();
+        doc.sort_definitions_by(|def| match def {
+            Definition::TypeDefinition(TypeDefinition::Object(o)) => {
+                let category = o
+                    .directives
+                    .iter()
+                    .find(|d| d.name == "category")
+                    .and_then(|d| d.arguments[0].1.as_string())
+                    .map(String::from);
+                (category, o.name)
+            }
+            Definition::TypeDefinition(t) => (None, *t.name()),
+            _ => (None, ""),
+        });
+        let names: Vec<&str> = doc
+            .definitions
+            .iter()
+            .map(|def| match def {
+                Definition::TypeDefinition(t) => *t.name(),
+                _ => unreachable!(),
+            })
+            .collect();
+        assert_eq!(names, vec!["C", "B", "A", "D"]);
+    }
+
+    #[test]
+    fn find_type() {
+        let doc = parse_schema::<&str>(
+            "extend type A @a type A { x: Int } type B { y: Int } extend type A { z: Int }",
+        )
+        .unwrap();
+        let view = doc.find_type("A").unwrap();
+        assert_eq!(view.definition.map(|d| *d.name()), Some("A"));
+        assert_eq!(view.extensions.len(), 2);
+        assert!(doc.find_type("B").unwrap().extensions.is_empty());
+        assert_eq!(doc.find_type("C"), None);
+    }
+
+    #[test]
+    fn all_interfaces() {
+        let doc = parse_schema::<&str>(
+            "type User implements Named { id: ID! }
+             extend type User implements Node & Named
+             interface Named implements Node & Entity { id: ID! }
+             interface Entity implements Resource { id: ID! }
+             interface Resource implements Entity { id: ID! }
+             interface Node implements User { id: ID! }",
+        )
+        .unwrap();
+        assert_eq!(
+            doc.all_interfaces("User"),
+            vec![&"Named", &"Node", &"Entity", &"Resource"]
+        );
+        assert_eq!(doc.all_interfaces("Entity"), vec![&"Resource"]);
+        assert!(doc.all_interfaces("Missing").is_empty());
+    }
+
+    #[test]
+    fn undefined_directives() {
+        let doc = parse_schema::<&str>(
+            "directive @key(fields: String) on OBJECT
+             directive @meta(v: Int @internal) on ARGUMENT_DEFINITION
+             type User @key(fields: \"id\") @cached {
+               id: ID! @depreated(reason: \"x\")
+               name(upper: Boolean @meta(v: 1)): String @deprecated
+             }
+             enum E { A @tag }
+             extend schema @link",
+        )
+        .unwrap();
+        let names = doc
+            .undefined_directives()
+            .into_iter()
+            .map(|(name, pos)| (name, pos.line))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![("internal", 2), ("cached", 3), ("depreated", 4), ("tag", 7), ("link", 8)]
+        );
+    }
+
+    #[test]
+    fn null_default_value() {
+        let doc = parse_schema::<&str>("input I { a: Int b: Int = null }").unwrap();
+        let fields = match doc.definitions[0] {
+            Definition::TypeDefinition(TypeDefinition::InputObject(ref i)) => &i.fields,
+            _ => unreachable!(),
+        };
+        assert_eq!(fields[0].default_value, None);
+        assert_eq!(fields[1].default_value, Some(Value::Null));
+    }
+
+    #[test]
+    fn types_mut() {
+        let mut doc = parse_schema::<String>(
+            "type Q { a(x: Int): String b: [Int!] }
+             extend type Q { c: Q }
+             input I { d: Int = 1 }
+             union U = Q
+             directive @e(y: Boolean) on FIELD",
+        )
+        .unwrap();
+        for ty in doc.types_mut() {
+            if let Type::NamedType(_) | Type::ListType(..) = ty {
+                let inner = std::mem::replace(ty, Type::NamedType(String::new()));
+                *ty = Type::NonNullType(Box::new(inner), std::marker::PhantomData);
+            }
+        }
+        assert_eq!(
+            doc.to_string(),
+            "type Q {
+  a(x: Int!): String!
+  b: [Int!]!
+}
+
+extend type Q {
+  c: Q!
+}
+
+input I {
+  d: Int! = 1
+}
+
+union U = Q
+
+directive @e(y: Boolean!) on FIELD
+"
+        );
+    }
+
+    #[test]
+    fn spans() {
+        use crate::position::node_source;
+
+        let src = "\"Doc\" type User @key {
+  \"Id\" id: ID! # comment
+  name(full: Boolean = true): String
+}
+union U = A | B   ";
+        let doc = parse_schema::<&str>(src).unwrap();
+        let types = doc
+            .definitions
+            .iter()
+            .map(|d| match d {
+                Definition::TypeDefinition(t) => t,
+                _ => unreachable!(),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            node_source(src, types[0].span()),
+            &src[6..src.find("\nunion").unwrap()]
+        );
+        assert_eq!(node_source(src, types[1].span()), "union U = A | B");
+        let fields = &types[0].as_object().unwrap().fields;
+        assert_eq!(node_source(src, fields[0].span()), "\"Id\" id: ID!");
+        assert_eq!(
+            node_source(src, fields[1].span()),
+            "name(full: Boolean = true): String"
+        );
+        assert_eq!(
+            node_source(src, fields[1].arguments[0].span()),
+            "full: Boolean = true"
+        );
+        assert_eq!(fields[1].span().start, fields[1].position);
+    }
+
+    #[test]
+    fn from_reader() {
+        let input: &[u8] = b"\xef\xbb\xbftype Query {\n  a: Int\n}\n";
+        let doc = parse_schema_from_reader(input).unwrap();
+        assert_eq!(doc.to_string(), "type Query {\n  a: Int\n}\n");
+        let def = doc.definitions[0].clone().into_type_definition().unwrap();
+        assert_eq!(def.span().start.offset, 3);
+
+        let err = parse_schema_from_reader(&b"type Query { a: \xff }"[..]).unwrap_err();
+        assert_eq!(err.to_string(), "schema parse error: invalid UTF-8 at byte 16");
+        assert_eq!((err.position().line, err.position().column), (1, 17));
+        assert!(parse_schema_from_reader(&b"type Query {"[..]).is_err());
+        let err = parse_schema_from_reader(&b"type Query { a: Int } # \xc3"[..]).unwrap_err();
+        assert_eq!(err.to_string(), "schema parse error: invalid UTF-8 at byte 24");
+    }
+
+    #[test]
+    fn from_reader_split_characters() {
+        /// Returns one byte per read
+        struct Bytes<'a>(&'a [u8]);
+
+        impl std::io::Read for Bytes<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                match self.0.split_first() {
+                    Some((&byte, rest)) if !buf.is_empty() => {
+                        buf[0] = byte;
+                        self.0 = rest;
+                        Ok(1)
+                    }
+                    _ => Ok(0),
+                }
+            }
+        }
+
+        let source = "\"Ünïcödé ✓\" type Query { a: Int }";
+        let doc = parse_schema_from_reader(Bytes(source.as_bytes())).unwrap();
+        assert_eq!(doc, parse_schema::<String>(source).unwrap().into_static());
+        let err = parse_schema_from_reader(Bytes(b"\"\xc3\xc3\" type Q")).unwrap_err();
+        assert_eq!(err.to_string(), "schema parse error: invalid UTF-8 at byte 1");
+    }
+
+    #[test]
+    fn field_arguments() {
+        let doc =
+            parse_schema::<&str>("type Query { user(id: ID!, limit: Int = 10): User }").unwrap();
+        let def = doc.definitions[0].clone().into_type_definition().unwrap();
+        let field = &def.as_object().unwrap().fields[0];
+        let limit = field.argument("limit").unwrap();
+        assert_eq!(limit.default_value, Some(Value::Int(10.into())));
+        assert_eq!(field.argument("id").unwrap().name, "id");
+        assert!(field.argument("missing").is_none());
+    }
+
+    #[test]
+    fn member_lookup() {
+        let mut doc = parse_schema::<&str>(
+            "type Query { a: Int b: String }
+             interface Node { id: ID! }
+             input Filter { name: String }
+             enum Color { RED GREEN }",
+        )
+        .unwrap();
+        let query = doc.find_type("Query").unwrap().definition.unwrap();
+        assert_eq!(query.as_object().unwrap().field("b").unwrap().name, "b");
+        assert!(query.as_object().unwrap().field("c").is_none());
+        let node = doc.find_type("Node").unwrap().definition.unwrap();
+        assert!(node.as_interface().unwrap().field("id").is_some());
+        let filter = doc.find_type("Filter").unwrap().definition.unwrap();
+        assert!(filter.as_input().unwrap().field("name").is_some());
+
+        if let Definition::TypeDefinition(TypeDefinition::Enum(e)) = &mut doc.definitions[3] {
+            assert!(e.value("BLUE").is_none());
+            e.value_mut("RED").unwrap().name = "BLUE";
+        }
+        if let Definition::TypeDefinition(TypeDefinition::Object(o)) = &mut doc.definitions[0] {
+            o.field_mut("a").unwrap().name = "c";
+        }
+        assert_eq!(
+            doc.to_string(),
+            "type Query {\n  c: Int\n  b: String\n}\n\ninterface Node {\n  id: ID!\n}\n\n\
+             input Filter {\n  name: String\n}\n\nenum Color {\n  BLUE\n  GREEN\n}\n"
+        );
+    }
+
+    #[test]
+    fn map_text() {
+        let source = include_str!("../../tests/schemas/kitchen-sink.graphql");
+        let borrowed = parse_schema::<&str>(source).unwrap();
+        let owned: Document<'static, String> = borrowed.clone().map_text(String::from);
+        assert_eq!(owned, parse_schema::<String>(source).unwrap());
+        assert_eq!(borrowed.into_owned(), owned);
+    }
+
+    #[test]
+    fn shift_positions() {
+        let source = include_str!("../../tests/schemas/kitchen-sink.graphql");
+        let mut doc = parse_schema::<&str>(source).unwrap();
+        let padded = format!("\n\n  \n{}", source);
+        let expected = parse_schema::<&str>(&padded).unwrap();
+        assert_ne!(doc, expected);
+        doc.shift_positions(3, 5);
+        assert_eq!(doc, expected);
+    }
+
+    #[test]
+    fn eq_ignore_positions() {
+        let a = parse_schema::<&str>("type A @d { f(x: Int = 1): [A!] }\nenum E { V }").unwrap();
+        let b = parse_schema::<&str>(
+            "type A @d {\n  f(\n    x: Int = 1\n  ): [A!]\n}\n\nenum E {\n  V\n}",
+        )
+        .unwrap();
+        assert_ne!(a, b);
+        assert!(a.eq_ignore_positions(&b));
+        let c = parse_schema::<&str>("\"A\" type A @d { f(x: Int = 1): [A!] }\nenum E { V }")
+            .unwrap();
+        assert!(!a.eq_ignore_positions(&c));
+    }
+
+    #[test]
+    fn content_hash() {
+        use crate::DocumentKey;
+
+        let a = parse_schema::<&str>("type A { f(x: Int = 1): A }").unwrap();
+        let b = parse_schema::<String>("# comment\ntype A {\n  f(x: Int = 1): A\n}").unwrap();
+        let c = parse_schema::<&str>("\"A\" type A { f(x: Int = 1): A }").unwrap();
+        assert_eq!(a.content_hash(), b.content_hash());
+        assert_ne!(a.content_hash(), c.content_hash());
+
+        assert!(DocumentKey(a.into_owned()) == DocumentKey(b));
+    }
+
+    #[test]
+    fn all_directives() {
+        let doc = parse_schema::<&str>(
+            "schema @a { query: Q }
+             type Q @b { f(x: Int @c): Int @d }
+             enum E @e { V @f }
+             input I @g { x: Int @h }
+             extend type Q @i { g: Int @j }
+             extend schema @k
+             directive @l(x: Int @m) on FIELD
+             union U @n = Q
+             scalar S @o",
+        )
+        .unwrap();
+        let names = doc.all_directives().map(|d| d.name).collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec!["a", "b", "d", "c", "e", "f", "g", "h", "i", "j", "k", "m", "n", "o"]
+        );
+    }
+
+    #[test]
+    fn parse_directive_locations() {
+        use crate::schema::ast::parse_directive_locations;
+        use crate::schema::ast::DirectiveLocation::*;
+
+        assert_eq!(
+            parse_directive_locations("FIELD | OBJECT|ENUM_VALUE").unwrap(),
+            vec![Field, Object, EnumValue]
+        );
+        assert_eq!(parse_directive_locations(" | QUERY").unwrap(), vec![Query]);
+        assert_eq!(parse_directive_locations("  ").unwrap(), vec![]);
+        assert!(parse_directive_locations("FIELD | field").is_err());
+        assert!(parse_directive_locations("FIELD || OBJECT").is_err());
+        assert!(parse_directive_locations("FIELD |").is_err());
+    }
+
+    #[test]
+    fn max_string_length() {
+        let mut options = crate::ParseOptions::default();
+        options.max_string_length(Some(8));
+        let parse = |s| parse_schema_with_options::<&str>(s, &options).map(|_| ());
+        assert!(parse("\"\"\"Short\"\"\" type A { a: Int }").is_ok());
+        assert!(parse("\"\"\"Too long\n\"\"\" type A { a: Int }").is_err());
+        assert!(parse("type A @doc(text: \"too long\\n\") { a: Int }").is_err());
+    }
+
+    #[test]
+    fn empty_bodies() {
+        // Types without a body are valid, but braces require at least one item
+        for s in &[
+            "type Foo {}",
+            "interface Foo {}",
+            "enum Foo {}",
+            "input Foo {}",
+            "extend type Foo {}",
+        ] {
+            assert!(parse_schema::<&str>(s).is_err(), "{} should fail", s);
+        }
+        let doc = Document::<&str> {
+            definitions: vec![
+                Definition::TypeDefinition(TypeDefinition::Object(ObjectType::new("Foo"))),
+                Definition::TypeDefinition(TypeDefinition::InputObject(InputObjectType::new(
+                    "Bar",
+                ))),
+            ],
+        };
+        assert_eq!(doc.to_string(), "type Foo\n\ninput Bar\n");
+    }
 }
@@ -0,0 +1,217 @@
+//! Scanning top-level definitions without parsing them
+//!
+use combine::{choice, eof, many1, optional, parser, position, satisfy, skip_many};
+use combine::{Parser, StdParseResult};
+
+use crate::helpers::{ident, kind, name, punct};
+use crate::position::Pos;
+use crate::schema::error::ParseError;
+use crate::tokenizer::{Kind, Token, TokenStream};
+
+const KEYWORDS: &[&str] = &[
+    "schema",
+    "scalar",
+    "type",
+    "interface",
+    "union",
+    "enum",
+    "input",
+    "directive",
+    "extend",
+];
+
+/// Kind of a top-level schema definition, see [`parse_schema_headers`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DefinitionKind {
+    Schema,
+    Scalar,
+    Object,
+    Interface,
+    Union,
+    Enum,
+    InputObject,
+    Directive,
+    SchemaExtension,
+    ScalarExtension,
+    ObjectExtension,
+    InterfaceExtension,
+    UnionExtension,
+    EnumExtension,
+    InputObjectExtension,
+}
+
+impl DefinitionKind {
+    /// Returns `true` for the `extend ...` definitions
+    pub fn is_extension(&self) -> bool {
+        use self::DefinitionKind::*;
+        match *self {
+            Schema | Scalar | Object | Interface | Union | Enum | InputObject | Directive => false,
+            SchemaExtension | ScalarExtension | ObjectExtension | InterfaceExtension
+            | UnionExtension | EnumExtension | InputObjectExtension => true,
+        }
+    }
+}
+
+fn is_punct(tok: &Token, values: &[&str]) -> bool {
+    tok.kind == Kind::Punctuator && values.contains(&tok.value)
+}
+
+/// Skips a bracketed group of tokens, including nested groups
+fn group<'a>(input: &mut TokenStream<'a>) -> StdParseResult<(), TokenStream<'a>> {
+    let inner =
+        || {
+            skip_many(parser(group).or(
+                satisfy(|t: Token<'a>| !is_punct(&t, &["{", "}", "(", ")", "[", "]"])).map(|_| ()),
+            ))
+        };
+    choice((
+        punct("{").with(inner()).skip(punct("}")),
+        punct("(").with(inner()).skip(punct(")")),
+        punct("[").with(inner()).skip(punct("]")),
+    ))
+    .parse_stream(input)
+    .into_result()
+}
+
+/// Skips the rest of a definition after its name
+///
+/// The definition ends before the next top-level keyword or description.
+/// Names following `=`, `|`, `&`, `@`, `:` or `implements` are references
+/// and never end a definition, even if they look like a keyword.
+fn body<'a>(input: &mut TokenStream<'a>) -> StdParseResult<(), TokenStream<'a>> {
+    let reference = satisfy(|t: Token<'a>| {
+        is_punct(&t, &["=", "|", "&", "@", ":"])
+            || (t.kind == Kind::Name && t.value == "implements")
+    })
+    .with(optional(kind(Kind::Name)))
+    .map(|_| ());
+    let other = satisfy(|t: Token<'a>| match t.kind {
+        Kind::Name => !KEYWORDS.contains(&t.value),
+        Kind::Punctuator => !is_punct(&t, &["}", ")", "]"]),
+        Kind::IntValue | Kind::FloatValue => true,
+        Kind::StringValue | Kind::BlockString => false,
+    })
+    .map(|_| ());
+    skip_many(choice((parser(group), reference, other)))
+        .parse_stream(input)
+        .into_result()
+}
+
+fn named<'a>(
+    keyword: &'static str,
+    kind: DefinitionKind,
+) -> impl Parser<TokenStream<'a>, Output = (DefinitionKind, String)> {
+    ident(keyword)
+        .with(name::<'a, String>())
+        .map(move |name| (kind, name))
+}
+
+fn header<'a>(
+    input: &mut TokenStream<'a>,
+) -> StdParseResult<(DefinitionKind, String, Pos), TokenStream<'a>> {
+    use self::DefinitionKind::*;
+
+    (
+        optional(kind(Kind::StringValue).or(kind(Kind::BlockString))),
+        position(),
+        choice((
+            ident("extend").with(choice((
+                ident("schema").map(|_| (SchemaExtension, String::new())),
+                named("scalar", ScalarExtension),
+                named("type", ObjectExtension),
+                named("interface", InterfaceExtension),
+                named("union", UnionExtension),
+                named("enum", EnumExtension),
+                named("input", InputObjectExtension),
+            ))),
+            ident("schema").map(|_| (Schema, String::new())),
+            named("scalar", Scalar),
+            named("type", Object),
+            named("interface", Interface),
+            named("union", Union),
+            named("enum", Enum),
+            named("input", InputObject),
+            ident("directive")
+                .with(punct("@"))
+                .with(name::<'a, String>())
+                .map(|name| (Directive, name)),
+        )),
+    )
+        .map(|(_, position, (kind, name))| (kind, name, position))
+        .skip(parser(body))
+        .parse_stream(input)
+        .into_result()
+}
+
+/// Scans top-level definitions of a schema, returning their kinds, names
+/// and positions
+///
+/// Only the header of each definition is parsed, everything up to the next
+/// definition is skipped after checking that brackets are balanced. This is
+/// much faster than [`parse_schema`](crate::schema::parse_schema), but
+/// doesn't detect most syntax errors. The name is empty for schema
+/// definitions and extensions, and doesn't include `@` for directives.
+pub fn parse_schema_headers(s: &str) -> Result<Vec<(DefinitionKind, String, Pos)>, ParseError> {
+    let mut tokens = TokenStream::new(s);
+    let (headers, _) = many1(parser(header))
+        .skip(eof())
+        .parse_stream(&mut tokens)
+        .into_result()
+        .map_err(|e| e.into_inner().error)?;
+    Ok(headers)
+}
+
+#[cfg(test)]
+mod test {
+    use super::parse_schema_headers;
+    use super::DefinitionKind::*;
+    use crate::position::Pos;
+
+    #[test]
+    fn headers() {
+        let src = r#"schema { query: Query }
+"Query root"
+type Query implements type & Node @key(fields: "id") {
+  type: String
+  nested(arg: [Int] = [1, 2]): [String]
+}
+union U = type | input
+"""
+Block description
+"""
+extend type Query { x: Int }
+extend schema @a
+directive @tag(name: String!) repeatable on FIELD | OBJECT
+input In { a: Int = 1 }
+"#;
+        let headers = parse_schema_headers(src).unwrap();
+        let pos = |line, column| Pos {
+            line,
+            column,
+            offset: src.split_inclusive('\n').take(line - 1).map(str::len).sum(),
+        };
+        assert_eq!(
+            headers,
+            vec![
+                (Schema, "".into(), pos(1, 1)),
+                (Object, "Query".into(), pos(3, 1)),
+                (Union, "U".into(), pos(7, 1)),
+                (ObjectExtension, "Query".into(), pos(11, 1)),
+                (SchemaExtension, "".into(), pos(12, 1)),
+                (Directive, "tag".into(), pos(13, 1)),
+                (InputObject, "In".into(), pos(14, 1)),
+            ]
+        );
+        assert!(ObjectExtension.is_extension());
+        assert!(!Directive.is_extension());
+    }
+
+    #[test]
+    fn errors() {
+        assert!(parse_schema_headers("type A { a: Int").is_err());
+        assert!(parse_schema_headers("type A { a: Int } }").is_err());
+        assert!(parse_schema_headers("type A { a: [Int) }").is_err());
+        assert!(parse_schema_headers("extend directive @a on FIELD").is_err());
+        assert!(parse_schema_headers("query { a }").is_err());
+    }
+}
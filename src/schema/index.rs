@@ -0,0 +1,139 @@
+//! Looking up definitions of a schema by name
+//!
+use std::collections::HashMap;
+
+use crate::common::Text;
+use crate::schema::ast::*;
+
+/// Definitions of a schema document indexed by name
+///
+/// Building the index takes a single pass over the document, after which
+/// types and directives are found in constant time. The index borrows the
+/// document, so the document can't be modified while the index is alive.
+/// If a type or directive is defined more than once, the first definition
+/// is used, the same as [`Document::find_type`] does.
+#[derive(Debug, Clone)]
+pub struct SchemaIndex<'d, 'a, T: Text<'a>> {
+    types: HashMap<&'d str, &'d TypeDefinition<'a, T>>,
+    extensions: HashMap<&'d str, Vec<&'d TypeExtension<'a, T>>>,
+    directives: HashMap<&'d str, &'d DirectiveDefinition<'a, T>>,
+    query: Option<&'d str>,
+    mutation: Option<&'d str>,
+    subscription: Option<&'d str>,
+}
+
+impl<'d, 'a, T: Text<'a>> SchemaIndex<'d, 'a, T> {
+    pub fn new(doc: &'d Document<'a, T>) -> Self {
+        let mut index = SchemaIndex {
+            types: HashMap::new(),
+            extensions: HashMap::new(),
+            directives: HashMap::new(),
+            query: doc.root_type_name(OperationType::Query),
+            mutation: doc.root_type_name(OperationType::Mutation),
+            subscription: doc.root_type_name(OperationType::Subscription),
+        };
+        for def in &doc.definitions {
+            match def {
+                Definition::TypeDefinition(t) => {
+                    index.types.entry(t.name().as_ref()).or_insert(t);
+                }
+                Definition::TypeExtension(e) => {
+                    index
+                        .extensions
+                        .entry(e.name().as_ref())
+                        .or_default()
+                        .push(e);
+                }
+                Definition::DirectiveDefinition(d) => {
+                    index.directives.entry(d.name.as_ref()).or_insert(d);
+                }
+                Definition::SchemaDefinition(_) | Definition::SchemaExtension(_) => {}
+            }
+        }
+        index
+    }
+
+    /// Returns the definition of type `name`
+    pub fn type_def(&self, name: &str) -> Option<&'d TypeDefinition<'a, T>> {
+        self.types.get(name).copied()
+    }
+
+    /// Returns the extensions of type `name` in document order
+    pub fn type_extensions(&self, name: &str) -> &[&'d TypeExtension<'a, T>] {
+        self.extensions.get(name).map_or(&[], |e| &e[..])
+    }
+
+    /// Returns the definition of directive `name`, without the `@`
+    ///
+    /// Built-in directives are only found if the document defines them.
+    pub fn directive_def(&self, name: &str) -> Option<&'d DirectiveDefinition<'a, T>> {
+        self.directives.get(name).copied()
+    }
+
+    /// Returns the root type for queries
+    ///
+    /// The root types are resolved the same way as by
+    /// [`Document::root_type_name`]. `None` is returned if the root type
+    /// named by the schema definition isn't defined.
+    pub fn root_query(&self) -> Option<&'d TypeDefinition<'a, T>> {
+        self.type_def(self.query?)
+    }
+
+    /// Returns the root type for mutations, see [`SchemaIndex::root_query`]
+    pub fn root_mutation(&self) -> Option<&'d TypeDefinition<'a, T>> {
+        self.type_def(self.mutation?)
+    }
+
+    /// Returns the root type for subscriptions, see
+    /// [`SchemaIndex::root_query`]
+    pub fn root_subscription(&self) -> Option<&'d TypeDefinition<'a, T>> {
+        self.type_def(self.subscription?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::SchemaIndex;
+    use crate::schema::parse_schema;
+
+    #[test]
+    fn lookup() {
+        let doc = parse_schema::<&str>(
+            "type Query { a: Int }
+             type Mutation { b: Int }
+             extend type Query { c: Int }
+             type Query { d: Int }
+             extend type Other { e: Int }
+             directive @tag(name: String) on FIELD_DEFINITION",
+        )
+        .unwrap();
+        let index = SchemaIndex::new(&doc);
+        let query = index.type_def("Query").unwrap();
+        assert_eq!(query.as_object().unwrap().fields[0].name, "a");
+        assert_eq!(index.type_extensions("Query").len(), 1);
+        assert_eq!(index.type_extensions("Other").len(), 1);
+        assert!(index.type_extensions("Mutation").is_empty());
+        assert!(index.type_def("Other").is_none());
+        assert_eq!(index.directive_def("tag").unwrap().name, "tag");
+        assert!(index.directive_def("skip").is_none());
+
+        assert_eq!(index.root_query(), Some(query));
+        assert_eq!(index.root_mutation().unwrap().name(), &"Mutation");
+        assert!(index.root_subscription().is_none());
+    }
+
+    #[test]
+    fn schema_definition() {
+        let doc = parse_schema::<&str>(
+            "schema { query: Root subscription: Missing }
+             type Root { a: Int }
+             type Query { b: Int }
+             type Subscription { c: Int }",
+        )
+        .unwrap();
+        let index = SchemaIndex::new(&doc);
+        assert_eq!(index.root_query().unwrap().name(), &"Root");
+        assert!(index.root_mutation().is_none());
+        assert!(index.root_subscription().is_none());
+    }
+}
@@ -0,0 +1,202 @@
+//! Running all schema validators at once
+//!
+use std::collections::HashMap;
+use std::fmt;
+
+use thiserror::Error;
+
+use crate::common::Text;
+use crate::position::Pos;
+use crate::schema::ast::Document;
+use crate::schema::validate::{
+    check_default_values, check_description_formatting, check_empty_types, check_input_cycles,
+    check_null_defaults, ValidationError,
+};
+
+/// Validation rule run by [`validate_all`]
+///
+/// There is a rule for every validator in [`schema`](crate::schema) which
+/// checks a parsed document. Not included are
+/// [`check_description_formatting_source`](crate::schema::check_description_formatting_source),
+/// which needs the source text, and
+/// [`apply_extensions`](crate::schema::apply_extensions), which rewrites the
+/// document and stops at the first error. Validators of executable documents
+/// are in [`query`](crate::query). Rules will be added along with new
+/// validators.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Rule {
+    /// See [`check_input_cycles`]
+    InputCycles,
+    /// See [`check_null_defaults`]
+    NullDefaults,
+    /// See [`check_default_values`]
+    DefaultValues,
+    /// See [`check_empty_types`]
+    EmptyTypes,
+    /// See [`check_description_formatting`]
+    DescriptionFormatting,
+}
+
+impl Rule {
+    /// All rules in the order they are run
+    pub const ALL: &'static [Rule] = &[
+        Rule::InputCycles,
+        Rule::NullDefaults,
+        Rule::DefaultValues,
+        Rule::EmptyTypes,
+        Rule::DescriptionFormatting,
+    ];
+
+    /// Returns the identifier of the rule, as shown in diagnostics
+    pub fn id(&self) -> &'static str {
+        match *self {
+            Rule::InputCycles => "input-cycles",
+            Rule::NullDefaults => "null-defaults",
+            Rule::DefaultValues => "default-values",
+            Rule::EmptyTypes => "empty-types",
+            Rule::DescriptionFormatting => "description-formatting",
+        }
+    }
+
+    fn check<'a, T: Text<'a>>(&self, doc: &Document<'a, T>) -> Vec<ValidationError> {
+        match *self {
+            Rule::InputCycles => check_input_cycles(doc),
+            Rule::NullDefaults => check_null_defaults(doc),
+            Rule::DefaultValues => check_default_values(doc),
+            Rule::EmptyTypes => check_empty_types(doc),
+            Rule::DescriptionFormatting => check_description_formatting(doc),
+        }
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.id())
+    }
+}
+
+/// How serious a violation of a rule is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// Rules enabled by [`validate_all`] and their severities
+///
+/// By default every rule is enabled with [`Severity::Error`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ValidationConfig {
+    rules: HashMap<Rule, Option<Severity>>,
+}
+
+impl ValidationConfig {
+    /// Disable a rule
+    pub fn disable(&mut self, rule: Rule) -> &mut Self {
+        self.rules.insert(rule, None);
+        self
+    }
+
+    /// Enable a rule reporting violations with `severity`
+    pub fn severity(&mut self, rule: Rule, severity: Severity) -> &mut Self {
+        self.rules.insert(rule, Some(severity));
+        self
+    }
+
+    /// Returns the severity of a rule, or `None` if the rule is disabled
+    pub fn rule_severity(&self, rule: Rule) -> Option<Severity> {
+        self.rules
+            .get(&rule)
+            .cloned()
+            .unwrap_or(Some(Severity::Error))
+    }
+}
+
+/// Violation of a rule reported by [`validate_all`]
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("{position}: {message} [{rule}]")]
+pub struct Diagnostic {
+    pub rule: Rule,
+    pub severity: Severity,
+    pub position: Pos,
+    pub message: String,
+}
+
+/// Runs every validation rule enabled in `config`
+///
+/// Diagnostics are sorted by position; ones at the same position are
+/// listed in the order of [`Rule::ALL`].
+pub fn validate_all<'a, T>(doc: &Document<'a, T>, config: &ValidationConfig) -> Vec<Diagnostic>
+where
+    T: Text<'a>,
+{
+    let mut result = Vec::new();
+    for &rule in Rule::ALL {
+        let severity = match config.rule_severity(rule) {
+            Some(severity) => severity,
+            None => continue,
+        };
+        result.extend(rule.check(doc).into_iter().map(|e| Diagnostic {
+            rule,
+            severity,
+            position: e.position,
+            message: e.message,
+        }));
+    }
+    result.sort_by_key(|d| d.position);
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::{validate_all, Rule, Severity, ValidationConfig};
+    use crate::schema::parse_schema;
+
+    #[test]
+    fn all_rules() {
+        let doc = parse_schema::<&str>(
+            "type Empty
+             input A { a: A! b: Int! = null c: Boolean = 1 }
+             \"trailing \" scalar S",
+        )
+        .unwrap();
+        let run = |config: &ValidationConfig| {
+            validate_all(&doc, config)
+                .into_iter()
+                .map(|d| (d.rule, d.severity, d.position.line))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            run(&ValidationConfig::default()),
+            vec![
+                (Rule::EmptyTypes, Severity::Error, 1),
+                (Rule::InputCycles, Severity::Error, 2),
+                (Rule::NullDefaults, Severity::Error, 2),
+                (Rule::DefaultValues, Severity::Error, 2),
+                (Rule::DefaultValues, Severity::Error, 2),
+                (Rule::DescriptionFormatting, Severity::Error, 3),
+            ]
+        );
+        let mut config = ValidationConfig::default();
+        config
+            .disable(Rule::InputCycles)
+            .severity(Rule::EmptyTypes, Severity::Warning)
+            .disable(Rule::DescriptionFormatting);
+        assert_eq!(config.rule_severity(Rule::InputCycles), None);
+        assert_eq!(
+            run(&config),
+            vec![
+                (Rule::EmptyTypes, Severity::Warning, 1),
+                (Rule::NullDefaults, Severity::Error, 2),
+                (Rule::DefaultValues, Severity::Error, 2),
+                (Rule::DefaultValues, Severity::Error, 2),
+            ]
+        );
+        let first = validate_all(&doc, &config).remove(0);
+        assert_eq!(
+            first.to_string(),
+            "1:1: object \"Empty\" must define one or more fields [empty-types]"
+        );
+    }
+}
@@ -0,0 +1,158 @@
+//! Writing schemas in the most compact form
+//!
+use alloc::string::{String, ToString};
+use core::fmt;
+
+use crate::common::Text;
+use crate::format::Style;
+use crate::schema::ast::Document;
+use crate::tokenizer::{Kind, LexError, TokenStream};
+
+/// Error minifying a schema
+///
+/// Schemas are formatted before minifying, so this is only returned if
+/// the AST holds text which doesn't form valid tokens, such as a name with
+/// a space in it.
+#[derive(Debug)]
+pub struct MinifyError(String);
+
+impl fmt::Display for MinifyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "schema minify error: {}", self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MinifyError {}
+
+fn is_word(kind: Kind) -> bool {
+    matches!(kind, Kind::Name | Kind::IntValue | Kind::FloatValue)
+}
+
+fn is_string(kind: Kind) -> bool {
+    matches!(kind, Kind::StringValue | Kind::BlockString)
+}
+
+/// Whether two adjacent tokens would merge into one unless separated
+///
+/// Strings are self-delimiting, except that an empty string followed by
+/// another string would start a block string, so consecutive strings are
+/// separated too.
+fn needs_space(prev: Kind, next: Kind) -> bool {
+    (is_word(prev) && is_word(next)) || (is_string(prev) && is_string(next))
+}
+
+/// Formats a schema without any insignificant whitespace or commas
+///
+/// Tokens are separated by a space only where they would otherwise merge
+/// into one. Strings containing new lines, including block string
+/// descriptions, are written as regular strings with the new lines
+/// escaped, which is shorter than the indented block string. If
+/// `keep_descriptions` is `false`, descriptions are left out entirely.
+pub fn minify_schema<'a, T>(
+    doc: &Document<'a, T>,
+    keep_descriptions: bool,
+) -> Result<String, MinifyError>
+where
+    T: Text<'a>,
+{
+    let mut style = Style::default();
+    style.descriptions(keep_descriptions).block_strings(false);
+    let formatted = doc.format(&style);
+
+    let mut result = String::with_capacity(formatted.len() / 2);
+    // The document is already in memory, so its nesting is not limited
+    let mut stream = TokenStream::with_recursion_limit(&formatted, usize::MAX);
+    let mut prev = None;
+    loop {
+        let token = match stream.next_token() {
+            Ok(token) => token,
+            Err(LexError::EndOfInput) => break,
+            Err(e) => return Err(MinifyError(e.to_string())),
+        };
+        if matches!(prev, Some(prev) if needs_space(prev, token.kind)) {
+            result.push(' ');
+        }
+        result.push_str(token.value);
+        prev = Some(token.kind);
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod test {
+    use super::minify_schema;
+    use crate::common::Type;
+    use crate::schema::{parse_schema, Definition, TypeDefinition};
+
+    const SCHEMA: &str = r#"
+        """
+        The root
+          with indented text
+        """
+        type Query implements Node & Entity @key(fields: "id") {
+          "Identifier" id: ID!
+          items(first: Int = 10, tags: [String!] = ["a", "b"]): [Item]
+        }
+
+        union Item = A | B
+
+        enum Color { "Red" RED GREEN }
+
+        directive @key(fields: String!) repeatable on OBJECT | INTERFACE
+    "#;
+
+    #[test]
+    fn minify() {
+        let doc = parse_schema::<&str>(SCHEMA).unwrap();
+        let minified = minify_schema(&doc, true).unwrap();
+        assert_eq!(
+            minified,
+            concat!(
+                r#""The root\n  with indented text"type Query implements Node&Entity"#,
+                r#"@key(fields:"id"){"Identifier"id:ID!items(first:Int=10 tags:[String!]=["a" "b"]):[Item]}"#,
+                r#"union Item=A|B enum Color{"Red"RED GREEN}"#,
+                r#"directive@key(fields:String!)repeatable on OBJECT|INTERFACE"#,
+            )
+        );
+        assert_eq!(
+            parse_schema::<&str>(&minified).unwrap().to_string(),
+            doc.to_string()
+        );
+    }
+
+    #[test]
+    fn without_descriptions() {
+        let doc = parse_schema::<&str>(SCHEMA).unwrap();
+        let minified = minify_schema(&doc, false).unwrap();
+        assert!(minified.starts_with("type Query implements"));
+        assert!(!minified.contains("Identifier"));
+        assert!(minified.contains("enum Color{RED GREEN}"));
+    }
+
+    #[test]
+    fn deep_nesting() {
+        let mut doc = parse_schema::<String>("type Query { f: Int }").unwrap();
+        if let Definition::TypeDefinition(TypeDefinition::Object(ref mut o)) = doc.definitions[0] {
+            for _ in 0..100 {
+                let inner = o.fields[0].field_type.clone();
+                o.fields[0].field_type = Type::ListType(Box::new(inner));
+            }
+        }
+        let minified = minify_schema(&doc, true).unwrap();
+        assert!(minified.ends_with(&format!("Int{}}}", "]".repeat(100))));
+    }
+
+    #[test]
+    fn invalid_text() {
+        let mut doc = parse_schema::<String>("type Query { f: Int }").unwrap();
+        if let Definition::TypeDefinition(TypeDefinition::Object(ref mut o)) = doc.definitions[0] {
+            o.fields[0].name = "f?".into();
+        }
+        let err = minify_schema(&doc, true).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "schema minify error: unexpected character '?'"
+        );
+    }
+}
@@ -1,10 +1,68 @@
 //! Schema definition language AST and utility
 //!
 mod ast;
+mod compare;
+#[cfg(feature = "std")]
+mod diff;
+#[cfg(feature = "std")]
+mod edit;
+#[cfg(feature = "std")]
 mod error;
+#[cfg(feature = "std")]
+mod extend;
+#[cfg(feature = "std")]
+mod flatten;
 mod format;
+#[cfg(feature = "std")]
 mod grammar;
+#[cfg(feature = "std")]
+mod headers;
+#[cfg(feature = "std")]
+mod index;
+#[cfg(feature = "std")]
+mod lint;
+mod minify;
+#[cfg(feature = "std")]
+mod rename;
+#[cfg(feature = "std")]
+mod skeleton;
+#[cfg(feature = "std")]
+mod validate;
 
 pub use self::ast::*;
+#[cfg(feature = "std")]
+pub use self::diff::{diff, SchemaChange};
+#[cfg(feature = "std")]
+pub use self::edit::{apply_edit, EditError, SchemaEdit};
+#[cfg(feature = "std")]
 pub use self::error::ParseError;
-pub use self::grammar::parse_schema;
+#[cfg(feature = "std")]
+pub use self::extend::{apply_extensions, ExtensionError};
+#[cfg(feature = "std")]
+pub use self::flatten::flatten_interfaces;
+#[cfg(feature = "std")]
+pub use self::format::format_schema_streaming;
+#[cfg(feature = "std")]
+pub(crate) use self::grammar::definition;
+#[cfg(feature = "std")]
+pub use self::grammar::{
+    parse_schema, parse_schema_from_reader, parse_schema_with_options, parse_schema_with_warnings,
+};
+#[cfg(feature = "std")]
+pub use self::headers::{parse_schema_headers, DefinitionKind};
+#[cfg(feature = "std")]
+pub use self::index::SchemaIndex;
+pub use self::minify::{minify_schema, MinifyError};
+#[cfg(feature = "std")]
+pub use self::lint::{validate_all, Diagnostic, Rule, Severity, ValidationConfig};
+#[cfg(feature = "std")]
+pub(crate) use self::rename::field_owners;
+#[cfg(feature = "std")]
+pub use self::rename::rename_field;
+#[cfg(feature = "std")]
+pub use self::skeleton::query_skeleton_for;
+#[cfg(feature = "std")]
+pub use self::validate::{
+    check_default_values, check_description_formatting, check_description_formatting_source,
+    check_empty_types, check_input_cycles, check_null_defaults, ValidationError,
+};
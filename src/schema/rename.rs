@@ -0,0 +1,136 @@
+//! Renaming fields of schema types
+//!
+use std::collections::HashSet;
+
+use crate::common::Text;
+use crate::schema::ast::*;
+
+/// Returns `type_name` and the names of every object and interface type
+/// implementing `type_name`, directly or through other interfaces
+pub(crate) fn field_owners<'a, T>(schema: &Document<'a, T>, type_name: &str) -> HashSet<String>
+where
+    T: Text<'a>,
+{
+    let mut owners = HashSet::new();
+    owners.insert(type_name.to_string());
+    for def in &schema.definitions {
+        let name = match def {
+            Definition::TypeDefinition(TypeDefinition::Object(o)) => &o.name,
+            Definition::TypeDefinition(TypeDefinition::Interface(i)) => &i.name,
+            Definition::TypeExtension(TypeExtension::Object(o)) => &o.name,
+            Definition::TypeExtension(TypeExtension::Interface(i)) => &i.name,
+            _ => continue,
+        };
+        if owners.contains(name.as_ref()) {
+            continue;
+        }
+        if schema
+            .all_interfaces(name.as_ref())
+            .iter()
+            .any(|i| i.as_ref() == type_name)
+        {
+            owners.insert(name.as_ref().to_string());
+        }
+    }
+    owners
+}
+
+/// Renames field `from` of the object or interface type `type_name` to `to`
+///
+/// The field is renamed in the type definition and in all of its
+/// extensions. When `type_name` is an interface, the field is renamed in
+/// every type implementing the interface as well, directly or through other
+/// interfaces, so that they keep satisfying it. Returns `false` if there was no such field.
+///
+/// Use [`rename_field_selections`](crate::query::rename_field_selections)
+/// to update queries selecting the field.
+pub fn rename_field<'a, T>(schema: &mut Document<'a, T>, type_name: &str, from: &str, to: T) -> bool
+where
+    T: Text<'a>,
+{
+    let owners = field_owners(schema, type_name);
+    let mut renamed = false;
+    for def in &mut schema.definitions {
+        let (name, fields) = match def {
+            Definition::TypeDefinition(TypeDefinition::Object(o)) => (&o.name, &mut o.fields),
+            Definition::TypeDefinition(TypeDefinition::Interface(i)) => (&i.name, &mut i.fields),
+            Definition::TypeExtension(TypeExtension::Object(o)) => (&o.name, &mut o.fields),
+            Definition::TypeExtension(TypeExtension::Interface(i)) => (&i.name, &mut i.fields),
+            _ => continue,
+        };
+        if !owners.contains(name.as_ref()) {
+            continue;
+        }
+        for field in fields.iter_mut().filter(|f| f.name.as_ref() == from) {
+            field.name = to.clone();
+            renamed = true;
+        }
+    }
+    renamed
+}
+
+#[cfg(test)]
+mod test {
+    use super::rename_field;
+    use crate::schema::parse_schema;
+
+    #[test]
+    fn interface_field() {
+        let mut doc = parse_schema::<&str>(
+            "interface Node { id: ID! }
+             type User implements Node { id: ID! name: String }
+             type Post { id: ID! }
+             extend type Post implements Node
+             type Other { id: ID }
+             extend interface Node { idx: Int }",
+        )
+        .unwrap();
+        assert!(rename_field(&mut doc, "Node", "id", "key"));
+        assert_eq!(
+            doc.to_string(),
+            "interface Node {
+  key: ID!
+}
+
+type User implements Node {
+  key: ID!
+  name: String
+}
+
+type Post {
+  key: ID!
+}
+
+extend type Post implements Node
+
+type Other {
+  id: ID
+}
+
+extend interface Node {
+  idx: Int
+}
+"
+        );
+    }
+
+    #[test]
+    fn inherited_interface_field() {
+        let mut doc = parse_schema::<&str>(
+            "interface Node { id: ID! }
+             interface Entity implements Node { id: ID! }
+             type User implements Entity & Node { id: ID! }
+             type Post implements Entity { id: ID! }",
+        )
+        .unwrap();
+        assert!(rename_field(&mut doc, "Node", "id", "key"));
+        assert_eq!(doc.to_string().matches("key: ID!").count(), 4);
+    }
+
+    #[test]
+    fn missing_field() {
+        let mut doc = parse_schema::<&str>("type User { id: ID! }").unwrap();
+        assert!(!rename_field(&mut doc, "User", "name", "title"));
+        assert!(!rename_field(&mut doc, "Post", "id", "key"));
+    }
+}
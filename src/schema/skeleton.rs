@@ -0,0 +1,321 @@
+//! Generating queries from schema types
+//!
+use std::collections::HashMap;
+
+use crate::common::{Text, Type};
+use crate::position::Pos;
+use crate::query;
+use crate::schema::ast::*;
+
+enum Composite<'d, 'a, T: Text<'a>> {
+    Fields(Vec<&'d Field<'a, T>>),
+    Union(Vec<&'d T>),
+}
+
+struct Generator<'d, 'a, T: Text<'a>> {
+    composites: HashMap<&'d str, Composite<'d, 'a, T>>,
+    leaves: Vec<&'d str>,
+    path: Vec<&'d str>,
+}
+
+fn has_required_arguments<'a, T: Text<'a>>(field: &Field<'a, T>) -> bool {
+    field
+        .arguments
+        .iter()
+        .any(|arg| matches!(arg.value_type, Type::NonNullType(..)) && arg.default_value.is_none())
+}
+
+fn empty_selection_set<'a, T: Text<'a>>() -> query::SelectionSet<'a, T> {
+    query::SelectionSet {
+        span: (Pos::default(), Pos::default()),
+        items: Vec::new(),
+    }
+}
+
+fn simple_field<'a, T: Text<'a>>(
+    name: T,
+    selection_set: query::SelectionSet<'a, T>,
+) -> query::Selection<'a, T> {
+    query::Selection::Field(query::Field {
+        position: Pos::default(),
+        end: Pos::default(),
+        alias: None,
+        name,
+        arguments: Vec::new(),
+        directives: Vec::new(),
+        selection_set,
+    })
+}
+
+impl<'d, 'a, T: Text<'a>> Generator<'d, 'a, T> {
+    fn new(doc: &'d Document<'a, T>) -> Self {
+        let mut composites = HashMap::new();
+        let mut leaves = BUILTIN_SCALARS.to_vec();
+        let mut add_fields = |name: &'d T, fields: &'d [Field<'a, T>]| {
+            let entry = composites
+                .entry(name.as_ref())
+                .or_insert_with(|| Composite::Fields(Vec::new()));
+            if let Composite::Fields(ref mut list) = *entry {
+                list.extend(fields);
+            }
+        };
+        for def in &doc.definitions {
+            match def {
+                Definition::TypeDefinition(TypeDefinition::Object(o)) => {
+                    add_fields(&o.name, &o.fields)
+                }
+                Definition::TypeDefinition(TypeDefinition::Interface(i)) => {
+                    add_fields(&i.name, &i.fields)
+                }
+                Definition::TypeExtension(TypeExtension::Object(o)) => {
+                    add_fields(&o.name, &o.fields)
+                }
+                Definition::TypeExtension(TypeExtension::Interface(i)) => {
+                    add_fields(&i.name, &i.fields)
+                }
+                _ => {}
+            }
+        }
+        for def in &doc.definitions {
+            match def {
+                Definition::TypeDefinition(TypeDefinition::Union(u)) => {
+                    let entry = composites
+                        .entry(u.name.as_ref())
+                        .or_insert_with(|| Composite::Union(Vec::new()));
+                    if let Composite::Union(ref mut list) = *entry {
+                        list.extend(&u.types);
+                    }
+                }
+                Definition::TypeExtension(TypeExtension::Union(u)) => {
+                    let entry = composites
+                        .entry(u.name.as_ref())
+                        .or_insert_with(|| Composite::Union(Vec::new()));
+                    if let Composite::Union(ref mut list) = *entry {
+                        list.extend(&u.types);
+                    }
+                }
+                Definition::TypeDefinition(TypeDefinition::Scalar(s)) => {
+                    leaves.push(s.name.as_ref())
+                }
+                Definition::TypeDefinition(TypeDefinition::Enum(e)) => leaves.push(e.name.as_ref()),
+                _ => {}
+            }
+        }
+        Generator {
+            composites,
+            leaves,
+            path: Vec::new(),
+        }
+    }
+
+    /// Builds selection set for a composite type, returns `None` if nothing
+    /// can be selected within remaining depth
+    fn selection_set(
+        &mut self,
+        type_name: &str,
+        depth: usize,
+    ) -> Option<query::SelectionSet<'a, T>> {
+        let (&type_name, composite) = self.composites.get_key_value(type_name)?;
+        self.path.push(type_name);
+        let mut items = Vec::new();
+        match *composite {
+            Composite::Fields(ref fields) => {
+                for field in fields.clone() {
+                    if has_required_arguments(field) {
+                        continue;
+                    }
+                    let target = field.field_type.base_name().as_ref();
+                    if self.leaves.contains(&target) {
+                        items.push(simple_field(field.name.clone(), empty_selection_set()));
+                    } else if depth > 0 && !self.path.contains(&target) {
+                        if let Some(sel) = self.selection_set(target, depth - 1) {
+                            items.push(simple_field(field.name.clone(), sel));
+                        }
+                    }
+                }
+            }
+            Composite::Union(ref members) => {
+                items.push(simple_field(T::from("__typename"), empty_selection_set()));
+                for member in members.clone() {
+                    if depth == 0 || self.path.contains(&member.as_ref()) {
+                        continue;
+                    }
+                    if let Some(sel) = self.selection_set(member.as_ref(), depth - 1) {
+                        items.push(query::Selection::InlineFragment(query::InlineFragment {
+                            position: Pos::default(),
+                            type_condition: Some(query::TypeCondition::On(
+                                member.clone(),
+                                Default::default(),
+                            )),
+                            directives: Vec::new(),
+                            selection_set: sel,
+                        }));
+                    }
+                }
+            }
+        }
+        self.path.pop();
+        if items.is_empty() {
+            return None;
+        }
+        Some(query::SelectionSet {
+            span: (Pos::default(), Pos::default()),
+            items,
+        })
+    }
+}
+
+/// Generates an operation selecting every field of the type `type_name`
+///
+/// Leaf (scalar and enum) fields are always selected, object fields are
+/// followed up to `depth` levels deep, so with `depth == 0` only leaf fields
+/// of the type itself are selected. Types which are already being expanded
+/// higher up are not expanded again, object fields that would end up with
+/// nothing selected are omitted. Fields having required arguments are
+/// skipped, since there is no value to pass for them. Union types are
+/// expanded into `__typename` and an inline fragment per member type, each
+/// member taking up one more level of depth.
+///
+/// The operation is a mutation or subscription if `type_name` is the
+/// respective root type of the schema, and a query otherwise. Returns `None`
+/// if the type is not defined or is not a composite type.
+pub fn query_skeleton_for<'a, T>(
+    schema: &Document<'a, T>,
+    type_name: &str,
+    depth: usize,
+) -> Option<query::OperationDefinition<'a, T>>
+where
+    T: Text<'a>,
+{
+    let selection_set = Generator::new(schema).selection_set(type_name, depth)?;
+    let mut mutation = "Mutation";
+    let mut subscription = "Subscription";
+    for def in &schema.definitions {
+        if let Definition::SchemaDefinition(s) = def {
+            mutation = s.mutation.as_ref().map_or("", |m| m.as_ref());
+            subscription = s.subscription.as_ref().map_or("", |s| s.as_ref());
+        }
+    }
+    let op = if type_name == mutation {
+        query::OperationDefinition::Mutation(query::Mutation {
+            position: Pos::default(),
+            name: None,
+            variable_definitions: Vec::new(),
+            directives: Vec::new(),
+            selection_set,
+        })
+    } else if type_name == subscription {
+        query::OperationDefinition::Subscription(query::Subscription {
+            position: Pos::default(),
+            name: None,
+            variable_definitions: Vec::new(),
+            directives: Vec::new(),
+            selection_set,
+        })
+    } else {
+        query::OperationDefinition::Query(query::Query {
+            position: Pos::default(),
+            name: None,
+            variable_definitions: Vec::new(),
+            directives: Vec::new(),
+            selection_set,
+        })
+    };
+    Some(op)
+}
+
+#[cfg(test)]
+mod test {
+    use super::query_skeleton_for;
+    use crate::schema::parse_schema;
+
+    const SCHEMA: &str = "
+        type Query { me: User node(id: ID!): User search: [Result] }
+        type Mutation { bump: Int }
+        type User { id: ID! name: String role: Role friends: [User] org: Org }
+        type Org { name: String owner: User! }
+        union Result = User | Org
+        enum Role { ADMIN USER }
+    ";
+
+    fn skeleton(type_name: &str, depth: usize) -> String {
+        let doc = parse_schema::<&str>(SCHEMA).unwrap();
+        query_skeleton_for(&doc, type_name, depth)
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn leaves_only() {
+        assert_eq!(skeleton("User", 0), "query {\n  id\n  name\n  role\n}\n");
+    }
+
+    #[test]
+    fn nested_with_cycles() {
+        assert_eq!(
+            skeleton("Query", 2),
+            "query {
+  me {
+    id
+    name
+    role
+    org {
+      name
+    }
+  }
+  search {
+    __typename
+    ... on User {
+      id
+      name
+      role
+    }
+    ... on Org {
+      name
+    }
+  }
+}
+"
+        );
+    }
+
+    #[test]
+    fn union_members_take_depth() {
+        assert_eq!(skeleton("Result", 0), "query {\n  __typename\n}\n");
+        assert_eq!(
+            skeleton("Result", 2),
+            "query {
+  __typename
+  ... on User {
+    id
+    name
+    role
+    org {
+      name
+    }
+  }
+  ... on Org {
+    name
+    owner {
+      id
+      name
+      role
+    }
+  }
+}
+"
+        );
+    }
+
+    #[test]
+    fn mutation_root() {
+        assert_eq!(skeleton("Mutation", 1), "mutation {\n  bump\n}\n");
+    }
+
+    #[test]
+    fn missing_type() {
+        let doc = parse_schema::<&str>(SCHEMA).unwrap();
+        assert!(query_skeleton_for(&doc, "Role", 1).is_none());
+        assert!(query_skeleton_for(&doc, "Nope", 1).is_none());
+    }
+}
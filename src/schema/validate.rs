@@ -0,0 +1,807 @@
+//! Validation rules for schema documents
+//!
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+
+use combine::{Positioned, StreamOnce};
+use thiserror::Error;
+
+use crate::common::{Text, Type, Value};
+use crate::position::Pos;
+use crate::schema::ast::*;
+use crate::tokenizer::{Kind, Token, TokenStream};
+
+/// Error found by one of the schema validators
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("{position}: {message}")]
+pub struct ValidationError {
+    pub position: Pos,
+    pub message: String,
+}
+
+impl ValidationError {
+    fn new(position: Pos, message: String) -> ValidationError {
+        ValidationError { position, message }
+    }
+}
+
+/// Collects fields of all input objects, including ones added by extensions
+fn input_fields<'d, 'a, T>(doc: &'d Document<'a, T>) -> HashMap<&'d str, Vec<&'d InputValue<'a, T>>>
+where
+    T: Text<'a>,
+{
+    let mut result: HashMap<&str, Vec<_>> = HashMap::new();
+    for def in &doc.definitions {
+        match def {
+            Definition::TypeDefinition(TypeDefinition::InputObject(i)) => {
+                result.entry(i.name.as_ref()).or_default().extend(&i.fields)
+            }
+            Definition::TypeExtension(TypeExtension::InputObject(i)) => {
+                result.entry(i.name.as_ref()).or_default().extend(&i.fields)
+            }
+            _ => {}
+        }
+    }
+    result
+}
+
+/// Checks that no input object references itself through non-null fields
+///
+/// Such an input object can never be constructed, because every value would
+/// need to contain another value of the same type. Cycles going through a
+/// nullable or a list field are fine.
+pub fn check_input_cycles<'a, T>(doc: &Document<'a, T>) -> Vec<ValidationError>
+where
+    T: Text<'a>,
+{
+    struct Walker<'d, 'a, T: Text<'a>> {
+        fields: HashMap<&'d str, Vec<&'d InputValue<'a, T>>>,
+        visited: HashSet<&'d str>,
+        stack: Vec<&'d str>,
+        path: Vec<&'d InputValue<'a, T>>,
+        errors: Vec<ValidationError>,
+    }
+
+    impl<'d, 'a, T: Text<'a>> Walker<'d, 'a, T> {
+        fn walk(&mut self, name: &'d str) {
+            if !self.visited.insert(name) {
+                return;
+            }
+            self.stack.push(name);
+            let fields = self.fields.get(name).cloned().unwrap_or_default();
+            for field in fields {
+                let target = match field.value_type {
+                    Type::NonNullType(ref inner, _) => match **inner {
+                        Type::NamedType(ref target) => target.as_ref(),
+                        _ => continue,
+                    },
+                    _ => continue,
+                };
+                if !self.fields.contains_key(target) {
+                    continue;
+                }
+                self.path.push(field);
+                if let Some(idx) = self.stack.iter().position(|&n| n == target) {
+                    let cycle = &self.path[idx..];
+                    let names: Vec<&str> = cycle.iter().map(|f| f.name.as_ref()).collect();
+                    self.errors.push(ValidationError::new(
+                        cycle[0].position,
+                        format!(
+                            "cannot reference input object \"{}\" within itself \
+                             through a series of non-null fields: \"{}\"",
+                            target,
+                            names.join("."),
+                        ),
+                    ));
+                } else {
+                    self.walk(target);
+                }
+                self.path.pop();
+            }
+            self.stack.pop();
+        }
+    }
+
+    let fields = input_fields(doc);
+    let mut names: Vec<&str> = fields.keys().cloned().collect();
+    names.sort_unstable();
+    let mut walker = Walker {
+        fields,
+        visited: HashSet::new(),
+        stack: Vec::new(),
+        path: Vec::new(),
+        errors: Vec::new(),
+    };
+    for name in names {
+        walker.walk(name);
+    }
+    walker.errors
+}
+
+/// Argument or input field along with a description of where it's defined
+struct InputValueRef<'d, 'a, T: Text<'a>> {
+    /// Either `argument` or `input field`
+    kind: &'static str,
+    /// Dotted path like `Type.field.argument` or `@directive.argument`
+    path: String,
+    value: &'d InputValue<'a, T>,
+}
+
+/// Collects input object fields and arguments of fields and directives,
+/// including ones added by type extensions
+fn input_values<'d, 'a, T>(doc: &'d Document<'a, T>) -> Vec<InputValueRef<'d, 'a, T>>
+where
+    T: Text<'a>,
+{
+    let mut result = Vec::new();
+    let mut add = |kind, owner: &str, values: &'d [InputValue<'a, T>]| {
+        result.extend(values.iter().map(|value| InputValueRef {
+            kind,
+            path: format!("{}.{}", owner, value.name.as_ref()),
+            value,
+        }))
+    };
+    for def in &doc.definitions {
+        let (owner, fields) = match def {
+            Definition::TypeDefinition(TypeDefinition::Object(o)) => (&o.name, &o.fields),
+            Definition::TypeDefinition(TypeDefinition::Interface(i)) => (&i.name, &i.fields),
+            Definition::TypeExtension(TypeExtension::Object(o)) => (&o.name, &o.fields),
+            Definition::TypeExtension(TypeExtension::Interface(i)) => (&i.name, &i.fields),
+            Definition::TypeDefinition(TypeDefinition::InputObject(i)) => {
+                add("input field", i.name.as_ref(), &i.fields);
+                continue;
+            }
+            Definition::TypeExtension(TypeExtension::InputObject(i)) => {
+                add("input field", i.name.as_ref(), &i.fields);
+                continue;
+            }
+            Definition::DirectiveDefinition(d) => {
+                add("argument", &format!("@{}", d.name.as_ref()), &d.arguments);
+                continue;
+            }
+            _ => continue,
+        };
+        for field in fields {
+            let owner = format!("{}.{}", owner.as_ref(), field.name.as_ref());
+            add("argument", &owner, &field.arguments);
+        }
+    }
+    result
+}
+
+/// Checks that no non-null input field or argument defaults to `null`
+///
+/// Both input object fields and arguments of fields and directives are
+/// checked, including ones added by type extensions.
+pub fn check_null_defaults<'a, T>(doc: &Document<'a, T>) -> Vec<ValidationError>
+where
+    T: Text<'a>,
+{
+    let mut errors = Vec::new();
+    for item in input_values(doc) {
+        if let (Type::NonNullType(..), Some(Value::Null)) =
+            (&item.value.value_type, &item.value.default_value)
+        {
+            errors.push(ValidationError::new(
+                item.value.position,
+                format!(
+                    "non-null {} \"{}\" of type \"{}\" has a null default value",
+                    item.kind, item.path, item.value.value_type,
+                ),
+            ));
+        }
+    }
+    errors
+}
+
+/// Types of a schema which input values are checked against
+struct InputTypes<'d, 'a, T: Text<'a>> {
+    scalars: HashSet<&'d str>,
+    enums: HashMap<&'d str, HashSet<&'d str>>,
+    inputs: HashMap<&'d str, Vec<&'d InputValue<'a, T>>>,
+}
+
+impl<'d, 'a, T: Text<'a>> InputTypes<'d, 'a, T> {
+    fn new(doc: &'d Document<'a, T>) -> Self {
+        let mut scalars = HashSet::new();
+        let mut enums: HashMap<&str, HashSet<&str>> = HashMap::new();
+        for def in &doc.definitions {
+            match def {
+                Definition::TypeDefinition(TypeDefinition::Scalar(s)) => {
+                    scalars.insert(s.name.as_ref());
+                }
+                Definition::TypeDefinition(TypeDefinition::Enum(e)) => enums
+                    .entry(e.name.as_ref())
+                    .or_default()
+                    .extend(e.values.iter().map(|v| v.name.as_ref())),
+                Definition::TypeExtension(TypeExtension::Enum(e)) => enums
+                    .entry(e.name.as_ref())
+                    .or_default()
+                    .extend(e.values.iter().map(|v| v.name.as_ref())),
+                _ => {}
+            }
+        }
+        InputTypes {
+            scalars,
+            enums,
+            inputs: input_fields(doc),
+        }
+    }
+
+    /// Returns a description of the problem if `value` is not a valid
+    /// value of type `typ`
+    fn check(&self, typ: &Type<'a, T>, value: &Value<'a, T>) -> Result<(), String> {
+        let name = match (typ, value) {
+            (Type::NonNullType(..), Value::Null) => {
+                return Err(format!("expected non-null type \"{}\", found null", typ))
+            }
+            (Type::NonNullType(inner, _), _) => return self.check(inner, value),
+            (_, Value::Null) => return Ok(()),
+            (_, Value::Variable(..)) => {
+                return Err(format!("variables are not allowed, found {}", value))
+            }
+            (Type::ListType(inner), Value::List(items)) => {
+                return items.iter().try_for_each(|item| self.check(inner, item))
+            }
+            // single value is coerced to a list of one item
+            (Type::ListType(inner), _) => return self.check(inner, value),
+            (Type::NamedType(name), _) => name.as_ref(),
+        };
+        let mismatch = || Err(format!("expected type \"{}\", found {}", name, value));
+        match (name, value) {
+            ("Int", Value::Int(n)) => match n.as_i64() {
+                Some(n) if i32::try_from(n).is_ok() => Ok(()),
+                _ => Err(format!("{} does not fit into type \"Int\"", value)),
+            },
+            ("Float", Value::Int(_)) | ("Float", Value::Float(_)) => Ok(()),
+            ("String", Value::String(_)) => Ok(()),
+            ("Boolean", Value::Boolean(_)) => Ok(()),
+            ("ID", Value::String(_)) | ("ID", Value::Int(_)) => Ok(()),
+            ("Int", _) | ("Float", _) | ("String", _) | ("Boolean", _) | ("ID", _) => mismatch(),
+            _ if self.scalars.contains(name) => Ok(()),
+            _ => {
+                if let Some(members) = self.enums.get(name) {
+                    return match value {
+                        Value::Enum(v) if members.contains(v.as_ref()) => Ok(()),
+                        Value::Enum(v) => Err(format!(
+                            "value \"{}\" is not a member of enum \"{}\"",
+                            v.as_ref(),
+                            name
+                        )),
+                        _ => mismatch(),
+                    };
+                }
+                let fields = match self.inputs.get(name) {
+                    Some(fields) => fields,
+                    // unknown types are reported elsewhere
+                    None => return Ok(()),
+                };
+                let object = match value {
+                    Value::Object(object) => object,
+                    _ => return mismatch(),
+                };
+                for key in object.keys() {
+                    if !fields.iter().any(|f| f.name.as_ref() == key.as_ref()) {
+                        return Err(format!(
+                            "field \"{}\" is not defined on input object \"{}\"",
+                            key.as_ref(),
+                            name
+                        ));
+                    }
+                }
+                for field in fields {
+                    match object.get(field.name.as_ref()) {
+                        Some(v) => self
+                            .check(&field.value_type, v)
+                            .map_err(|e| format!("in field \"{}\": {}", field.name.as_ref(), e))?,
+                        None if matches!(field.value_type, Type::NonNullType(..))
+                            && field.default_value.is_none() =>
+                        {
+                            return Err(format!(
+                                "required field \"{}\" of input object \"{}\" is missing",
+                                field.name.as_ref(),
+                                name
+                            ))
+                        }
+                        None => {}
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Checks that default values of arguments and input fields are valid
+/// values of their declared types
+///
+/// Enum values must be members of the enum, integers must fit into 32 bits,
+/// and input objects must contain only known fields and all required ones.
+/// Values of custom scalars are not checked. Types which are not defined in
+/// the document are skipped.
+pub fn check_default_values<'a, T>(doc: &Document<'a, T>) -> Vec<ValidationError>
+where
+    T: Text<'a>,
+{
+    let types = InputTypes::new(doc);
+    let mut errors = Vec::new();
+    for item in input_values(doc) {
+        let default = match item.value.default_value {
+            Some(ref default) => default,
+            None => continue,
+        };
+        if let Err(message) = types.check(&item.value.value_type, default) {
+            errors.push(ValidationError::new(
+                item.value.position,
+                format!(
+                    "invalid default value of {} \"{}\": {}",
+                    item.kind, item.path, message
+                ),
+            ));
+        }
+    }
+    errors
+}
+
+/// Checks that object, interface and input object types have at least one
+/// field and enum types at least one value
+///
+/// Fields and values added by extensions are counted too, so a type whose
+/// body consists only of extensions is fine.
+pub fn check_empty_types<'a, T>(doc: &Document<'a, T>) -> Vec<ValidationError>
+where
+    T: Text<'a>,
+{
+    let mut extended: HashMap<&str, usize> = HashMap::new();
+    for def in &doc.definitions {
+        let (name, count) = match def {
+            Definition::TypeExtension(TypeExtension::Object(o)) => (&o.name, o.fields.len()),
+            Definition::TypeExtension(TypeExtension::Interface(i)) => (&i.name, i.fields.len()),
+            Definition::TypeExtension(TypeExtension::InputObject(i)) => (&i.name, i.fields.len()),
+            Definition::TypeExtension(TypeExtension::Enum(e)) => (&e.name, e.values.len()),
+            _ => continue,
+        };
+        *extended.entry(name.as_ref()).or_default() += count;
+    }
+    let mut errors = Vec::new();
+    for def in &doc.definitions {
+        let (position, kind, name, count, items) = match def {
+            Definition::TypeDefinition(TypeDefinition::Object(o)) => {
+                (o.position, "object", &o.name, o.fields.len(), "fields")
+            }
+            Definition::TypeDefinition(TypeDefinition::Interface(i)) => {
+                (i.position, "interface", &i.name, i.fields.len(), "fields")
+            }
+            Definition::TypeDefinition(TypeDefinition::InputObject(i)) => {
+                (i.position, "input object", &i.name, i.fields.len(), "fields")
+            }
+            Definition::TypeDefinition(TypeDefinition::Enum(e)) => {
+                (e.position, "enum", &e.name, e.values.len(), "values")
+            }
+            _ => continue,
+        };
+        let name = name.as_ref();
+        if count + extended.get(name).cloned().unwrap_or(0) == 0 {
+            errors.push(ValidationError::new(
+                position,
+                format!("{} \"{}\" must define one or more {}", kind, name, items),
+            ));
+        }
+    }
+    errors
+}
+
+fn block_string_warnings(raw: &str, position: Pos, indent: usize, errors: &mut Vec<ValidationError>) {
+    let lines: Vec<&str> = raw[3..raw.len() - 3]
+        .split('\n')
+        .map(|line| line.strip_suffix('\r').unwrap_or(line))
+        .collect();
+    let mut line_starts = vec![position.offset + 3];
+    line_starts.extend(raw.match_indices('\n').map(|(idx, _)| position.offset + idx + 1));
+    let line_pos = |idx: usize| Pos {
+        line: position.line + idx,
+        column: if idx == 0 { position.column + 3 } else { 1 },
+        offset: line_starts[idx],
+    };
+    let leading = |line: &str| line.len() - line.trim_start_matches([' ', '\t']).len();
+    let mut indents = Vec::new();
+    for (idx, line) in lines.iter().enumerate() {
+        if idx + 1 < lines.len() && line.ends_with([' ', '\t']) && !line.trim().is_empty() {
+            errors.push(ValidationError::new(
+                line_pos(idx),
+                "trailing whitespace is kept in the description".into(),
+            ));
+        }
+        if idx > 0 && !line.trim().is_empty() {
+            indents.push((idx, &line[..leading(line)]));
+        }
+    }
+    let tabs = indents.iter().any(|(_, ws)| ws.contains('\t'));
+    let spaces = indents.iter().any(|(_, ws)| ws.contains(' '));
+    if tabs && spaces {
+        errors.push(ValidationError::new(
+            position,
+            "indentation mixes tabs and spaces, a tab is removed as a single character".into(),
+        ));
+        return;
+    }
+    let common = indents.iter().map(|(_, ws)| ws.len()).min().unwrap_or(0);
+    let deeper = indents.iter().any(|(_, ws)| ws.len() > common);
+    if common < indent && deeper {
+        let (idx, _) = indents.iter().find(|(_, ws)| ws.len() == common).unwrap();
+        errors.push(ValidationError::new(
+            line_pos(*idx),
+            format!(
+                "line is indented less than the opening quotes, \
+                 so other lines keep up to {} extra leading characters",
+                indent - common,
+            ),
+        ));
+    }
+}
+
+/// Collects the descriptions of every definition, field, argument and enum
+/// value, with the position of the node they describe
+fn descriptions<'d, 'a, T>(doc: &'d Document<'a, T>) -> Vec<(Pos, &'d str)>
+where
+    T: Text<'a>,
+{
+    fn push<'d>(result: &mut Vec<(Pos, &'d str)>, position: Pos, description: &'d Option<String>) {
+        if let Some(description) = description {
+            result.push((position, description));
+        }
+    }
+    fn input_values<'d, 'a, T: Text<'a>>(
+        result: &mut Vec<(Pos, &'d str)>,
+        values: &'d [InputValue<'a, T>],
+    ) {
+        for value in values {
+            push(result, value.position, &value.description);
+        }
+    }
+    fn fields<'d, 'a, T: Text<'a>>(result: &mut Vec<(Pos, &'d str)>, fields: &'d [Field<'a, T>]) {
+        for field in fields {
+            push(result, field.position, &field.description);
+            input_values(result, &field.arguments);
+        }
+    }
+    fn enum_values<'d, 'a, T: Text<'a>>(
+        result: &mut Vec<(Pos, &'d str)>,
+        values: &'d [EnumValue<'a, T>],
+    ) {
+        for value in values {
+            push(result, value.position, &value.description);
+        }
+    }
+
+    let mut result = Vec::new();
+    for def in &doc.definitions {
+        match def {
+            Definition::SchemaDefinition(_) | Definition::SchemaExtension(_) => {}
+            Definition::TypeDefinition(def) => match def {
+                TypeDefinition::Scalar(s) => push(&mut result, s.position, &s.description),
+                TypeDefinition::Object(o) => {
+                    push(&mut result, o.position, &o.description);
+                    fields(&mut result, &o.fields);
+                }
+                TypeDefinition::Interface(i) => {
+                    push(&mut result, i.position, &i.description);
+                    fields(&mut result, &i.fields);
+                }
+                TypeDefinition::Union(u) => push(&mut result, u.position, &u.description),
+                TypeDefinition::Enum(e) => {
+                    push(&mut result, e.position, &e.description);
+                    enum_values(&mut result, &e.values);
+                }
+                TypeDefinition::InputObject(i) => {
+                    push(&mut result, i.position, &i.description);
+                    input_values(&mut result, &i.fields);
+                }
+            },
+            Definition::TypeExtension(ext) => match ext {
+                TypeExtension::Object(o) => fields(&mut result, &o.fields),
+                TypeExtension::Interface(i) => fields(&mut result, &i.fields),
+                TypeExtension::Enum(e) => enum_values(&mut result, &e.values),
+                TypeExtension::InputObject(i) => input_values(&mut result, &i.fields),
+                TypeExtension::Scalar(_) | TypeExtension::Union(_) => {}
+            },
+            Definition::DirectiveDefinition(d) => {
+                push(&mut result, d.position, &d.description);
+                input_values(&mut result, &d.arguments);
+            }
+        }
+    }
+    result
+}
+
+/// Checks descriptions for whitespace which is easy to miss in the source
+///
+/// The document only holds descriptions after block strings had their
+/// indentation removed, so this reports what is left of the problems
+/// [`check_description_formatting_source`] finds in the source text:
+///
+/// * trailing whitespace on a line
+/// * indentation mixing tabs and spaces which was kept in the description
+///
+/// Lines indented less than the opening `"""` can only be found in the
+/// source. Descriptions written as regular strings are checked too, since
+/// the document doesn't record how a description was quoted. Errors point at
+/// the node the description belongs to.
+pub fn check_description_formatting<'a, T>(doc: &Document<'a, T>) -> Vec<ValidationError>
+where
+    T: Text<'a>,
+{
+    let leading = |line: &str| line.len() - line.trim_start_matches([' ', '\t']).len();
+    let mut errors = Vec::new();
+    for (position, description) in descriptions(doc) {
+        let lines = description.split('\n').collect::<Vec<_>>();
+        if lines.iter().any(|line| line.ends_with([' ', '\t']) && !line.trim().is_empty()) {
+            errors.push(ValidationError::new(
+                position,
+                "trailing whitespace is kept in the description".into(),
+            ));
+        }
+        let indents = lines.iter().map(|line| &line[..leading(line)]);
+        let tabs = indents.clone().any(|ws| ws.contains('\t'));
+        let spaces = indents.clone().any(|ws| ws.contains(' '));
+        if tabs && spaces {
+            errors.push(ValidationError::new(
+                position,
+                "indentation kept in the description mixes tabs and spaces".into(),
+            ));
+        }
+    }
+    errors
+}
+
+/// Checks block string descriptions for whitespace that ends up in the
+/// description differently than it looks in the source
+///
+/// This works on the source text, because the parsed document only contains
+/// descriptions after removing the indentation, so it finds more than
+/// [`check_description_formatting`], doesn't need the source to parse, and
+/// points at the offending lines. It reports:
+///
+/// * trailing whitespace, which is kept in the description
+/// * indentation mixing tabs and spaces, since a tab is removed as one
+///   character regardless of its width
+/// * lines indented less than the opening `"""`, which makes all other lines
+///   keep part of their indentation
+///
+/// Block strings used as values in default values and directive arguments
+/// are not checked. Scanning stops at the first token the tokenizer rejects.
+pub fn check_description_formatting_source(source: &str) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+    let mut tokens = TokenStream::new(source);
+    let mut prev: Option<Token> = None;
+    let mut list_depth = 0usize;
+    loop {
+        let position = tokens.position();
+        let offset = tokens.offset();
+        let tok = match tokens.uncons() {
+            Ok(tok) => tok,
+            Err(_) => break,
+        };
+        match (tok.kind, tok.value) {
+            (Kind::Punctuator, "[") => list_depth += 1,
+            (Kind::Punctuator, "]") => list_depth = list_depth.saturating_sub(1),
+            (Kind::BlockString, raw) => {
+                let is_value = list_depth > 0
+                    || matches!(prev, Some(p) if p.kind == Kind::Punctuator
+                        && (p.value == ":" || p.value == "="));
+                if !is_value {
+                    let line_start = source[..offset].rfind('\n').map_or(0, |i| i + 1);
+                    let before = &source[line_start..offset];
+                    let indent = match before.trim_start_matches([' ', '\t']) {
+                        "" => before.len(),
+                        _ => 0,
+                    };
+                    block_string_warnings(raw, position, indent, &mut errors);
+                }
+            }
+            _ => {}
+        }
+        prev = Some(tok);
+    }
+    errors
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        check_default_values, check_description_formatting, check_description_formatting_source,
+        check_empty_types, check_input_cycles, check_null_defaults,
+    };
+    use crate::schema::parse_schema;
+
+    fn cycles(s: &str) -> Vec<String> {
+        let doc = parse_schema::<&str>(s).unwrap();
+        check_input_cycles(&doc)
+            .into_iter()
+            .map(|e| e.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn direct_cycle() {
+        assert_eq!(
+            cycles("input A { a: A! }"),
+            vec![
+                "1:11: cannot reference input object \"A\" within itself \
+                 through a series of non-null fields: \"a\""
+            ]
+        );
+    }
+
+    #[test]
+    fn indirect_cycle() {
+        assert_eq!(
+            cycles("input A { b: B! } input B { c: C! } input C { a: A! x: Int }"),
+            vec![
+                "1:11: cannot reference input object \"A\" within itself \
+                 through a series of non-null fields: \"b.c.a\""
+            ]
+        );
+    }
+
+    #[test]
+    fn cycle_through_extension() {
+        assert_eq!(
+            cycles("input A { x: Int } extend input A { a: A! }").len(),
+            1
+        );
+    }
+
+    #[test]
+    fn nullable_and_list_break_cycles() {
+        assert!(cycles("input A { a: A b: [A!]! } input B { b: [B]! }").is_empty());
+    }
+
+    #[test]
+    fn null_defaults() {
+        let doc = parse_schema::<&str>(
+            "input A { x: Int! = null y: Int = null z: Int! = 1 }
+             type Q { f(a: [Int]! = null, b: [Int!] = null): Int }
+             extend input A { w: String! = null }
+             directive @d(v: Boolean! = null) on FIELD",
+        )
+        .unwrap();
+        let errors: Vec<String> = check_null_defaults(&doc)
+            .into_iter()
+            .map(|e| e.to_string())
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                "1:11: non-null input field \"A.x\" of type \"Int!\" has a null default value",
+                "2:25: non-null argument \"Q.f.a\" of type \"[Int]!\" has a null default value",
+                "3:31: non-null input field \"A.w\" of type \"String!\" has a null default value",
+                "4:27: non-null argument \"@d.v\" of type \"Boolean!\" has a null default value",
+            ]
+        );
+    }
+
+    #[test]
+    fn default_values() {
+        let doc = parse_schema::<&str>(
+            "enum Color { RED GREEN }
+             extend enum Color { BLUE }
+             input Point { x: Int! y: Int! = 0 }
+             scalar Json
+             type Q {
+               a(c: Color = BLUE, d: [Color!] = [RED, GREEN], e: Json = {any: [1]}): Int
+               b(c: Color = PINK, p: Point = {y: 1}, q: Point = {x: 1, z: 2}): Int
+               c(i: Int = 3000000000, f: Float = 1, s: [String] = \"x\", t: String = 1): Int
+               d(p: [Point!] = [{x: 1}, null], id: ID = 5): Int
+             }",
+        )
+        .unwrap();
+        let errors: Vec<String> = check_default_values(&doc)
+            .into_iter()
+            .map(|e| e.to_string())
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                "7:18: invalid default value of argument \"Q.b.c\": \
+                 value \"PINK\" is not a member of enum \"Color\"",
+                "7:35: invalid default value of argument \"Q.b.p\": \
+                 required field \"x\" of input object \"Point\" is missing",
+                "7:54: invalid default value of argument \"Q.b.q\": \
+                 field \"z\" is not defined on input object \"Point\"",
+                "8:18: invalid default value of argument \"Q.c.i\": \
+                 3000000000 does not fit into type \"Int\"",
+                "8:72: invalid default value of argument \"Q.c.t\": \
+                 expected type \"String\", found 1",
+                "9:18: invalid default value of argument \"Q.d.p\": \
+                 expected non-null type \"Point!\", found null",
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_types() {
+        let doc = parse_schema::<&str>(
+            "type A type B { b: Int } type C extend type C { c: Int } extend type C @x
+             interface I input In enum E union U scalar S
+             extend type Z { z: Int } extend enum E @x",
+        )
+        .unwrap();
+        let errors: Vec<String> = check_empty_types(&doc)
+            .into_iter()
+            .map(|e| e.to_string())
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                "1:1: object \"A\" must define one or more fields",
+                "2:14: interface \"I\" must define one or more fields",
+                "2:26: input object \"In\" must define one or more fields",
+                "2:35: enum \"E\" must define one or more values",
+            ]
+        );
+    }
+
+    #[test]
+    fn description_formatting() {
+        let source = "\"\"\"
+Fine
+  indented
+\"\"\"
+type A {
+  \"\"\"
+  Trailing  
+  \"\"\"
+  a: Int
+  \"\"\"
+    Deeper
+ Shallow
+    Deeper
+  \"\"\"
+  b(x: String = \"\"\"value \"\"\", y: [String] = [\"\"\"
+\t\"\"\"]): Int
+  \"\"\"
+  \tTab
+    Space
+  \"\"\"
+  c: Int
+}
+";
+        let errors: Vec<String> = check_description_formatting_source(source)
+            .into_iter()
+            .map(|e| e.to_string())
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                "7:1: trailing whitespace is kept in the description",
+                "12:1: line is indented less than the opening quotes, \
+                 so other lines keep up to 1 extra leading characters",
+                "17:3: indentation mixes tabs and spaces, a tab is removed as a single character",
+            ]
+        );
+
+        let doc = parse_schema::<&str>(source).unwrap();
+        let errors: Vec<String> = check_description_formatting(&doc)
+            .into_iter()
+            .map(|e| e.to_string())
+            .collect();
+        assert_eq!(errors, vec!["6:3: trailing whitespace is kept in the description"]);
+
+        let doc = parse_schema::<&str>(
+            "enum E {\n  \"\"\"\n  a\n  \tb\n      c\n  \"\"\"\n  V\n}\n\"x  \" scalar S",
+        )
+        .unwrap();
+        let errors: Vec<String> = check_description_formatting(&doc)
+            .into_iter()
+            .map(|e| e.to_string())
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                "2:3: indentation kept in the description mixes tabs and spaces",
+                "9:7: trailing whitespace is kept in the description",
+            ]
+        );
+    }
+}
@@ -1,11 +1,26 @@
-use std::fmt;
-
+//! Splitting documents into tokens
+//!
+use alloc::borrow::Cow;
+use alloc::collections::VecDeque;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+
+#[cfg(feature = "std")]
 use combine::easy::{Error, Errors, Info};
+#[cfg(feature = "std")]
 use combine::error::StreamError;
+#[cfg(feature = "std")]
 use combine::stream::ResetStream;
+#[cfg(feature = "std")]
 use combine::{Positioned, StreamOnce};
 
-use crate::position::Pos;
+use crate::common::Comment;
+#[cfg(feature = "std")]
+use crate::options::ParseOptions;
+use crate::options::{ParseLimits, ParseWarning};
+use crate::position::{Pos, Span};
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum Kind {
@@ -23,27 +38,102 @@ pub struct Token<'a> {
     pub value: &'a str,
 }
 
+/// Error reading the next token
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum LexError {
+    EndOfInput,
+    /// Input which can't start or continue a token
+    Unexpected(Cow<'static, str>),
+    /// A limit of the stream was exceeded
+    Limit(&'static str),
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LexError::EndOfInput => f.write_str("unexpected end of input"),
+            LexError::Unexpected(message) => f.write_str(message),
+            LexError::Limit(message) => f.write_str(message),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a> From<LexError> for Error<Token<'a>, Token<'a>> {
+    fn from(e: LexError) -> Self {
+        match e {
+            LexError::EndOfInput => Error::end_of_input(),
+            LexError::Unexpected(Cow::Borrowed(message)) => Error::Unexpected(Info::Static(message)),
+            LexError::Unexpected(Cow::Owned(message)) => Error::Unexpected(Info::Owned(message)),
+            LexError::Limit(message) => Error::message_static_message(message),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub struct TokenStream<'a> {
     buf: &'a str,
     position: Pos,
+    /// Position right past the last token returned
+    end_position: Pos,
     off: usize,
-    next_state: Option<(usize, Token<'a>, usize, Pos)>,
+    next_state: Option<(usize, Token<'a>, usize, Pos, Pos, usize, usize)>,
     recursion_limit: usize,
+    /// Number of tokens up to the current position
+    tokens: usize,
+    max_tokens: Option<usize>,
+    max_string_length: Option<usize>,
+    single_quoted_strings: bool,
+    warnings: Vec<ParseWarning>,
+    /// Comments skipped so far, if they are collected
+    comments: Option<Vec<Comment>>,
 }
 
 impl TokenStream<'_> {
+    #[cfg(feature = "std")]
     pub(crate) fn offset(&self) -> usize {
         self.off
     }
+
+    /// Returns the position right past the last token, before any
+    /// whitespace and comments following it
+    pub(crate) fn end_position(&self) -> Pos {
+        self.end_position
+    }
+
+    /// Returns the comments skipped so far, in source order
+    #[cfg(feature = "std")]
+    pub(crate) fn take_comments(&mut self) -> Vec<Comment> {
+        self.comments.take().unwrap_or_default()
+    }
+
+    /// Returns the comments skipped since the last call, continuing to
+    /// collect the following ones
+    fn drain_comments(&mut self) -> Vec<Comment> {
+        self.comments.as_mut().map(core::mem::take).unwrap_or_default()
+    }
+
+    /// Returns warnings about input accepted only because of lenient
+    /// parse options, in source order
+    #[cfg(feature = "std")]
+    pub(crate) fn take_warnings(&mut self) -> Vec<ParseWarning> {
+        let mut warnings = core::mem::take(&mut self.warnings);
+        warnings.sort_by_key(|w| w.position);
+        warnings
+    }
 }
 
+#[cfg(feature = "std")]
 #[derive(Clone, Debug, PartialEq)]
 pub struct Checkpoint {
     position: Pos,
+    end_position: Pos,
     off: usize,
+    recursion_limit: usize,
+    tokens: usize,
 }
 
+#[cfg(feature = "std")]
 impl<'a> StreamOnce for TokenStream<'a> {
     type Token = Token<'a>;
     type Range = Token<'a>;
@@ -51,40 +141,35 @@ impl<'a> StreamOnce for TokenStream<'a> {
     type Error = Errors<Token<'a>, Token<'a>, Pos>;
 
     fn uncons(&mut self) -> Result<Self::Token, Error<Token<'a>, Token<'a>>> {
-        if let Some((at, tok, off, pos)) = self.next_state {
-            if at == self.off {
-                self.off = off;
-                self.position = pos;
-                return Ok(tok);
-            }
-        }
-        let old_pos = self.off;
-        let (kind, len) = self.take_token()?;
-        let value = &self.buf[self.off - len..self.off];
-        self.skip_whitespace();
-        let token = Token { kind, value };
-        self.next_state = Some((old_pos, token, self.off, self.position));
-        Ok(token)
+        self.next_token().map_err(Error::from)
     }
 }
 
+#[cfg(feature = "std")]
 impl<'a> Positioned for TokenStream<'a> {
     fn position(&self) -> Self::Position {
         self.position
     }
 }
 
+#[cfg(feature = "std")]
 impl<'a> ResetStream for TokenStream<'a> {
     type Checkpoint = Checkpoint;
     fn checkpoint(&self) -> Self::Checkpoint {
         Checkpoint {
             position: self.position,
+            end_position: self.end_position,
             off: self.off,
+            recursion_limit: self.recursion_limit,
+            tokens: self.tokens,
         }
     }
     fn reset(&mut self, checkpoint: Checkpoint) -> Result<(), Self::Error> {
         self.position = checkpoint.position;
+        self.end_position = checkpoint.end_position;
         self.off = checkpoint.off;
+        self.recursion_limit = checkpoint.recursion_limit;
+        self.tokens = checkpoint.tokens;
         Ok(())
     }
 }
@@ -129,25 +214,116 @@ fn check_float(value: &str, exponent: Option<usize>, real: Option<usize>) -> boo
 }
 
 impl<'a> TokenStream<'a> {
-    pub fn new(s: &str) -> TokenStream {
-        Self::with_recursion_limit(s, 50)
+    pub fn new(s: &str) -> TokenStream<'_> {
+        Self::with_recursion_limit(s, ParseLimits::default().max_depth)
     }
 
     /// Specify a limit to recursive parsing. Note that increasing the limit
     /// from the default may represent a security issue since a maliciously
     /// crafted input may cause a stack overflow, crashing the process.
-    pub(crate) fn with_recursion_limit(s: &str, recursion_limit: usize) -> TokenStream {
+    pub(crate) fn with_recursion_limit(s: &str, recursion_limit: usize) -> TokenStream<'_> {
         let mut me = TokenStream {
             buf: s,
-            position: Pos { line: 1, column: 1 },
+            position: Pos {
+                line: 1,
+                column: 1,
+                offset: 0,
+            },
+            end_position: Pos {
+                line: 1,
+                column: 1,
+                offset: 0,
+            },
             off: 0,
             next_state: None,
             recursion_limit,
+            tokens: 0,
+            max_tokens: None,
+            max_string_length: None,
+            single_quoted_strings: false,
+            warnings: Vec::new(),
+            comments: None,
         };
         me.skip_whitespace();
         me
     }
 
+    /// Creates a stream which collects the comments it skips, see
+    /// [`TokenStream::take_comments`]
+    pub(crate) fn with_comments(s: &str) -> TokenStream<'_> {
+        let mut me = TokenStream::new(s);
+        // Skip the whitespace before the first token again, collecting
+        // comments this time
+        me.comments = Some(Vec::new());
+        me.off = 0;
+        me.position = Pos {
+            line: 1,
+            column: 1,
+            offset: 0,
+        };
+        me.skip_whitespace();
+        me
+    }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn with_options<'s>(s: &'s str, options: &ParseOptions) -> TokenStream<'s> {
+        let mut me = TokenStream::with_recursion_limit(s, options.limits.max_depth);
+        me.max_tokens = options.limits.max_tokens;
+        me.max_string_length = options.max_string_length;
+        me.single_quoted_strings = options.single_quoted_strings;
+        me
+    }
+
+    /// Returns the next token, skipping the whitespace and comments after it
+    pub(crate) fn next_token(&mut self) -> Result<Token<'a>, LexError> {
+        if let Some((at, tok, off, pos, end, recursion_limit, tokens)) = self.next_state {
+            if at == self.off {
+                self.off = off;
+                self.position = pos;
+                self.end_position = end;
+                self.recursion_limit = recursion_limit;
+                self.tokens = tokens;
+                return Ok(tok);
+            }
+        }
+        let old_pos = self.off;
+        let (kind, len) = self.take_token()?;
+        self.tokens += 1;
+        if matches!(self.max_tokens, Some(max) if self.tokens > max) {
+            return Err(LexError::Limit("Token limit exceeded"));
+        }
+        let value = &self.buf[self.off - len..self.off];
+        self.end_position = Pos {
+            offset: self.off,
+            ..self.position
+        };
+        self.skip_whitespace();
+        let token = Token { kind, value };
+        self.next_state = Some((
+            old_pos,
+            token,
+            self.off,
+            self.position,
+            self.end_position,
+            self.recursion_limit,
+            self.tokens,
+        ));
+        Ok(token)
+    }
+
+    fn check_string_length(&self, len: usize) -> Result<(), LexError> {
+        match self.max_string_length {
+            Some(max) if len > max => Err(LexError::Unexpected(
+                format!(
+                    "string value of {} bytes exceeds the limit of {} bytes",
+                    len, max
+                )
+                .into(),
+            )),
+            _ => Ok(()),
+        }
+    }
+
     /// Convenience for the common case where a token does
     /// not span multiple lines. Infallible.
     #[inline]
@@ -157,12 +333,12 @@ impl<'a> TokenStream<'a> {
         Ok((kind, size))
     }
 
-    fn take_token(&mut self) -> Result<(Kind, usize), Error<Token<'a>, Token<'a>>> {
+    fn take_token(&mut self) -> Result<(Kind, usize), LexError> {
         use self::Kind::*;
         let mut iter = self.buf[self.off..].char_indices();
         let cur_char = match iter.next() {
             Some((_, x)) => x,
-            None => return Err(Error::end_of_input()),
+            None => return Err(LexError::EndOfInput),
         };
 
         match cur_char {
@@ -171,7 +347,7 @@ impl<'a> TokenStream<'a> {
                 self.recursion_limit = self
                     .recursion_limit
                     .checked_sub(1)
-                    .ok_or_else(|| Error::message_static_message("Recursion limit exceeded"))?;
+                    .ok_or(LexError::Limit("Recursion limit exceeded"))?;
 
                 self.advance_token(Punctuator, 1)
             }
@@ -194,14 +370,14 @@ impl<'a> TokenStream<'a> {
                 if iter.as_str().starts_with("..") {
                     self.advance_token(Punctuator, 3)
                 } else {
-                    Err(Error::Unexpected(Info::Owned(
-                        format_args!(
+                    Err(LexError::Unexpected(
+                        format!(
                             "bare dot {:?} is not supported, \
                             only \"...\"",
                             cur_char
                         )
-                        .to_string(),
-                    )))
+                        .into(),
+                    ))
                 }
             }
             '_' | 'a'..='z' | 'A'..='Z' => {
@@ -238,9 +414,9 @@ impl<'a> TokenStream<'a> {
                 if exponent.is_some() || real.is_some() {
                     let value = &self.buf[self.off..][..len];
                     if !check_float(value, exponent, real) {
-                        return Err(Error::Unexpected(Info::Owned(
-                            format_args!("unsupported float {:?}", value).to_string(),
-                        )));
+                        return Err(LexError::Unexpected(
+                            format!("unsupported float {:?}", value).into(),
+                        ));
                     }
                     self.position.column += len;
                     self.off += len;
@@ -249,9 +425,9 @@ impl<'a> TokenStream<'a> {
                 } else {
                     let value = &self.buf[self.off..][..len];
                     if !check_int(value) {
-                        return Err(Error::Unexpected(Info::Owned(
-                            format_args!("unsupported integer {:?}", value).to_string(),
-                        )));
+                        return Err(LexError::Unexpected(
+                            format!("unsupported integer {:?}", value).into(),
+                        ));
                     }
                     self.advance_token(IntValue, len)
                 }
@@ -261,47 +437,64 @@ impl<'a> TokenStream<'a> {
                     let tail = &iter.as_str()[2..];
                     for (end_idx, _) in tail.match_indices("\"\"\"") {
                         if !tail[..end_idx].ends_with('\\') {
+                            self.check_string_length(end_idx)?;
                             self.update_position(end_idx + 6);
                             return Ok((BlockString, end_idx + 6));
                         }
                     }
 
-                    Err(Error::Unexpected(Info::Owned(
-                        "unterminated block string value".to_string(),
-                    )))
+                    Err(LexError::Unexpected(
+                        "unterminated block string value".into(),
+                    ))
                 } else {
-                    let mut nchars = 1;
-                    let mut escaped = false;
-                    for (idx, cur_char) in iter {
-                        nchars += 1;
-                        match cur_char {
-                            '"' if escaped => {}
-                            '"' => {
-                                self.position.column += nchars;
-                                self.off += idx + 1;
-                                return Ok((StringValue, idx + 1));
-                            }
-                            '\n' => {
-                                return Err(Error::Unexpected(Info::Owned(
-                                    "unterminated string value".to_string(),
-                                )));
-                            }
-
-                            _ => {}
-                        }
+                    self.take_string('"')
+                }
+            }
+            '\'' if self.single_quoted_strings => {
+                let position = self.position;
+                // A token may be scanned again after backtracking
+                if !self.warnings.iter().any(|w| w.position == position) {
+                    self.warnings.push(ParseWarning {
+                        position,
+                        message: "single-quoted string, GraphQL requires double quotes".into(),
+                    });
+                }
+                self.take_string('\'')
+            }
+            '\'' => Err(LexError::Unexpected(
+                "single-quoted string, GraphQL strings use double quotes".into(),
+            )),
+            _ => Err(LexError::Unexpected(
+                format!("unexpected character {:?}", cur_char).into(),
+            )),
+        }
+    }
 
-                        // if we aren't escaped and the current char is a \, we are now escaped
-                        escaped = !escaped && cur_char == '\\';
-                    }
-                    Err(Error::Unexpected(Info::Owned(
-                        "unterminated string value".to_string(),
-                    )))
+    /// Scans a single-line string delimited by `quote`
+    fn take_string(&mut self, quote: char) -> Result<(Kind, usize), LexError> {
+        let mut nchars = 1;
+        let mut escaped = false;
+        for (idx, cur_char) in self.buf[self.off..].char_indices().skip(1) {
+            nchars += 1;
+            match cur_char {
+                c if c == quote && escaped => {}
+                c if c == quote => {
+                    self.check_string_length(idx - 1)?;
+                    self.position.column += nchars;
+                    self.off += idx + 1;
+                    return Ok((Kind::StringValue, idx + 1));
+                }
+                '\n' => {
+                    return Err(LexError::Unexpected("unterminated string value".into()));
                 }
+
+                _ => {}
             }
-            _ => Err(Error::Unexpected(Info::Owned(
-                format_args!("unexpected character {:?}", cur_char).to_string(),
-            ))),
+
+            // if we aren't escaped and the current char is a \, we are now escaped
+            escaped = !escaped && cur_char == '\\';
         }
+        Err(LexError::Unexpected("unterminated string value".into()))
     }
 
     fn skip_whitespace(&mut self) {
@@ -325,20 +518,36 @@ impl<'a> TokenStream<'a> {
                 }
                 //comment
                 '#' => {
-                    for (_, cur_char) in iter.by_ref() {
+                    let position = Pos {
+                        offset: self.off + idx,
+                        ..self.position
+                    };
+                    let mut end = self.buf.len() - self.off;
+                    for (idx, cur_char) in iter.by_ref() {
                         // TODO(tailhook) ensure SourceCharacter
                         if cur_char == '\r' || cur_char == '\n' {
                             self.position.column = 1;
                             self.position.line += 1;
+                            end = idx;
                             break;
                         }
                     }
+                    if let Some(ref mut comments) = self.comments {
+                        // Backtracking may skip the same comment again
+                        if comments.last().map(|c| c.position) < Some(position) {
+                            comments.push(Comment {
+                                position,
+                                text: self.buf[self.off..][idx + 1..end].to_string(),
+                            });
+                        }
+                    }
                     continue;
                 }
                 _ => break idx,
             }
         };
         self.off += idx;
+        self.position.offset = self.off;
     }
 
     fn update_position(&mut self, len: usize) {
@@ -357,6 +566,138 @@ impl<'a> TokenStream<'a> {
     }
 }
 
+/// Kind of a token returned by [`tokenize`]
+///
+/// The same as [`Kind`], plus comments, which the parsers skip.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TokenKind {
+    Punctuator,
+    Name,
+    IntValue,
+    FloatValue,
+    StringValue,
+    BlockString,
+    /// Comment including the `#`
+    Comment,
+}
+
+impl From<Kind> for TokenKind {
+    fn from(kind: Kind) -> TokenKind {
+        match kind {
+            Kind::Punctuator => TokenKind::Punctuator,
+            Kind::Name => TokenKind::Name,
+            Kind::IntValue => TokenKind::IntValue,
+            Kind::FloatValue => TokenKind::FloatValue,
+            Kind::StringValue => TokenKind::StringValue,
+            Kind::BlockString => TokenKind::BlockString,
+        }
+    }
+}
+
+/// Token along with the range of source code it was read from
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct SpannedToken<'a> {
+    pub kind: TokenKind,
+    pub value: &'a str,
+    pub span: Span,
+}
+
+/// Error splitting a document into tokens, see [`tokenize`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenizeError {
+    /// Position of the first character which isn't part of a valid token
+    pub position: Pos,
+    pub message: String,
+}
+
+impl fmt::Display for TokenizeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.position, self.message)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TokenizeError {}
+
+/// Iterator over the tokens of a document, see [`tokenize`]
+#[derive(Debug)]
+pub struct Tokens<'a> {
+    stream: TokenStream<'a>,
+    pending: VecDeque<SpannedToken<'a>>,
+    done: bool,
+}
+
+impl<'a> Tokens<'a> {
+    fn push_comments(&mut self) {
+        for comment in self.stream.drain_comments() {
+            let start = comment.position;
+            let value = &self.stream.buf[start.offset..][..comment.text.len() + 1];
+            let end = Pos {
+                column: start.column + value.chars().count(),
+                offset: start.offset + value.len(),
+                ..start
+            };
+            self.pending.push_back(SpannedToken {
+                kind: TokenKind::Comment,
+                value,
+                span: Span { start, end },
+            });
+        }
+    }
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Result<SpannedToken<'a>, TokenizeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(token) = self.pending.pop_front() {
+            return Some(Ok(token));
+        }
+        if self.done {
+            return None;
+        }
+        let start = self.stream.position;
+        match self.stream.next_token() {
+            Ok(token) => {
+                self.pending.push_back(SpannedToken {
+                    kind: token.kind.into(),
+                    value: token.value,
+                    span: Span {
+                        start,
+                        end: self.stream.end_position(),
+                    },
+                });
+                self.push_comments();
+            }
+            Err(LexError::EndOfInput) => self.done = true,
+            Err(e) => {
+                self.done = true;
+                return Some(Err(TokenizeError {
+                    position: start,
+                    message: e.to_string(),
+                }));
+            }
+        }
+        self.pending.pop_front().map(Ok)
+    }
+}
+
+/// Splits `input` into tokens, including comments
+///
+/// This is the tokenizer used by the parsers, which skip the comments.
+/// Whitespace and commas are skipped. Tokens aren't checked to form a
+/// valid document, but iteration stops at the first character that can't
+/// start a token, or an unterminated string, after yielding an error.
+pub fn tokenize(input: &str) -> Tokens<'_> {
+    let mut tokens = Tokens {
+        stream: TokenStream::with_comments(input),
+        pending: VecDeque::new(),
+        done: false,
+    };
+    tokens.push_comments();
+    tokens
+}
+
 impl<'a> fmt::Display for Token<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}[{:?}]", self.value, self.kind)
@@ -366,7 +707,8 @@ impl<'a> fmt::Display for Token<'a> {
 #[cfg(test)]
 mod test {
     use super::Kind::*;
-    use super::{Kind, TokenStream};
+    use super::{tokenize, Kind, TokenKind, TokenStream};
+    use crate::position::Pos;
     use combine::easy::Error;
 
     use combine::{Positioned, StreamOnce};
@@ -617,4 +959,57 @@ mod test {
         assert_eq!(tok_str(r#""""\"""quote" """"#), [r#""""\"""quote" """"#]);
         assert_eq!(tok_typ(r#""""\"""quote" """"#), [BlockString]);
     }
+
+    #[test]
+    fn tokenize_with_comments() {
+        let source = "# first\nquery {\n  a(x: \"\"\"b\nc\"\"\") # after a\n}\n# last";
+        let tokens = tokenize(source).collect::<Result<Vec<_>, _>>().unwrap();
+        let kinds = tokens
+            .iter()
+            .map(|t| (t.kind, t.value))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [
+                (TokenKind::Comment, "# first"),
+                (TokenKind::Name, "query"),
+                (TokenKind::Punctuator, "{"),
+                (TokenKind::Name, "a"),
+                (TokenKind::Punctuator, "("),
+                (TokenKind::Name, "x"),
+                (TokenKind::Punctuator, ":"),
+                (TokenKind::BlockString, "\"\"\"b\nc\"\"\""),
+                (TokenKind::Punctuator, ")"),
+                (TokenKind::Comment, "# after a"),
+                (TokenKind::Punctuator, "}"),
+                (TokenKind::Comment, "# last"),
+            ]
+        );
+        for token in &tokens {
+            assert_eq!(&source[token.span.start.offset..token.span.end.offset], token.value);
+        }
+        let string = tokens[7].span;
+        assert_eq!((string.start.line, string.start.column), (3, 8));
+        assert_eq!((string.end.line, string.end.column), (4, 5));
+        let comment = tokens[9].span;
+        assert_eq!((comment.start.line, comment.start.column), (4, 7));
+        assert_eq!((comment.end.line, comment.end.column), (4, 16));
+    }
+
+    #[test]
+    fn tokenize_error() {
+        let mut tokens = tokenize("a ? b");
+        assert_eq!(tokens.next().unwrap().unwrap().value, "a");
+        let err = tokens.next().unwrap().unwrap_err();
+        assert_eq!(
+            err.position,
+            Pos {
+                line: 1,
+                column: 3,
+                offset: 2,
+            }
+        );
+        assert_eq!(err.to_string(), "1:3: unexpected character '?'");
+        assert!(tokens.next().is_none());
+    }
 }
@@ -28,3 +28,19 @@ fn invalid_curly_brace() {
 fn bad_args() {
     test_error("bad_args");
 }
+#[test]
+fn multibyte_column() {
+    test_error("multibyte_column");
+}
+#[test]
+fn multibyte_column_same_line() {
+    test_error("multibyte_column_same_line");
+}
+#[test]
+fn fragment_named_on() {
+    test_error("fragment_named_on");
+}
+#[test]
+fn type_system_definition() {
+    test_error("type_system_definition");
+}
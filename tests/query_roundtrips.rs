@@ -6,12 +6,17 @@ extern crate pretty_assertions;
 use std::fs::File;
 use std::io::Read;
 
+use graphql_parser::query::parse_query_with_comments;
 use graphql_parser::{parse_query, Style};
 
 fn roundtrip_multiline_args(filename: &str) {
     roundtrip(filename, Style::default().multiline_arguments(true))
 }
 
+fn roundtrip_expand_all(filename: &str) {
+    roundtrip(filename, Style::default().expand_all(true))
+}
+
 fn roundtrip_default(filename: &str) {
     roundtrip(filename, &Style::default())
 }
@@ -25,6 +30,15 @@ fn roundtrip(filename: &str, style: &Style) {
     assert_eq!(ast.format(style), buf);
 }
 
+fn roundtrip_comments(filename: &str) {
+    let mut buf = String::with_capacity(1024);
+    let path = format!("tests/queries/{}.graphql", filename);
+    let mut f = File::open(path).unwrap();
+    f.read_to_string(&mut buf).unwrap();
+    let (ast, comments) = parse_query_with_comments::<&str>(&buf).unwrap();
+    assert_eq!(ast.format_with_comments(&Style::default(), &comments), buf);
+}
+
 fn roundtrip2(filename: &str) {
     let mut buf = String::with_capacity(1024);
     let source = format!("tests/queries/{}.graphql", filename);
@@ -171,3 +185,40 @@ fn directive_args_multiline() {
 fn kitchen_sink() {
     roundtrip2("kitchen-sink");
 }
+#[test]
+fn number_literals() {
+    roundtrip2("number_literals");
+    roundtrip_default("number_literals_canonical");
+}
+#[test]
+fn expand_all() {
+    roundtrip_expand_all("expand_all");
+}
+#[test]
+fn block_string_arguments() {
+    roundtrip_default("block_string_arguments");
+}
+#[test]
+fn source_order() {
+    roundtrip_default("source_order");
+}
+#[test]
+fn query_var_default_null() {
+    roundtrip_default("query_var_default_null");
+}
+#[test]
+fn comments() {
+    roundtrip_comments("comments");
+}
+#[test]
+fn indent_tabs() {
+    roundtrip("indent_tabs", Style::default().tabs(true));
+}
+#[test]
+fn indent_four_spaces() {
+    roundtrip("indent_four_spaces", Style::default().indent(4));
+}
+#[test]
+fn max_width() {
+    roundtrip("max_width", Style::default().max_width(Some(40)));
+}
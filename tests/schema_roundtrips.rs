@@ -6,7 +6,8 @@ extern crate pretty_assertions;
 use std::fs::File;
 use std::io::Read;
 
-use graphql_parser::parse_schema;
+use graphql_parser::schema::format_schema_streaming;
+use graphql_parser::{parse_schema, Preset, Style};
 
 fn roundtrip(filename: &str) {
     let mut buf = String::with_capacity(1024);
@@ -17,6 +18,29 @@ fn roundtrip(filename: &str) {
     assert_eq!(ast.to_string(), buf);
 }
 
+fn roundtrip_expand_all(filename: &str) {
+    let mut buf = String::with_capacity(1024);
+    let path = format!("tests/schemas/{}.graphql", filename);
+    let mut f = File::open(path).unwrap();
+    f.read_to_string(&mut buf).unwrap();
+    let ast = parse_schema::<String>(&buf).unwrap().to_owned();
+    assert_eq!(ast.format(Style::default().expand_all(true)), buf);
+}
+
+fn format_preset(filename: &str, preset: Preset, suffix: &str) {
+    let mut buf = String::with_capacity(1024);
+    let source = format!("tests/schemas/{}.graphql", filename);
+    let target = format!("tests/schemas/{}_{}.graphql", filename, suffix);
+    let mut f = File::open(source).unwrap();
+    f.read_to_string(&mut buf).unwrap();
+    let ast = parse_schema::<String>(&buf).unwrap();
+
+    let mut buf = String::with_capacity(1024);
+    let mut f = File::open(target).unwrap();
+    f.read_to_string(&mut buf).unwrap();
+    assert_eq!(ast.format(&Style::preset(preset)), buf);
+}
+
 fn roundtrip2(filename: &str) {
     let mut buf = String::with_capacity(1024);
     let source = format!("tests/schemas/{}.graphql", filename);
@@ -116,6 +140,10 @@ fn directive_descriptions() {
     roundtrip2("directive_descriptions");
 }
 #[test]
+fn multiline_descriptions() {
+    roundtrip2("multiline_descriptions");
+}
+#[test]
 fn directive_variable_definition() {
     roundtrip("directive_variable_definition");
 }
@@ -123,3 +151,41 @@ fn directive_variable_definition() {
 fn repeatable() {
     roundtrip("repeatable")
 }
+#[test]
+fn schema_operation_order() {
+    roundtrip("schema_operation_order")
+}
+#[test]
+fn expand_all() {
+    roundtrip_expand_all("expand_all")
+}
+#[test]
+fn block_string_arguments() {
+    roundtrip("block_string_arguments")
+}
+#[test]
+fn streaming() {
+    let mut buf = String::with_capacity(1024);
+    let mut f = File::open("tests/schemas/kitchen-sink.graphql").unwrap();
+    f.read_to_string(&mut buf).unwrap();
+    let ast = parse_schema::<&str>(&buf).unwrap();
+    let mut out = Vec::new();
+    format_schema_streaming(&ast, &mut out, &Style::default()).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), ast.to_string());
+}
+#[test]
+fn bodyless_types() {
+    roundtrip("bodyless_types");
+}
+#[test]
+fn null_defaults() {
+    roundtrip("null_defaults");
+}
+#[test]
+fn presets() {
+    roundtrip("presets");
+    format_preset("presets", Preset::GraphqlJs, "graphql_js");
+    format_preset("presets", Preset::Relay, "graphql_js");
+    format_preset("presets", Preset::ApolloFederation, "graphql_js");
+    format_preset("presets", Preset::Minimal, "minimal");
+}
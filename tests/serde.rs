@@ -0,0 +1,82 @@
+#![cfg(feature = "serde")]
+extern crate graphql_parser;
+#[cfg(test)]
+#[macro_use]
+extern crate pretty_assertions;
+
+use std::fs::File;
+use std::io::Read;
+
+use graphql_parser::{parse_query, parse_schema, query, schema, Pos};
+
+fn read(path: &str) -> String {
+    let mut buf = String::with_capacity(1024);
+    let mut f = File::open(path).unwrap();
+    f.read_to_string(&mut buf).unwrap();
+    buf
+}
+
+#[test]
+fn query_roundtrip() {
+    let buf = read("tests/queries/kitchen-sink.graphql");
+    let ast = parse_query::<String>(&buf).unwrap().into_static();
+    let json = serde_json::to_string(&ast).unwrap();
+    let back: query::Document<'static, String> = serde_json::from_str(&json).unwrap();
+    assert_eq!(back, ast);
+}
+
+#[test]
+fn schema_roundtrip() {
+    let buf = read("tests/schemas/kitchen-sink.graphql");
+    let ast = parse_schema::<String>(&buf).unwrap().into_static();
+    let json = serde_json::to_string(&ast).unwrap();
+    let back: schema::Document<'static, String> = serde_json::from_str(&json).unwrap();
+    assert_eq!(back, ast);
+}
+
+#[test]
+fn selection_tree_roundtrip() {
+    let buf = read("tests/queries/kitchen-sink.graphql");
+    let ast = parse_query::<String>(&buf).unwrap().into_static();
+    let op = ast
+        .definitions
+        .iter()
+        .find_map(|def| match def {
+            query::Definition::Operation(op) => Some(op),
+            _ => None,
+        })
+        .unwrap();
+    let tree = query::to_selection_tree(op, &ast.fragments());
+    assert!(!tree.is_empty());
+    let json = serde_json::to_string(&tree).unwrap();
+    let back: Vec<query::SelectionTree<'static, String>> = serde_json::from_str(&json).unwrap();
+    assert_eq!(back, tree);
+}
+
+#[test]
+fn position() {
+    let pos = Pos {
+        line: 2,
+        column: 3,
+        offset: 10,
+    };
+    assert_eq!(
+        serde_json::to_string(&pos).unwrap(),
+        r#"{"line":2,"column":3,"offset":10}"#
+    );
+    let back: Pos = serde_json::from_str(r#"{"line":2,"column":3,"offset":10}"#).unwrap();
+    assert_eq!(back, pos);
+}
+
+#[test]
+fn position_without_offset() {
+    let pos: Pos = serde_json::from_str(r#"{"line":1,"column":2}"#).unwrap();
+    assert_eq!(
+        pos,
+        Pos {
+            line: 1,
+            column: 2,
+            offset: 0,
+        }
+    );
+}